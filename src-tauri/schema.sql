@@ -49,6 +49,7 @@ CREATE TABLE IF NOT EXISTS tasks (
   priority INTEGER DEFAULT 0,
   due_date INTEGER,
   description TEXT,
+  position REAL NOT NULL DEFAULT 0, -- ordering within (node_id, status)
 
   created_at INTEGER NOT NULL,
   updated_at INTEGER NOT NULL,
@@ -96,6 +97,39 @@ CREATE TABLE IF NOT EXISTS notes (
   id TEXT PRIMARY KEY,
   title TEXT NOT NULL,
   content_path TEXT NOT NULL,
+  encrypted INTEGER NOT NULL DEFAULT 0, -- 1: 文件以口令加密保存
+
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS note_tags (
+  note_id TEXT NOT NULL,
+  tag TEXT NOT NULL,
+
+  PRIMARY KEY (note_id, tag),
+  FOREIGN KEY (note_id) REFERENCES notes(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_tags_tag
+ON note_tags(tag);
+
+CREATE TABLE IF NOT EXISTS note_aliases (
+  note_id TEXT NOT NULL,
+  alias TEXT NOT NULL,
+
+  PRIMARY KEY (note_id, alias),
+  FOREIGN KEY (note_id) REFERENCES notes(id)
+);
+
+-- -----------------------------------------------------
+-- 4.1 Note Templates
+--   content 支持 {{title}} / {{project}} / {{date}} / {{time}} / {{datetime}} / {{cursor}}
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS note_templates (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  content TEXT NOT NULL,
 
   created_at INTEGER NOT NULL,
   updated_at INTEGER NOT NULL
@@ -108,12 +142,45 @@ CREATE TABLE IF NOT EXISTS snippets (
   id TEXT PRIMARY KEY,
   title TEXT NOT NULL,
   language TEXT,
+  language_detected INTEGER NOT NULL DEFAULT 0,  -- 1: language 由自动识别得出
+  language_confident INTEGER NOT NULL DEFAULT 0, -- 1: 用户指定或识别结果可信
   content TEXT NOT NULL,
+  use_count INTEGER NOT NULL DEFAULT 0, -- 模板渲染次数
+  last_used_at INTEGER,
+  prefix TEXT,       -- 编辑器触发前缀，多个时以换行分隔
+  description TEXT,
+  scope TEXT,        -- VS Code scope 原文（逗号分隔的语言 id）
 
   created_at INTEGER NOT NULL,
   updated_at INTEGER NOT NULL
 );
 
+-- -----------------------------------------------------
+-- 5.1 Snippet Files (multi-file snippets)
+--   第一个文件（order_index 最小）为主文件，
+--   其内容 / 语言同步到 snippets.content / snippets.language
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS snippet_files (
+  id TEXT PRIMARY KEY,
+  snippet_id TEXT NOT NULL,
+
+  file_name TEXT NOT NULL,
+  language TEXT,
+  language_detected INTEGER NOT NULL DEFAULT 0,
+  language_confident INTEGER NOT NULL DEFAULT 0,
+  content TEXT NOT NULL,
+  order_index INTEGER NOT NULL DEFAULT 0,
+
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL,
+
+  UNIQUE (snippet_id, file_name),
+  FOREIGN KEY (snippet_id) REFERENCES snippets(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_snippet_files_snippet_order
+ON snippet_files(snippet_id, order_index);
+
 -- -----------------------------------------------------
 -- 6. Node Resources (attachments / references)
 -- -----------------------------------------------------
@@ -134,6 +201,102 @@ ON node_resources(node_id);
 CREATE INDEX IF NOT EXISTS idx_node_resources_resource
 ON node_resources(resource_id, resource_type);
 
+-- -----------------------------------------------------
+-- 7. Daily Notes (journal)
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS daily_notes (
+  date TEXT PRIMARY KEY,         -- 'YYYY-MM-DD'
+  note_id TEXT NOT NULL,
+
+  created_at INTEGER NOT NULL,
+
+  FOREIGN KEY (note_id) REFERENCES notes(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_daily_notes_note
+ON daily_notes(note_id);
+
+-- -----------------------------------------------------
+-- 8. Settings (key / value)
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS settings (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL,
+
+  updated_at INTEGER NOT NULL
+);
+
+-- -----------------------------------------------------
+-- 9. Sync (change exchange via folder / WebDAV)
+--   sync_log：本机待导出的修改（由触发器写入，仅在启用同步时记录）
+--   sync_versions：每条记录当前的版本（设备 + Lamport 时钟）
+--   sync_peers：已合并的其他设备变更进度
+--   sync_files：附件文件上次同步时的内容哈希与远端 ETag
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS sync_log (
+  seq INTEGER PRIMARY KEY AUTOINCREMENT,
+  table_name TEXT NOT NULL,
+  row_key TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sync_versions (
+  table_name TEXT NOT NULL,
+  row_key TEXT NOT NULL,
+  device_id TEXT NOT NULL,
+  clock INTEGER NOT NULL,
+
+  PRIMARY KEY (table_name, row_key)
+);
+
+CREATE TABLE IF NOT EXISTS sync_peers (
+  device_id TEXT PRIMARY KEY,
+  last_clock INTEGER NOT NULL DEFAULT 0,
+  last_synced_at INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS sync_files (
+  path TEXT PRIMARY KEY,
+  local_hash TEXT NOT NULL,
+  remote_etag TEXT,
+  synced_at INTEGER NOT NULL
+);
+
+-- -----------------------------------------------------
+-- 10. Undo / redo change log
+--   undo_actions：可撤销的操作（undone = 1 表示已撤销、可重做）
+--   undo_changes：操作涉及的记录修改前后的内容（JSON，NULL 表示不存在）
+--   undo_files：操作涉及的 note 文件修改前后的内容（NULL 表示不存在）
+-- -----------------------------------------------------
+CREATE TABLE IF NOT EXISTS undo_actions (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  label TEXT NOT NULL,
+  undone INTEGER NOT NULL DEFAULT 0,
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS undo_changes (
+  action_id INTEGER NOT NULL,
+  seq INTEGER NOT NULL,
+  table_name TEXT NOT NULL,
+  row_key TEXT NOT NULL,
+  before_row TEXT,
+  after_row TEXT,
+
+  PRIMARY KEY (action_id, seq),
+  FOREIGN KEY (action_id) REFERENCES undo_actions(id)
+);
+
+CREATE TABLE IF NOT EXISTS undo_files (
+  action_id INTEGER NOT NULL,
+  path TEXT NOT NULL,
+  before_content BLOB,
+  after_content BLOB,
+
+  PRIMARY KEY (action_id, path),
+  FOREIGN KEY (action_id) REFERENCES undo_actions(id)
+);
+
 -- =====================================================
 -- End of schema
 -- =====================================================
@@ -0,0 +1,260 @@
+pub mod routes;
+
+use once_cell::sync::Lazy;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::AppHandle;
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::db::connection::get_connection;
+use crate::db::models::{get_setting, set_setting};
+use crate::service::Error;
+use crate::util::percent_decode;
+
+/// 是否启用本机 HTTP API
+pub const API_ENABLED_KEY: &str = "api.enabled";
+pub const API_PORT_KEY: &str = "api.port";
+/// 访问令牌（每个安装各自生成，不参与同步）
+pub const API_TOKEN_KEY: &str = "api.token";
+pub const DEFAULT_PORT: u16 = 27183;
+/// 请求体上限
+const MAX_BODY_BYTES: u64 = 16 * 1024 * 1024;
+
+struct Running {
+    server: Arc<Server>,
+    port: u16,
+}
+
+static SERVER: Lazy<Mutex<Option<Running>>> = Lazy::new(Default::default);
+
+pub fn is_enabled() -> bool {
+    get_connection()
+        .ok()
+        .and_then(|conn| get_setting(&conn, API_ENABLED_KEY).ok().flatten())
+        .is_some_and(|v| v == "1")
+}
+
+/// 正在监听的端口（未运行时为 None）
+pub fn running_port() -> Option<u16> {
+    SERVER.lock().ok()?.as_ref().map(|r| r.port)
+}
+
+/// 读取访问令牌，不存在时生成并保存
+pub fn token() -> Result<String, String> {
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    if let Some(token) = get_setting(&conn, API_TOKEN_KEY).map_err(|e| e.to_string())? {
+        return Ok(token);
+    }
+    regenerate_token()
+}
+
+/// 生成新的访问令牌，旧令牌立即失效
+pub fn regenerate_token() -> Result<String, String> {
+    let now = chrono::Utc::now().timestamp();
+    let token = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let _lock = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    set_setting(&conn, API_TOKEN_KEY, Some(&token), now).map_err(|e| e.to_string())?;
+    Ok(token)
+}
+
+pub fn port() -> Result<u16, String> {
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    Ok(get_setting(&conn, API_PORT_KEY)
+        .map_err(|e| e.to_string())?
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PORT))
+}
+
+/// 启用 API：保存设置并（重新）启动服务
+pub fn enable(app: AppHandle, port: u16) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    token()?;
+    start(app, port)?;
+
+    let _lock = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    set_setting(&conn, API_PORT_KEY, Some(&port.to_string()), now).map_err(|e| e.to_string())?;
+    set_setting(&conn, API_ENABLED_KEY, Some("1"), now).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 停用 API：停止服务，令牌保留
+pub fn disable() -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    stop();
+
+    let _lock = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    set_setting(&conn, API_ENABLED_KEY, None, now).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 启动时（或解锁加密库后）按设置启动服务；失败不影响应用启动
+pub fn start_if_enabled(app: &AppHandle) {
+    if running_port().is_some() || !is_enabled() {
+        return;
+    }
+    if let Ok(port) = port() {
+        let _ = start(app.clone(), port);
+    }
+}
+
+/// 在 127.0.0.1 上启动服务；已在运行时先停止旧服务
+fn start(app: AppHandle, port: u16) -> Result<(), String> {
+    stop();
+
+    let server = Arc::new(Server::http(("127.0.0.1", port)).map_err(|e| e.to_string())?);
+    let listener = Arc::clone(&server);
+    thread::spawn(move || {
+        // unblock() 后 incoming_requests 结束
+        for request in listener.incoming_requests() {
+            let app = app.clone();
+            thread::spawn(move || handle(&app, request));
+        }
+    });
+
+    let mut running = SERVER.lock().map_err(|e| e.to_string())?;
+    *running = Some(Running { server, port });
+    Ok(())
+}
+
+/// 停止服务（切换库时也会停止，新库按自己的设置重新启动）
+pub fn stop() {
+    let Ok(mut running) = SERVER.lock() else {
+        return;
+    };
+    if let Some(running) = running.take() {
+        running.server.unblock();
+    }
+}
+
+fn handle(app: &AppHandle, mut request: Request) {
+    let (status, body) = match respond(app, &mut request) {
+        Ok(value) => (200, value),
+        Err(e) => (e.status, serde_json::json!({ "error": e.message })),
+    };
+
+    let response = if body.is_null() {
+        Response::from_data(Vec::new()).with_status_code(204)
+    } else {
+        Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("valid header"),
+            )
+    };
+    let _ = request.respond(response);
+}
+
+fn respond(app: &AppHandle, request: &mut Request) -> Result<serde_json::Value, ApiError> {
+    // 1. 校验令牌
+    let expected = token().map_err(ApiError::unavailable)?;
+    let given = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(str::trim);
+    if !given.is_some_and(|given| constant_time_eq(given.as_bytes(), expected.as_bytes())) {
+        return Err(ApiError::new(401, "missing or invalid bearer token"));
+    }
+
+    // 2. 读取参数：查询字符串与 JSON 请求体合并
+    let (path, query) = request
+        .url()
+        .split_once('?')
+        .map(|(p, q)| (p.to_string(), Some(q.to_string())))
+        .unwrap_or_else(|| (request.url().to_string(), None));
+    let mut args = serde_json::Map::new();
+    if let Some(query) = query {
+        for (key, value) in parse_query(&query) {
+            args.insert(key, serde_json::Value::String(value));
+        }
+    }
+    if request.body_length().unwrap_or(0) as u64 > MAX_BODY_BYTES {
+        return Err(ApiError::new(413, "request body too large"));
+    }
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::new(400, e.to_string()))?;
+    if !body.trim().is_empty() {
+        match serde_json::from_str(&body) {
+            Ok(serde_json::Value::Object(fields)) => args.extend(fields),
+            Ok(_) => return Err(ApiError::new(400, "request body must be a JSON object")),
+            Err(e) => return Err(ApiError::new(400, e.to_string())),
+        }
+    }
+
+    // 3. 分发到对应的命令
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    routes::dispatch(app, request.method(), &segments, &routes::Args(args))
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// 数据库不可用（例如加密库尚未解锁）
+    fn unavailable(message: String) -> Self {
+        Self::new(503, message)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, "no such endpoint")
+    }
+}
+
+/// service 错误：记录不存在 404，参数不合法 400，note 未解锁 423，其余 500
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let status = match e {
+            Error::NotFound(_) => 404,
+            Error::Invalid(_) => 400,
+            Error::Locked => 423,
+            Error::Database(_) | Error::Io(_) | Error::Other(_) => 500,
+        };
+        Self::new(status, e.to_string())
+    }
+}
+
+/// 比较令牌，耗时与内容无关
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| percent_decode(&s.replace('+', " "));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
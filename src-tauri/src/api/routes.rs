@@ -0,0 +1,252 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tiny_http::Method;
+
+use super::ApiError;
+use crate::lang::highlight::parse_line_ranges;
+use crate::lang::HighlightOptions;
+use crate::service::time_entries::NewTimeEntry;
+use crate::service::{self, notes, projects, snippets, tasks, time_entries, tree, Error, Storage};
+
+/// 请求参数：查询字符串与 JSON 请求体合并后的字段
+pub struct Args(pub serde_json::Map<String, Value>);
+
+impl Args {
+    /// 按命令参数名取值；缺省视为 null（对应 Option 参数的 None）
+    ///
+    /// 查询字符串的值都是字符串，类型不符时再按 JSON 解析一次（数字、布尔值）
+    fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, ApiError> {
+        let value = self.0.get(name).cloned().unwrap_or(Value::Null);
+        let parsed = match serde_json::from_value(value.clone()) {
+            Err(e) => match &value {
+                Value::String(s) => serde_json::from_str(s).map_err(|_| e),
+                _ => Err(e),
+            },
+            ok => ok,
+        };
+        parsed.map_err(|e| match value {
+            Value::Null => ApiError::new(400, format!("missing argument: {}", name)),
+            _ => ApiError::new(400, format!("invalid argument {}: {}", name, e)),
+        })
+    }
+
+    /// 请求中出现该字段时按 get 取值（显式 null 为 None），未出现时返回 default
+    fn get_or<T: DeserializeOwned>(&self, name: &str, default: T) -> Result<T, ApiError> {
+        if self.0.contains_key(name) {
+            self.get(name)
+        } else {
+            Ok(default)
+        }
+    }
+}
+
+fn reply<T: Serialize>(result: service::Result<T>) -> Result<Value, ApiError> {
+    let value = result?;
+    serde_json::to_value(value).map_err(|e| ApiError::new(500, e.to_string()))
+}
+
+/// 路由表：每个端点调用与 Tauri 命令相同的 service 函数，行为与前端调用一致
+///
+/// 返回 null 的命令以 204 响应
+pub fn dispatch(
+    app: &AppHandle,
+    method: &Method,
+    segments: &[&str],
+    a: &Args,
+) -> Result<Value, ApiError> {
+    let ["v1", segments @ ..] = segments else {
+        return Err(ApiError::not_found());
+    };
+    // 数据库未解锁或库正忙时整体不可用
+    let s = &mut Storage::open().map_err(|e| ApiError::unavailable(e.to_string()))?;
+
+    match (method, segments) {
+        // 树
+        (Method::Get, ["tree"]) => reply(tree::list_tree(
+            s,
+            a.get::<Option<String>>("scope")?.as_deref(),
+        )),
+        (Method::Get, ["nodes"]) => {
+            reply(tree::list(s, a.get::<Option<String>>("scope")?.as_deref()))
+        }
+        (Method::Post, ["nodes"]) => reply(tree::create(
+            s,
+            &a.get::<String>("name")?,
+            &a.get::<String>("node_type")?,
+            &a.get::<String>("scope")?,
+            a.get::<Option<String>>("parent_id")?.as_deref(),
+            a.get("order_index")?,
+        )),
+        (Method::Put, ["nodes", node]) => reply(tree::update(
+            s,
+            node,
+            &a.get::<String>("name")?,
+            a.get::<Option<String>>("parent_id")?.as_deref(),
+            a.get("order_index")?,
+        )),
+        (Method::Delete, ["nodes", node]) => reply(tree::delete(s, node)),
+        (Method::Get, ["projects", node]) => reply(projects::get(s, node)),
+
+        // Notes
+        (Method::Post, ["notes"]) => reply(notes::create(
+            s,
+            &a.get::<String>("title")?,
+            a.get::<Option<String>>("parent_id")?.as_deref(),
+            a.get::<Option<String>>("template_id")?.as_deref(),
+        )),
+        (Method::Get, ["notes", note]) => reply(notes::get(s, note)),
+        (Method::Put, ["notes", note, "title"]) => {
+            reply(notes::update_title(s, note, &a.get::<String>("title")?))
+        }
+        (Method::Put, ["notes", note, "content"]) => {
+            reply(notes::update_content(s, note, &a.get::<String>("content")?))
+        }
+        (Method::Put, ["notes", note, "metadata"]) => reply(notes::update_metadata(
+            s,
+            note,
+            &a.get::<Vec<String>>("tags")?,
+            &a.get::<Vec<String>>("aliases")?,
+        )),
+        (Method::Get, ["notes", note, "outline"]) => reply(notes::outline(s, note)),
+        (Method::Get, ["notes", note, "html"]) => {
+            reply(notes::render_html(s, note, a.get("theme")?))
+        }
+
+        // Snippets
+        (Method::Post, ["snippets"]) => reply(
+            snippets::create(
+                s,
+                &a.get::<String>("title")?,
+                a.get("language")?,
+                a.get("content")?,
+                a.get::<Option<String>>("parent_id")?.as_deref(),
+            )
+            .map(|_| ()),
+        ),
+        (Method::Get, ["snippets", snippet]) => reply(snippets::get(s, snippet)),
+        (Method::Put, ["snippets", snippet]) => reply(snippets::update(
+            s,
+            snippet,
+            &a.get::<String>("title")?,
+            a.get("language")?,
+            a.get("content")?,
+        )),
+        (Method::Put, ["snippets", snippet, "meta"]) => reply(snippets::update_meta(
+            s,
+            snippet,
+            a.get::<Option<String>>("prefix")?.as_deref(),
+            a.get::<Option<String>>("description")?.as_deref(),
+            a.get::<Option<String>>("scope")?.as_deref(),
+        )),
+        (Method::Delete, ["snippets", snippet]) => reply(snippets::delete(s, snippet)),
+        (Method::Get, ["snippets", snippet, "render"]) => {
+            let highlight_lines: Option<String> = a.get("highlight_lines")?;
+            let options = HighlightOptions {
+                theme: a.get("theme")?,
+                line_numbers: a.get::<Option<bool>>("line_numbers")?.unwrap_or(false),
+                highlight_lines: parse_line_ranges(highlight_lines.as_deref().unwrap_or(""))
+                    .map_err(Error::Invalid)?,
+            };
+            let format: Option<String> = a.get("format")?;
+            reply(snippets::render(
+                s,
+                snippet,
+                format.as_deref().unwrap_or("html"),
+                options,
+            ))
+        }
+        (Method::Get, ["snippets", snippet, "variables"]) => reply(snippets::variables(s, snippet)),
+        (Method::Post, ["snippets", snippet, "expand"]) => {
+            let values: Option<HashMap<String, String>> = a.get("values")?;
+            reply(snippets::render_template(
+                s,
+                snippet,
+                &values.unwrap_or_default(),
+                || app.clipboard().read_text().ok(),
+            ))
+        }
+        (Method::Post, ["snippets", snippet, "files"]) => reply(snippets::add_file(
+            s,
+            snippet,
+            a.get("file_name")?,
+            a.get("language")?,
+            a.get("content")?,
+        )),
+        (Method::Put, ["snippets", snippet, "files", "order"]) => reply(snippets::reorder_files(
+            s,
+            snippet,
+            &a.get::<Vec<String>>("file_ids")?,
+        )),
+        (Method::Put, ["snippet-files", file]) => reply(snippets::update_file(
+            s,
+            file,
+            a.get("language")?,
+            a.get("content")?,
+        )),
+        (Method::Put, ["snippet-files", file, "name"]) => {
+            reply(snippets::rename_file(s, file, a.get("file_name")?))
+        }
+        (Method::Delete, ["snippet-files", file]) => reply(snippets::delete_file(s, file)),
+
+        // 任务
+        (Method::Get, ["projects", node, "tasks"]) => reply(tasks::list(s, node)),
+        (Method::Post, ["projects", node, "tasks"]) => reply(tasks::create(
+            s,
+            node,
+            &a.get::<String>("title")?,
+            a.get::<Option<String>>("status")?.as_deref(),
+            a.get("priority")?,
+            a.get("due_date")?,
+            a.get::<Option<String>>("description")?.as_deref(),
+        )),
+        (Method::Get, ["tasks", task]) => reply(tasks::get(s, task)),
+        (Method::Patch, ["tasks", task]) => {
+            // 只修改请求中出现的字段；due_date / description 显式传 null 时清空
+            let current = tasks::get(s, task)?;
+            reply(tasks::update(
+                s,
+                task,
+                a.get::<Option<String>>("title")?.as_deref(),
+                a.get::<Option<String>>("status")?.as_deref(),
+                a.get("priority")?,
+                a.get_or("due_date", current.due_date)?,
+                a.get_or("description", current.description)?.as_deref(),
+            ))
+        }
+        (Method::Delete, ["tasks", task]) => reply(tasks::delete(s, task)),
+        (Method::Post, ["tasks", task, "move"]) => reply(tasks::move_to(
+            s,
+            task,
+            &a.get::<String>("status")?,
+            a.get::<Option<String>>("before_task_id")?.as_deref(),
+        )),
+
+        // 工时
+        (Method::Get, ["tasks", task, "time-entries"]) => reply(time_entries::list(s, task)),
+        (Method::Post, ["tasks", task, "time-entries"]) => reply(time_entries::create(
+            s,
+            task,
+            &NewTimeEntry {
+                work_date: a.get("work_date")?,
+                duration: a.get("duration")?,
+                description: &a.get::<String>("description")?,
+                start_time: a.get("start_time")?,
+                end_time: a.get("end_time")?,
+                source: a.get::<Option<String>>("source")?.as_deref(),
+            },
+        )),
+        (Method::Put, ["time-entries", entry]) => reply(time_entries::update(
+            s,
+            entry,
+            a.get("duration")?,
+            &a.get::<String>("description")?,
+        )),
+        (Method::Delete, ["time-entries", entry]) => reply(time_entries::delete(s, entry)),
+
+        _ => Err(ApiError::not_found()),
+    }
+}
@@ -1,25 +1,110 @@
-use once_cell::sync::OnceCell;
 use std::path::PathBuf;
+use std::sync::RwLock;
 use tauri::{AppHandle, Manager};
 
-static DB_PATH: OnceCell<PathBuf> = OnceCell::new();
+use crate::location::{self, Source};
 
-pub fn init(app: &AppHandle) -> anyhow::Result<()> {
-    // 1️⃣ 初始化目录
-    crate::fs::init_dirs(app)?;
-    // 2️⃣ 保存 db_path
-    let db_path = app.path().app_data_dir()?.join("db.sqlite");
+/// 应用标识，与 tauri.conf.json 的 identifier 一致
+pub const APP_IDENTIFIER: &str = "com.sevennorth.noto";
+
+/// 当前打开的库；切换库时整体替换
+struct Paths {
+    data_dir: PathBuf,
+    db_path: PathBuf,
+    source: Source,
+}
 
-    DB_PATH
-        .set(db_path)
-        .map_err(|_| anyhow::anyhow!("DB_PATH already initialized"))?;
+static PATHS: RwLock<Option<Paths>> = RwLock::new(None);
 
-    // 3️⃣ 初始化数据库
+pub fn init(app: &AppHandle) -> anyhow::Result<()> {
+    // 1️⃣ 确定数据目录（命令行 / 环境变量 / 便携模式 / 位置设置），并创建子目录
+    let (data_dir, source) =
+        location::resolve(location::flag_from_args(), app.path().app_data_dir()?);
+    crate::fs::create_dirs(&data_dir)?;
+    // 2️⃣ 保存数据目录与 db_path
+    set_paths(data_dir, source)?;
+
+    // 3️⃣ 初始化数据库（加密库需等前端输入密钥，由 unlock_vault 完成初始化）
+    if crate::db::vault::is_locked(&db_path()) {
+        return Ok(());
+    }
     crate::db::init_db()?;
 
+    // 4️⃣ 启用版本管理时，提交应用关闭期间对 notes/ 的修改（失败不影响启动）
+    if crate::history::is_enabled() {
+        let _ = crate::history::flush();
+    }
+
+    // 5️⃣ 启用了本机 HTTP API 时启动服务
+    crate::api::start_if_enabled(app);
+
+    Ok(())
+}
+
+/// 不经 Tauri 初始化（命令行工具），按与应用相同的规则确定数据目录
+///
+/// 加密库在此不解锁，由调用方设置密钥后再访问数据库
+pub fn init_standalone(data_dir_flag: Option<PathBuf>) -> anyhow::Result<()> {
+    let default =
+        default_data_dir().ok_or_else(|| anyhow::anyhow!("cannot determine the data directory"))?;
+    let (data_dir, source) = location::resolve(data_dir_flag, default);
+    crate::fs::create_dirs(&data_dir)?;
+    set_paths(data_dir, source)?;
+
+    if crate::db::vault::is_locked(&db_path()) {
+        return Ok(());
+    }
+    let conn = crate::db::connection::get_connection()?;
+    crate::db::migrate::run(&conn)
+}
+
+/// 与 Tauri 的 app_data_dir 相同：系统数据目录下以应用标识命名的目录
+pub fn default_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+fn set_paths(data_dir: PathBuf, source: Source) -> anyhow::Result<()> {
+    let mut paths = PATHS
+        .write()
+        .map_err(|_| anyhow::anyhow!("paths lock poisoned"))?;
+    if paths.is_some() {
+        anyhow::bail!("data directory already initialized");
+    }
+    *paths = Some(Paths {
+        db_path: data_dir.join("db.sqlite"),
+        data_dir,
+        source,
+    });
+    Ok(())
+}
+
+/// 运行中切换到另一个数据目录（由调用方负责收尾旧库的状态）
+pub fn switch_paths(data_dir: PathBuf, source: Source) -> anyhow::Result<()> {
+    let mut paths = PATHS
+        .write()
+        .map_err(|_| anyhow::anyhow!("paths lock poisoned"))?;
+    *paths = Some(Paths {
+        db_path: data_dir.join("db.sqlite"),
+        data_dir,
+        source,
+    });
     Ok(())
 }
 
-pub fn db_path() -> &'static PathBuf {
-    DB_PATH.get().expect("DB_PATH not initialized")
+fn with_paths<T>(f: impl FnOnce(&Paths) -> T) -> T {
+    let paths = PATHS.read().expect("paths lock poisoned");
+    f(paths.as_ref().expect("data directory not initialized"))
+}
+
+pub fn data_dir() -> PathBuf {
+    with_paths(|p| p.data_dir.clone())
+}
+
+pub fn db_path() -> PathBuf {
+    with_paths(|p| p.db_path.clone())
+}
+
+/// 当前数据目录的来源
+pub fn data_dir_source() -> Source {
+    with_paths(|p| p.source)
 }
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    noto_lib::run_cli()
+}
@@ -0,0 +1,633 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::db::models::{get_note_aliases, get_note_tags, set_note_aliases, set_note_tags};
+use crate::history::{self, ChangeKind};
+use crate::sync::records::{self, key_expr};
+
+/// 记录撤销信息的表（均为参与同步的表，复用其主键定义）
+const TRACKED_TABLES: &[&str] = &[
+    "tree_nodes",
+    "notes",
+    "tasks",
+    "time_entries",
+    "snippets",
+    "snippet_files",
+    "node_resources",
+    "daily_notes",
+];
+/// 按 note 整体记录的列表子表：(表名, 值列)，记录内容为排序后的 JSON 数组
+const NOTE_LISTS: &[(&str, &str)] = &[("note_tags", "tag"), ("note_aliases", "alias")];
+
+/// 最多保留的操作数
+const MAX_ACTIONS: i64 = 200;
+/// 超过该时长的操作不再可撤销
+const MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+/// 窗口内对同一批记录的同名操作合并为一次（例如连续编辑 note 内容）
+const MERGE_WINDOW_SECS: i64 = 60;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UndoAction {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+struct RowChange {
+    table: String,
+    key: String,
+    /// 修改前后的记录（JSON）；None 表示不存在
+    before: Option<String>,
+    after: Option<String>,
+}
+
+struct FileChange {
+    path: String,
+    before: Option<Vec<u8>>,
+    after: Option<Vec<u8>>,
+}
+
+/// 开始记录本连接上的修改
+///
+/// 以 TEMP 触发器在修改前保存记录原样，只对当前连接生效；同步合并、使用统计等
+/// 其他连接上的写入不会进入撤销历史
+pub fn begin(conn: &Connection) -> Result<(), String> {
+    let mut sql = String::from(
+        r#"
+        CREATE TEMP TABLE IF NOT EXISTS undo_pending (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            table_name TEXT NOT NULL,
+            row_key TEXT NOT NULL,
+            before_row TEXT
+        );
+        CREATE TEMP TABLE IF NOT EXISTS undo_pending_files (
+            path TEXT PRIMARY KEY,
+            before_content BLOB
+        );
+        DELETE FROM temp.undo_pending;
+        DELETE FROM temp.undo_pending_files;
+        "#,
+    );
+    let mut add_trigger = |table: &str, event: &str, key: &str, snapshot: &str| {
+        sql.push_str(&format!(
+            r#"
+            CREATE TEMP TRIGGER IF NOT EXISTS undo_{table}_{event_lower}
+            BEFORE {event} ON main.{table}
+            BEGIN
+                INSERT INTO undo_pending (table_name, row_key, before_row)
+                VALUES ('{table}', {key}, {snapshot});
+            END;
+            "#,
+            table = table,
+            event = event,
+            event_lower = event.to_lowercase(),
+            key = key,
+            snapshot = snapshot,
+        ));
+    };
+
+    for name in TRACKED_TABLES {
+        let table = records::find_table(name).ok_or_else(|| format!("unknown table: {}", name))?;
+        let fields = records::table_columns(conn, name)?
+            .iter()
+            .map(|c| format!("'{0}', \"{0}\"", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        // 插入时同样按主键取一次：INSERT OR REPLACE / upsert 覆盖已有记录
+        for (event, row) in [("INSERT", "NEW"), ("UPDATE", "OLD"), ("DELETE", "OLD")] {
+            let filter = table
+                .key
+                .iter()
+                .map(|k| format!("{0} = {1}.{0}", k, row))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            let snapshot = format!(
+                "(SELECT json_object({}) FROM main.{} WHERE {})",
+                fields, name, filter
+            );
+            add_trigger(name, event, &key_expr(table, row), &snapshot);
+        }
+    }
+    for (name, column) in NOTE_LISTS {
+        for (event, row) in [("INSERT", "NEW"), ("DELETE", "OLD")] {
+            let snapshot = format!(
+                "(SELECT json_group_array({col}) FROM (SELECT {col} FROM main.{table} WHERE note_id = {row}.note_id ORDER BY {col}))",
+                col = column,
+                table = name,
+                row = row,
+            );
+            add_trigger(name, event, &format!("{}.note_id", row), &snapshot);
+        }
+    }
+
+    conn.execute_batch(&sql).map_err(|e| e.to_string())
+}
+
+/// 保存 note 文件修改前的内容（在写入或删除文件之前调用，同一文件只保存第一次）
+pub fn capture_file(conn: &Connection, path: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO temp.undo_pending_files (path, before_content) VALUES (?, ?)",
+        params![path, fs::read(path).ok()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 把 begin 之后本连接上的修改登记为一个可撤销操作（在事务提交、文件写入完成后调用）
+///
+/// 登记失败不影响已经完成的修改
+pub fn record(conn: &Connection, label: &str) {
+    let _ = try_record(conn, label);
+}
+
+fn try_record(conn: &Connection, label: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+
+    // 1. 每条记录取第一次修改前的原样，与当前状态对比
+    let pending: Vec<(String, String, Option<String>)> = {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT table_name, row_key, before_row FROM temp.undo_pending
+                WHERE seq IN (SELECT MIN(seq) FROM temp.undo_pending GROUP BY table_name, row_key)
+                ORDER BY seq
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    let mut changes = Vec::new();
+    for (table, key, before) in pending {
+        let after = current_row(conn, &table, &key)?;
+        if !same_row(before.as_deref(), after.as_deref()) {
+            changes.push(RowChange {
+                table,
+                key,
+                before,
+                after,
+            });
+        }
+    }
+
+    // 2. note 文件：新建的 note 文件修改前不存在
+    let mut captured: Vec<(String, Option<Vec<u8>>)> = {
+        let mut stmt = conn
+            .prepare("SELECT path, before_content FROM temp.undo_pending_files")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    for change in changes
+        .iter()
+        .filter(|c| c.table == "notes" && c.before.is_none())
+    {
+        if let Some(path) = row_field(change.after.as_deref(), "content_path") {
+            if !captured.iter().any(|(p, _)| p == &path) {
+                captured.push((path, None));
+            }
+        }
+    }
+    let files: Vec<FileChange> = captured
+        .into_iter()
+        .filter_map(|(path, before)| {
+            let after = fs::read(&path).ok();
+            (before != after).then_some(FileChange {
+                path,
+                before,
+                after,
+            })
+        })
+        .collect();
+
+    conn.execute_batch("DELETE FROM temp.undo_pending; DELETE FROM temp.undo_pending_files;")
+        .map_err(|e| e.to_string())?;
+    if changes.is_empty() && files.is_empty() {
+        return Ok(());
+    }
+
+    // 3. 登记操作：新操作之后不能再重做已撤销的操作
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    discard_actions(&tx, "undone = 1")?;
+
+    match mergeable_action(&tx, label, &changes, &files, now)? {
+        Some(action_id) => {
+            for change in &changes {
+                tx.execute(
+                    "UPDATE undo_changes SET after_row = ? WHERE action_id = ? AND table_name = ? AND row_key = ?",
+                    params![change.after, action_id, change.table, change.key],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            for file in &files {
+                tx.execute(
+                    "UPDATE undo_files SET after_content = ? WHERE action_id = ? AND path = ?",
+                    params![file.after, action_id, file.path],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            tx.execute(
+                "UPDATE undo_actions SET updated_at = ? WHERE id = ?",
+                params![now, action_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO undo_actions (label, created_at, updated_at) VALUES (?, ?, ?)",
+                params![label, now, now],
+            )
+            .map_err(|e| e.to_string())?;
+            let action_id = tx.last_insert_rowid();
+            for (seq, change) in changes.iter().enumerate() {
+                tx.execute(
+                    r#"
+                    INSERT INTO undo_changes (action_id, seq, table_name, row_key, before_row, after_row)
+                    VALUES (?, ?, ?, ?, ?, ?)
+                    "#,
+                    params![
+                        action_id,
+                        seq as i64,
+                        change.table,
+                        change.key,
+                        change.before,
+                        change.after
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            for file in &files {
+                tx.execute(
+                    "INSERT INTO undo_files (action_id, path, before_content, after_content) VALUES (?, ?, ?, ?)",
+                    params![action_id, file.path, file.before, file.after],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    prune(&tx, now)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 最近一次操作与本次同名、在合并窗口内且已包含本次涉及的全部记录与文件时，返回其 id
+fn mergeable_action(
+    tx: &Transaction,
+    label: &str,
+    changes: &[RowChange],
+    files: &[FileChange],
+    now: i64,
+) -> Result<Option<i64>, String> {
+    let latest: Option<(i64, String, i64)> = tx
+        .query_row(
+            "SELECT id, label, updated_at FROM undo_actions ORDER BY id DESC LIMIT 1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((action_id, latest_label, updated_at)) = latest else {
+        return Ok(None);
+    };
+    if latest_label != label || updated_at < now - MERGE_WINDOW_SECS {
+        return Ok(None);
+    }
+
+    let (existing_rows, existing_files) = action_targets(tx, action_id)?;
+    let rows: RowTargets = changes
+        .iter()
+        .map(|c| (c.table.clone(), c.key.clone()))
+        .collect();
+    let paths: BTreeSet<String> = files.iter().map(|f| f.path.clone()).collect();
+
+    Ok((rows.is_subset(&existing_rows) && paths.is_subset(&existing_files)).then_some(action_id))
+}
+
+/// 一次操作涉及的行（表名，主键）
+type RowTargets = BTreeSet<(String, String)>;
+
+fn action_targets(
+    conn: &Connection,
+    action_id: i64,
+) -> Result<(RowTargets, BTreeSet<String>), String> {
+    let mut stmt = conn
+        .prepare("SELECT table_name, row_key FROM undo_changes WHERE action_id = ?")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![action_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<BTreeSet<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT path FROM undo_files WHERE action_id = ?")
+        .map_err(|e| e.to_string())?;
+    let paths = stmt
+        .query_map(params![action_id], |r| r.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<BTreeSet<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok((rows, paths))
+}
+
+/// 按数量与时长裁剪撤销历史
+fn prune(conn: &Connection, now: i64) -> Result<(), String> {
+    discard_actions(conn, &format!("updated_at < {}", now - MAX_AGE_SECS))?;
+    discard_actions(
+        conn,
+        &format!(
+            "id NOT IN (SELECT id FROM undo_actions ORDER BY id DESC LIMIT {})",
+            MAX_ACTIONS
+        ),
+    )
+}
+
+/// 删除满足条件的操作及其明细
+fn discard_actions(conn: &Connection, filter: &str) -> Result<(), String> {
+    conn.execute_batch(&format!(
+        r#"
+        DELETE FROM undo_changes WHERE action_id IN (SELECT id FROM undo_actions WHERE {0});
+        DELETE FROM undo_files WHERE action_id IN (SELECT id FROM undo_actions WHERE {0});
+        DELETE FROM undo_actions WHERE {0};
+        "#,
+        filter
+    ))
+    .map_err(|e| e.to_string())
+}
+
+/// 可撤销的最近一次操作
+pub fn next_undo(conn: &Connection) -> Result<Option<UndoAction>, String> {
+    prune(conn, chrono::Utc::now().timestamp())?;
+    find_action(conn, "undone = 0 ORDER BY id DESC")
+}
+
+/// 可重做的操作（最近一次撤销的）
+pub fn next_redo(conn: &Connection) -> Result<Option<UndoAction>, String> {
+    prune(conn, chrono::Utc::now().timestamp())?;
+    find_action(conn, "undone = 1 ORDER BY id ASC")
+}
+
+fn find_action(conn: &Connection, clause: &str) -> Result<Option<UndoAction>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT id, label, created_at, updated_at FROM undo_actions WHERE {} LIMIT 1",
+            clause
+        ),
+        [],
+        |r| {
+            Ok(UndoAction {
+                id: r.get(0)?,
+                label: r.get(1)?,
+                created_at: r.get(2)?,
+                updated_at: r.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 撤销最近一次操作：在一个事务中恢复记录原样，并恢复 note 文件内容
+///
+/// 记录或文件在操作之后又被修改过（例如同步合并、外部编辑）时拒绝撤销，并丢弃该操作
+pub fn undo(conn: &mut Connection) -> Result<Option<UndoAction>, String> {
+    let Some(action) = next_undo(conn)? else {
+        return Ok(None);
+    };
+    apply(conn, &action, true)?;
+    Ok(Some(action))
+}
+
+/// 重做最近一次撤销的操作（同样要求记录与文件仍是撤销后的状态）
+pub fn redo(conn: &mut Connection) -> Result<Option<UndoAction>, String> {
+    let Some(action) = next_redo(conn)? else {
+        return Ok(None);
+    };
+    apply(conn, &action, false)?;
+    Ok(Some(action))
+}
+
+/// 丢弃涉及该文件的全部操作（note 加密或解密后，历史中的内容不再与文件对应，且不应保留明文）
+pub fn forget_file(conn: &Connection, path: &str) -> Result<(), String> {
+    let ids: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT action_id FROM undo_files WHERE path = ?")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![path], |r| r.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?;
+        rows.map(|id| id.map(|id| id.to_string()))
+            .collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())?
+    };
+    if ids.is_empty() {
+        return Ok(());
+    }
+    discard_actions(conn, &format!("id IN ({})", ids.join(", ")))
+}
+
+fn apply(conn: &mut Connection, action: &UndoAction, undo: bool) -> Result<(), String> {
+    let action_id = action.id;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut changes: Vec<RowChange> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT table_name, row_key, before_row, after_row FROM undo_changes WHERE action_id = ? ORDER BY seq",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![action_id], |r| {
+                Ok(RowChange {
+                    table: r.get(0)?,
+                    key: r.get(1)?,
+                    before: r.get(2)?,
+                    after: r.get(3)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+    let files: Vec<FileChange> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT path, before_content, after_content FROM undo_files WHERE action_id = ?",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![action_id], |r| {
+                Ok(FileChange {
+                    path: r.get(0)?,
+                    before: r.get(1)?,
+                    after: r.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    // 当前状态必须仍是撤销时的修改后 / 重做时的修改前状态，否则会覆盖之后的修改
+    let mut unchanged = files.iter().all(|file| {
+        let expected = if undo { &file.after } else { &file.before };
+        fs::read(&file.path).ok() == *expected
+    });
+    for change in &changes {
+        let expected = if undo { &change.after } else { &change.before };
+        let current = current_row(&tx, &change.table, &change.key)?;
+        unchanged &= same_row(current.as_deref(), expected.as_deref());
+    }
+    if !unchanged {
+        drop(tx);
+        discard_actions(conn, &format!("id = {}", action_id))?;
+        return Err(format!(
+            "cannot {} \"{}\": the affected items have been changed since",
+            if undo { "undo" } else { "redo" },
+            action.label
+        ));
+    }
+
+    // 撤销按相反顺序恢复修改前的状态，重做按原顺序恢复修改后的状态
+    if undo {
+        changes.reverse();
+    }
+    for change in &changes {
+        let target = if undo { &change.before } else { &change.after };
+        apply_row(&tx, &change.table, &change.key, target.as_deref())?;
+    }
+
+    // 文件先写入临时文件，事务提交后再替换，避免回滚后文件与数据库不一致
+    let mut staged = Vec::new();
+    let result = files.iter().try_for_each(|file| {
+        let target = if undo { &file.before } else { &file.after };
+        let tmp_path = match target {
+            Some(content) => {
+                if let Some(parent) = Path::new(&file.path).parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                let tmp_path = format!("{}.undo.tmp", file.path);
+                fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+                Some(tmp_path)
+            }
+            None => None,
+        };
+        staged.push((file.path.as_str(), tmp_path));
+        Ok::<_, String>(())
+    });
+    let result = result.and_then(|_| {
+        tx.execute(
+            "UPDATE undo_actions SET undone = ? WHERE id = ?",
+            params![undo, action_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        for (_, tmp_path) in staged {
+            if let Some(tmp_path) = tmp_path {
+                let _ = fs::remove_file(tmp_path);
+            }
+        }
+        return Err(e);
+    }
+    for (path, tmp_path) in staged {
+        match tmp_path {
+            Some(tmp_path) => fs::rename(&tmp_path, path).map_err(|e| e.to_string())?,
+            None => {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    // 版本历史记录恢复后的 note
+    for change in changes.iter().filter(|c| c.table == "notes") {
+        let (from, to) = if undo {
+            (&change.after, &change.before)
+        } else {
+            (&change.before, &change.after)
+        };
+        let kind = match (from, to) {
+            (_, None) => ChangeKind::Delete,
+            (None, Some(_)) => ChangeKind::Create,
+            _ => ChangeKind::Update,
+        };
+        let title = row_field(from.as_deref().or(to.as_deref()), "title");
+        history::record(kind, &change.key, title.as_deref());
+    }
+
+    Ok(())
+}
+
+/// 把一条记录写回指定状态（None 表示删除）
+fn apply_row(tx: &Transaction, table: &str, key: &str, row: Option<&str>) -> Result<(), String> {
+    if NOTE_LISTS.iter().any(|(name, _)| *name == table) {
+        let items: Vec<String> = match row {
+            Some(json) => serde_json::from_str(json).map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+        return if table == "note_tags" {
+            set_note_tags(tx, key, &items)
+        } else {
+            set_note_aliases(tx, key, &items)
+        }
+        .map_err(|e| e.to_string());
+    }
+
+    let sync_table =
+        records::find_table(table).ok_or_else(|| format!("unknown table: {}", table))?;
+    match row {
+        Some(json) => {
+            let object = serde_json::from_str(json).map_err(|e| e.to_string())?;
+            records::upsert_row(tx, sync_table, &object)
+        }
+        None => records::delete_row(tx, sync_table, key),
+    }
+}
+
+/// 记录当前状态（JSON）；不存在时为 None
+fn current_row(conn: &Connection, table: &str, key: &str) -> Result<Option<String>, String> {
+    let items = match table {
+        "note_tags" => Some(get_note_tags(conn, key)),
+        "note_aliases" => Some(get_note_aliases(conn, key)),
+        _ => None,
+    };
+    if let Some(items) = items {
+        let items = items.map_err(|e| e.to_string())?;
+        return serde_json::to_string(&items)
+            .map(Some)
+            .map_err(|e| e.to_string());
+    }
+
+    let sync_table =
+        records::find_table(table).ok_or_else(|| format!("unknown table: {}", table))?;
+    records::read_row(conn, sync_table, key)?
+        .map(|object| serde_json::to_string(&object).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+fn same_row(a: Option<&str>, b: Option<&str>) -> bool {
+    let parse = |s: Option<&str>| s.and_then(|s| serde_json::from_str::<JsonValue>(s).ok());
+    match (a, b) {
+        (None, None) => true,
+        (Some(_), Some(_)) => parse(a) == parse(b),
+        _ => false,
+    }
+}
+
+fn row_field(row: Option<&str>, field: &str) -> Option<String> {
+    let object: JsonValue = serde_json::from_str(row?).ok()?;
+    object.get(field)?.as_str().map(str::to_string)
+}
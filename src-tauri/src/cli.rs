@@ -0,0 +1,516 @@
+use chrono::{Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use crate::crypto;
+use crate::db::{migrate, vault};
+use crate::service::{notes, search, snippets, tasks, time_entries, Storage};
+
+const USAGE: &str = "\
+usage: noto-cli [--json] [--data-dir <path>] <command>
+
+commands:
+  note new <title> [--parent <folder>] [--template <template id>]
+  note cat <note>
+  note edit <note> [--stdin]
+  task add <title> --project <project> [--priority <n>] [--due <YYYY-MM-DD>] [--status <status>]
+  task list --project <project> [--status <status>]
+  task done <task>
+  log <duration> <description> --task <task> [--date <YYYY-MM-DD>]
+  snippet get <snippet>
+  search <query> [--limit <n>]
+
+<note>, <task>, <project>, <folder> and <snippet> accept an id or an exact title.
+Durations look like 1h30m, 45m, 1.5h or 90 (minutes).
+The vault location follows the app: --data-dir, then NOTO_DATA_DIR, portable mode,
+the location chosen in the app and finally the system data directory.
+Encrypted vaults are unlocked with the NOTO_VAULT_KEY environment variable.";
+
+/// 不带值的选项
+const FLAGS: &[&str] = &["json", "stdin", "help"];
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    json: bool,
+}
+
+impl Args {
+    fn parse(raw: Vec<String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut raw = raw.into_iter();
+        while let Some(arg) = raw.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            if name.is_empty() {
+                positional.extend(raw.by_ref());
+                break;
+            }
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None if FLAGS.contains(&name) => (name.to_string(), String::new()),
+                None => {
+                    let value = raw
+                        .next()
+                        .ok_or_else(|| format!("option --{} needs a value", name))?;
+                    (name.to_string(), value)
+                }
+            };
+            options.insert(name, value);
+        }
+        Ok(Self {
+            json: options.remove("json").is_some(),
+            positional,
+            options,
+        })
+    }
+
+    fn arg(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing <{}>\n\n{}", name, USAGE))
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.option(name)
+            .ok_or_else(|| format!("missing --{}\n\n{}", name, USAGE))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+/// 命令输出：`--json` 时输出 JSON，否则输出文本
+enum Output {
+    None,
+    Text(String),
+    Json(serde_json::Value, String),
+}
+
+impl Output {
+    fn value<T: Serialize>(value: &T, text: String) -> Result<Self, String> {
+        let json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+        Ok(Output::Json(json, text))
+    }
+}
+
+/// 命令行入口：与应用共用数据目录与业务逻辑，返回进程退出码
+pub fn run(raw: Vec<String>) -> ExitCode {
+    let args = match Args::parse(raw) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("noto-cli: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if args.positional.is_empty() || args.flag("help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match open(&args).and_then(|_| dispatch(&args)) {
+        Ok(output) => {
+            match output {
+                Output::None => {}
+                Output::Text(text) => println!("{}", text),
+                Output::Json(json, _) if args.json => println!("{}", json),
+                Output::Json(_, text) => println!("{}", text),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("noto-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 打开与应用相同的数据目录（可用 --data-dir 指定）；加密库用 NOTO_VAULT_KEY 解锁
+fn open(args: &Args) -> Result<(), String> {
+    let data_dir = args.option("data-dir").map(PathBuf::from);
+    crate::app::init_standalone(data_dir).map_err(|e| e.to_string())?;
+
+    let db_path = crate::app::db_path();
+    if vault::is_locked(&db_path) {
+        let key = std::env::var("NOTO_VAULT_KEY")
+            .map_err(|_| "the vault is encrypted: set NOTO_VAULT_KEY".to_string())?;
+        let conn = vault::open_with_key(&db_path, Some(&key)).map_err(|e| e.to_string())?;
+        migrate::run(&conn).map_err(|e| e.to_string())?;
+        vault::set_key(Some(key));
+    }
+    Ok(())
+}
+
+fn dispatch(args: &Args) -> Result<Output, String> {
+    let command: Vec<&str> = args.positional.iter().take(2).map(String::as_str).collect();
+    match command.as_slice() {
+        ["note", "new", ..] => note_new(args),
+        ["note", "cat", ..] => note_cat(args),
+        ["note", "edit", ..] => note_edit(args),
+        ["task", "add", ..] => task_add(args),
+        ["task", "list", ..] => task_list(args),
+        ["task", "done", ..] => task_done(args),
+        ["log", ..] => log_time(args),
+        ["snippet", "get", ..] => snippet_get(args),
+        ["search", ..] => search(args),
+        _ => Err(format!("unknown command\n\n{}", USAGE)),
+    }
+}
+
+fn note_new(args: &Args) -> Result<Output, String> {
+    let title = args.arg(2, "title")?;
+    let mut storage = Storage::open()?;
+    let parent_id = args
+        .option("parent")
+        .map(|p| resolve(&storage.conn, Kind::Folder, p))
+        .transpose()?;
+
+    let created = notes::create(
+        &mut storage,
+        title,
+        parent_id.as_deref(),
+        args.option("template"),
+    )?;
+    flush_history();
+    Output::value(&created, created.note_id.clone())
+}
+
+fn note_cat(args: &Args) -> Result<Output, String> {
+    let mut storage = Storage::open()?;
+    let note = readable_note(&mut storage, args)?;
+    Output::value(&note, note.content.trim_end().to_string())
+}
+
+/// 用 $VISUAL / $EDITOR 编辑（或 `--stdin` 读取新内容）后保存
+///
+/// 编辑期间不持有库锁；保存时在锁内确认文件未被应用修改，否则放弃保存并保留编辑结果
+fn note_edit(args: &Args) -> Result<Output, String> {
+    let note = readable_note(&mut Storage::open()?, args)?;
+
+    let (content, draft) = if args.flag("stdin") {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| e.to_string())?;
+        (content, None)
+    } else {
+        let draft = std::env::temp_dir().join(format!("noto-{}.md", note.id));
+        std::fs::write(&draft, &note.content).map_err(|e| e.to_string())?;
+        run_editor(&draft)?;
+        let content = std::fs::read_to_string(&draft).map_err(|e| e.to_string())?;
+        (content, Some(draft))
+    };
+    if content == note.content {
+        remove_draft(draft);
+        return Ok(Output::Text("no changes".to_string()));
+    }
+
+    let mut storage = Storage::open()?;
+    let current = notes::get(&mut storage, &note.id)?;
+    if current.content != note.content {
+        let kept = draft
+            .map(|d| format!("; your version is in {}", d.display()))
+            .unwrap_or_default();
+        return Err(format!(
+            "the note was changed while you were editing it{}",
+            kept
+        ));
+    }
+
+    notes::update_content(&mut storage, &note.id, &content)?;
+    flush_history();
+    remove_draft(draft);
+    Ok(Output::None)
+}
+
+/// 版本管理的提交在后台线程中延迟执行，进程退出前立即提交
+fn flush_history() {
+    let _ = crate::history::flush();
+}
+
+fn readable_note(storage: &mut Storage, args: &Args) -> Result<notes::NoteDetail, String> {
+    let note_id = resolve(&storage.conn, Kind::Note, args.arg(2, "note")?)?;
+    let note = notes::get(storage, &note_id)?;
+    if note.encrypted || crypto::note::is_encrypted(&note.content) {
+        return Err("the note is encrypted; open it in the app".to_string());
+    }
+    Ok(note)
+}
+
+fn run_editor(path: &Path) -> Result<(), String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // 允许带参数，例如 `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or("EDITOR is empty")?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("failed to start {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+fn remove_draft(draft: Option<PathBuf>) {
+    if let Some(draft) = draft {
+        let _ = std::fs::remove_file(draft);
+    }
+}
+
+fn task_add(args: &Args) -> Result<Output, String> {
+    let title = args.arg(2, "title")?;
+    let mut storage = Storage::open()?;
+    let project_id = resolve(&storage.conn, Kind::Project, args.required("project")?)?;
+
+    let priority = args
+        .option("priority")
+        .map(|p| {
+            p.parse::<i64>()
+                .map_err(|_| format!("invalid priority: {}", p))
+        })
+        .transpose()?;
+    // 与前端一致：截止日期为当地零点的秒级时间戳
+    let due_date = args
+        .option("due")
+        .map(|d| local_midnight(d).map(|t| t / 1000))
+        .transpose()?;
+
+    let task_id = tasks::create(
+        &mut storage,
+        &project_id,
+        title,
+        args.option("status"),
+        priority,
+        due_date,
+        None,
+    )?;
+    Output::value(&task_id, task_id.clone())
+}
+
+fn task_list(args: &Args) -> Result<Output, String> {
+    let storage = Storage::open()?;
+    let project_id = resolve(&storage.conn, Kind::Project, args.required("project")?)?;
+
+    let mut list = tasks::list(&storage, &project_id)?;
+    if let Some(status) = args.option("status") {
+        list.retain(|t| t.status == status);
+    }
+
+    let text = list
+        .iter()
+        .map(|t| {
+            let due = t
+                .due_date
+                .and_then(|d| Local.timestamp_opt(d, 0).single())
+                .map(|d| format!("  due {}", d.format("%Y-%m-%d")))
+                .unwrap_or_default();
+            format!("{:<6} {}{}  ({})", t.status, t.title, due, t.id)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Output::value(&list, text)
+}
+
+/// 与看板拖到「完成」列相同：移到 done 列末尾
+fn task_done(args: &Args) -> Result<Output, String> {
+    let mut storage = Storage::open()?;
+    let task_id = resolve(&storage.conn, Kind::Task, args.arg(2, "task")?)?;
+
+    tasks::move_to(&mut storage, &task_id, "done", None)?;
+    Ok(Output::None)
+}
+
+fn log_time(args: &Args) -> Result<Output, String> {
+    let duration = parse_duration(args.arg(1, "duration")?)?;
+    let description = args.arg(2, "description")?;
+    let mut storage = Storage::open()?;
+    let task_id = resolve(&storage.conn, Kind::Task, args.required("task")?)?;
+
+    // 与前端一致：工作日期为当地零点的毫秒级时间戳
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let work_date = local_midnight(args.option("date").unwrap_or(&today))?;
+
+    let entry_id = time_entries::create(
+        &mut storage,
+        &task_id,
+        &time_entries::NewTimeEntry {
+            work_date,
+            duration,
+            description,
+            start_time: None,
+            end_time: None,
+            source: Some("cli"),
+        },
+    )?;
+    Output::value(&entry_id, entry_id.clone())
+}
+
+fn snippet_get(args: &Args) -> Result<Output, String> {
+    let storage = Storage::open()?;
+    let snippet_id = resolve(&storage.conn, Kind::Snippet, args.arg(2, "snippet")?)?;
+
+    let snippet = snippets::get(&storage, &snippet_id)?;
+    Output::value(&snippet, snippet.content.clone())
+}
+
+/// 按标题与内容搜索 note / 任务 / snippet（不区分大小写；加密 note 只匹配标题）
+fn search(args: &Args) -> Result<Output, String> {
+    let query = args.arg(1, "query")?;
+    let limit = match args.option("limit") {
+        Some(l) => l
+            .parse::<usize>()
+            .map_err(|_| format!("invalid limit: {}", l))?,
+        None => 50,
+    };
+    let storage = Storage::open()?;
+    let hits = search::search(&storage, query, limit)?;
+
+    let text = hits
+        .iter()
+        .map(|h| {
+            let excerpt = h
+                .excerpt
+                .as_ref()
+                .map(|e| format!("\n        {}", e))
+                .unwrap_or_default();
+            format!("{:<7} {}  ({}){}", h.kind, h.title, h.id, excerpt)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Output::value(&hits, text)
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Note,
+    Folder,
+    Project,
+    Task,
+    Snippet,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Note => "note",
+            Kind::Folder => "folder",
+            Kind::Project => "project",
+            Kind::Task => "task",
+            Kind::Snippet => "snippet",
+        }
+    }
+
+    /// 按 id 或标题查找的 SQL（?1 为 id 或标题）
+    fn sql(self) -> &'static str {
+        match self {
+            Kind::Note => "SELECT id FROM notes WHERE id = ?1 OR title = ?1",
+            Kind::Folder => "SELECT id FROM tree_nodes WHERE node_type = 'folder' AND scope = 'notes' AND (id = ?1 OR name = ?1)",
+            Kind::Project => "SELECT id FROM tree_nodes WHERE node_type = 'project' AND (id = ?1 OR name = ?1)",
+            Kind::Task => "SELECT id FROM tasks WHERE id = ?1 OR title = ?1",
+            Kind::Snippet => "SELECT id FROM snippets WHERE id = ?1 OR title = ?1",
+        }
+    }
+}
+
+/// 把 id 或标题解析为 id；标题重名时要求改用 id
+fn resolve(conn: &Connection, kind: Kind, ident: &str) -> Result<String, String> {
+    let mut stmt = conn.prepare(kind.sql()).map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![ident], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    if ids.iter().any(|id| id == ident) {
+        return Ok(ident.to_string());
+    }
+    match ids.as_slice() {
+        [id] => Ok(id.clone()),
+        [] => Err(format!("{} not found: {}", kind.name(), ident)),
+        _ => Err(format!(
+            "{} title is ambiguous: {} (use the id)",
+            kind.name(),
+            ident
+        )),
+    }
+}
+
+/// `YYYY-MM-DD` → 当地零点的毫秒级时间戳
+fn local_midnight(date: &str) -> Result<i64, String> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date (expected YYYY-MM-DD): {}", date))?;
+    let midnight = day.and_hms_opt(0, 0, 0).expect("valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+        .ok_or_else(|| format!("invalid local date: {}", date))
+}
+
+/// `1h30m` / `45m` / `1.5h` / `90s` / `90`（分钟）→ 秒
+fn parse_duration(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid duration: {}", value);
+    if let Ok(minutes) = value.parse::<f64>() {
+        if !minutes.is_finite() || minutes <= 0.0 {
+            return Err(invalid());
+        }
+        return Ok((minutes * 60.0).round() as i64);
+    }
+
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' | 's' => {
+                let n: f64 = number.parse().map_err(|_| invalid())?;
+                seconds += n * match c {
+                    'h' => 3600.0,
+                    'm' => 60.0,
+                    _ => 1.0,
+                };
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || !seconds.is_finite() || seconds <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(seconds.round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(5400));
+        assert_eq!(parse_duration("1.5h"), Ok(5400));
+        assert_eq!(parse_duration("1h30m"), Ok(5400));
+        assert_eq!(parse_duration("45s"), Ok(45));
+        for invalid in ["0", "-5", "nan", "inf", "infinity", "1x", "h", "1h30"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+        let huge = format!("{}h", "9".repeat(400));
+        assert!(parse_duration(&huge).is_err());
+    }
+}
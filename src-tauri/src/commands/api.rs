@@ -0,0 +1,49 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::api;
+
+#[derive(Serialize)]
+pub struct ApiStatus {
+    pub enabled: bool,
+    /// 服务是否正在监听（端口被占用时启动失败）
+    pub running: bool,
+    pub port: u16,
+    /// 例如 `http://127.0.0.1:27183/v1`
+    pub base_url: String,
+    /// 请求头 `Authorization: Bearer <token>` 使用的令牌
+    pub token: String,
+}
+
+/// 查询本机 HTTP API 状态（含访问令牌，供设置页展示）
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_api_status() -> Result<ApiStatus, String> {
+    let port = api::running_port().map_or_else(api::port, Ok)?;
+    Ok(ApiStatus {
+        enabled: api::is_enabled(),
+        running: api::running_port().is_some(),
+        port,
+        base_url: format!("http://127.0.0.1:{}/v1", port),
+        token: api::token()?,
+    })
+}
+
+/// 启用本机 HTTP API（仅监听 127.0.0.1）；port 为空时沿用上次的端口
+#[tauri::command(rename_all = "snake_case")]
+pub fn enable_api(app: AppHandle, port: Option<u16>) -> Result<ApiStatus, String> {
+    let port = port.map_or_else(api::port, Ok)?;
+    api::enable(app, port)?;
+    get_api_status()
+}
+
+/// 停用本机 HTTP API
+#[tauri::command(rename_all = "snake_case")]
+pub fn disable_api() -> Result<(), String> {
+    api::disable()
+}
+
+/// 重新生成访问令牌，旧令牌立即失效
+#[tauri::command(rename_all = "snake_case")]
+pub fn regenerate_api_token() -> Result<String, String> {
+    api::regenerate_token()
+}
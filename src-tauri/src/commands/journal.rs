@@ -0,0 +1,56 @@
+use tauri::{AppHandle, Emitter};
+
+use crate::service::journal::{self, DailyNote, DailyNoteEntry, JournalSettings};
+use crate::service::Storage;
+use crate::settings::SETTINGS_CHANGED_EVENT;
+
+/// 获取日记设置
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_journal_settings() -> Result<JournalSettings, String> {
+    let storage = Storage::open()?;
+    journal::get_settings(&storage).map_err(|e| e.to_string())
+}
+
+/// 更新日记设置（只影响之后新建的日记，已有日记保持原位置）
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_journal_settings(
+    app: AppHandle,
+    folder_name: String,
+    template_id: Option<String>,
+) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    let settings = journal::update_settings(&mut storage, &folder_name, template_id.as_deref())?;
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, settings);
+    Ok(())
+}
+
+/// 打开某天的日记（date 格式 YYYY-MM-DD），不存在时创建
+///
+/// 新日记位于 `<Journal>/<YYYY>/<MM>/`，标题为日期；
+/// template_id 为空时使用日记设置中的默认模板
+#[tauri::command(rename_all = "snake_case")]
+pub fn open_daily_note(date: String, template_id: Option<String>) -> Result<DailyNote, String> {
+    let mut storage = Storage::open()?;
+    journal::open_daily(&mut storage, &date, template_id.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 获取 date 之前最近的一篇日记
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_previous_daily_note(date: String) -> Result<Option<DailyNoteEntry>, String> {
+    let storage = Storage::open()?;
+    journal::adjacent(&storage, &date, true).map_err(|e| e.to_string())
+}
+
+/// 获取 date 之后最近的一篇日记
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_next_daily_note(date: String) -> Result<Option<DailyNoteEntry>, String> {
+    let storage = Storage::open()?;
+    journal::adjacent(&storage, &date, false).map_err(|e| e.to_string())
+}
+
+/// 列出某月有日记的日期（1..=31，升序）
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_daily_note_days(year: i32, month: u32) -> Result<Vec<u32>, String> {
+    let storage = Storage::open()?;
+    journal::list_days(&storage, year, month).map_err(|e| e.to_string())
+}
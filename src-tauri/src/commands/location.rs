@@ -0,0 +1,71 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::app::{data_dir, data_dir_source, db_path};
+use crate::db::vault;
+use crate::location::{self, Source};
+
+#[derive(Serialize)]
+pub struct DataDirInfo {
+    pub data_dir: String,
+    /// 位置来源：flag / env / portable / setting / default
+    pub source: Source,
+    /// 是否可以在应用内修改（命令行、环境变量和便携模式指定的位置不可修改）
+    pub configurable: bool,
+}
+
+fn require_configurable() -> Result<(), String> {
+    if !data_dir_source().is_configurable() {
+        return Err(
+            "the data directory is set by --data-dir, NOTO_DATA_DIR or portable mode".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// 查询当前数据目录及其来源
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_data_dir_info() -> Result<DataDirInfo, String> {
+    let source = data_dir_source();
+    Ok(DataDirInfo {
+        data_dir: data_dir().to_string_lossy().to_string(),
+        source,
+        configurable: source.is_configurable(),
+    })
+}
+
+/// 切换到另一个位置的库（不复制数据），path 为空时恢复系统默认目录；完成后重启应用
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_data_dir(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    require_configurable()?;
+    let path = path.filter(|p| !p.trim().is_empty()).map(PathBuf::from);
+    if let Some(path) = &path {
+        if !path.is_absolute() {
+            return Err("data directory must be an absolute path".to_string());
+        }
+    }
+
+    location::save_setting(path.as_deref())?;
+    app.restart()
+}
+
+/// 把当前库安全地移动到新位置（复制、校验后再删除旧文件）；完成后重启应用
+#[tauri::command(rename_all = "snake_case")]
+pub fn move_vault(app: AppHandle, target: String) -> Result<(), String> {
+    require_configurable()?;
+    if vault::is_locked(&db_path()) {
+        return Err("unlock the vault before moving it".to_string());
+    }
+
+    // 先提交等待中的版本历史，随库一起移动
+    if crate::history::is_enabled() {
+        crate::history::flush()?;
+    }
+
+    // 库锁持有到重启，期间本进程的其他操作不会写入已移走的旧位置
+    let from = data_dir();
+    let _lock = location::move_vault(&from, Path::new(&target))?;
+    crate::vaults::relocate(&from, Path::new(&target))?;
+    app.restart()
+}
@@ -1,19 +1,92 @@
+pub mod api;
+pub mod journal;
+pub mod location;
+pub mod note_crypto;
+pub mod note_history;
+pub mod note_templates;
 pub mod notes;
+pub mod projects;
+pub mod settings;
+pub mod snippet_files;
+pub mod snippet_vscode;
 pub mod snippets;
+pub mod sync;
 pub mod tasks;
 pub mod time_entries;
 pub mod tree;
+pub mod undo;
+pub mod vault;
+pub mod vaults;
+
+// Local HTTP API commands
+pub use self::api::{disable_api, enable_api, get_api_status, regenerate_api_token};
+
+// Data directory commands
+pub use self::location::{get_data_dir_info, move_vault, set_data_dir};
+
+// Journal commands
+pub use self::journal::{
+    get_journal_settings, get_next_daily_note, get_previous_daily_note, list_daily_note_days,
+    open_daily_note, update_journal_settings,
+};
+
+// Note encryption commands
+pub use self::note_crypto::{
+    decrypt_note, encrypt_note, is_note_unlocked, lock_all_notes, lock_note, unlock_note,
+};
+
+// Note history commands
+pub use self::note_history::{
+    disable_note_history, enable_note_history, get_note_history, is_note_history_enabled,
+    restore_note_version,
+};
+
+// Note template commands
+pub use self::note_templates::{
+    create_note_template, delete_note_template, list_note_templates, update_note_template,
+};
 
 pub use self::notes::create_note;
+pub use self::notes::export_note_html;
 pub use self::notes::get_note;
+pub use self::notes::get_note_outline;
+pub use self::notes::render_note_html;
 pub use self::notes::update_note_content;
+pub use self::notes::update_note_metadata;
 pub use self::notes::update_note_title;
+
+// Project commands
+pub use self::projects::{
+    attach_project_description, create_project_description, detach_project_description, get_project,
+};
+
+// Settings commands
+pub use self::settings::{
+    export_settings, get_settings, import_settings, reset_settings, update_settings,
+};
+
+// Snippet file commands
+pub use self::snippet_files::{
+    add_snippet_file, delete_snippet_file, rename_snippet_file, reorder_snippet_files,
+    update_snippet_file,
+};
+
+// VS Code snippet import / export
+pub use self::snippet_vscode::{export_vscode_snippets, import_vscode_snippets};
+
 pub use self::snippets::{
-    create_snippet, delete_snippet_only, get_snippet_detail, update_snippet_detail,
+    create_snippet, delete_snippet_only, detect_snippet_languages, get_snippet_detail,
+    list_highlight_themes, list_snippet_variables, render_snippet, render_snippet_template,
+    update_snippet_detail, update_snippet_meta,
+};
+
+// Sync commands
+pub use self::sync::{
+    configure_folder_sync, configure_webdav_sync, disable_sync, get_sync_status, sync_now,
 };
 
 // Task commands
-pub use self::tasks::{create_task, delete_task, get_task, list_tasks, update_task};
+pub use self::tasks::{create_task, delete_task, get_task, list_tasks, move_task, update_task};
 
 // Time entry commands
 pub use self::time_entries::{
@@ -22,5 +95,17 @@ pub use self::time_entries::{
 
 // Tree node commands
 pub use self::tree::{
-    create_tree_node, delete_tree_node, list_tree_nodes, list_tree_nodes_tree, update_tree_node,
+    create_tree_node, delete_tree_node, list_children, list_tree_nodes, list_tree_nodes_tree,
+    refresh_tree_node, update_tree_node,
+};
+
+// Undo / redo commands
+pub use self::undo::{get_undo_state, redo, undo};
+
+// Vault encryption commands
+pub use self::vault::{
+    change_vault_key, decrypt_vault, encrypt_vault, get_vault_status, unlock_vault,
 };
+
+// Vault registry commands
+pub use self::vaults::{close_vault, create_vault, list_vaults, open_vault, rename_vault};
@@ -0,0 +1,83 @@
+use rusqlite::params;
+use std::fs;
+use std::time::Duration;
+
+use crate::crypto::note as note_crypto;
+use crate::crypto::session::{self, DEFAULT_UNLOCK_TIMEOUT};
+use crate::db::connection::get_connection;
+use crate::service::{notes, Storage};
+
+/// 读取 note 文件路径与当前文件内容
+fn read_raw(note_id: &str) -> Result<(String, String), String> {
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    let content_path: String = conn
+        .query_row(
+            "SELECT content_path FROM notes WHERE id = ?",
+            params![note_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let raw = fs::read_to_string(&content_path).map_err(|e| e.to_string())?;
+    Ok((content_path, raw))
+}
+
+/// 以口令加密 note 文件（XChaCha20-Poly1305，密钥由 Argon2id 派生）
+///
+/// 加密后处于锁定状态，需要 unlock_note 才能读取
+#[tauri::command(rename_all = "snake_case")]
+pub fn encrypt_note(note_id: String, passphrase: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    notes::encrypt(&mut storage, &note_id, &passphrase).map_err(|e| e.to_string())
+}
+
+/// 校验口令并解除 note 加密，文件恢复为明文
+#[tauri::command(rename_all = "snake_case")]
+pub fn decrypt_note(note_id: String, passphrase: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    notes::decrypt(&mut storage, &note_id, &passphrase).map_err(|e| e.to_string())
+}
+
+/// 解锁加密 note；timeout_secs 秒无操作后自动锁定（默认 5 分钟）
+#[tauri::command(rename_all = "snake_case")]
+pub fn unlock_note(
+    note_id: String,
+    passphrase: String,
+    timeout_secs: Option<u64>,
+) -> Result<(), String> {
+    let (_, raw) = read_raw(&note_id)?;
+    if !note_crypto::is_encrypted(&raw) {
+        return Err("note is not encrypted".to_string());
+    }
+
+    // 解密一次以校验口令
+    let salt = note_crypto::read_salt(&raw)?;
+    let key = note_crypto::derive_key(&passphrase, &salt)?;
+    note_crypto::decrypt(&raw, &key)?;
+
+    let timeout = timeout_secs
+        .filter(|s| *s > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_UNLOCK_TIMEOUT);
+    session::unlock(&note_id, key, salt, timeout);
+    Ok(())
+}
+
+/// 立即锁定 note
+#[tauri::command(rename_all = "snake_case")]
+pub fn lock_note(note_id: String) -> Result<(), String> {
+    session::lock(&note_id);
+    Ok(())
+}
+
+/// 锁定全部已解锁的 note
+#[tauri::command(rename_all = "snake_case")]
+pub fn lock_all_notes() -> Result<(), String> {
+    session::lock_all();
+    Ok(())
+}
+
+/// note 当前是否已解锁
+#[tauri::command(rename_all = "snake_case")]
+pub fn is_note_unlocked(note_id: String) -> Result<bool, String> {
+    Ok(session::is_unlocked(&note_id))
+}
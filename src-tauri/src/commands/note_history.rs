@@ -0,0 +1,105 @@
+use chrono::{Local, TimeZone};
+use rusqlite::{params, OptionalExtension};
+use std::fs;
+
+use crate::changelog;
+use crate::db::models::get_note_title;
+use crate::history::{self, repo as history_repo, ChangeKind};
+use crate::markdown::front_matter;
+use crate::service::notes::{insert_note_copy, note_parent_id, CreatedNote};
+use crate::service::Storage;
+
+/// 默认返回的历史条数
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+/// 启用 notes/ 目录的本地 git 版本管理（不涉及网络）
+#[tauri::command(rename_all = "snake_case")]
+pub fn enable_note_history() -> Result<(), String> {
+    history::enable()
+}
+
+/// 停用版本管理（已有的提交保留）
+#[tauri::command(rename_all = "snake_case")]
+pub fn disable_note_history() -> Result<(), String> {
+    history::disable()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn is_note_history_enabled() -> Result<bool, String> {
+    Ok(history::is_enabled())
+}
+
+/// 获取 note 的修改历史（新 → 旧）；会先提交等待中的变更
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_note_history(
+    note_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<history_repo::Revision>, String> {
+    if !history::is_enabled() {
+        return Err("note history is not enabled".to_string());
+    }
+    history::flush()?;
+
+    let repo = history_repo::open_or_init(&crate::app::data_dir()).map_err(|e| e.to_string())?;
+    history_repo::note_log(&repo, &note_id, limit.unwrap_or(DEFAULT_LOG_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+/// 将 note 的历史版本检出为新 note（与原 note 位于同一文件夹，原 note 不变）
+#[tauri::command(rename_all = "snake_case")]
+pub fn restore_note_version(note_id: String, commit_id: String) -> Result<CreatedNote, String> {
+    let now = chrono::Utc::now().timestamp();
+
+    // 1. 读取历史内容（已加密的 note 不能恢复加密前的明文版本）
+    let mut storage = Storage::open()?;
+    let encrypted: Option<bool> = storage
+        .conn
+        .query_row(
+            "SELECT encrypted FROM notes WHERE id = ?",
+            params![note_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if encrypted == Some(true) {
+        return Err("cannot restore a version of an encrypted note".to_string());
+    }
+    let repo = history_repo::open_or_init(&storage.root).map_err(|e| e.to_string())?;
+    let (content, committed_at) =
+        history_repo::note_at(&repo, &note_id, &commit_id).map_err(|e| e.to_string())?;
+
+    // 2. 新标题：原标题（已删除时取 front matter）+ 版本时间
+    let base_title = match get_note_title(&storage.conn, &note_id).map_err(|e| e.to_string())? {
+        Some(title) => title,
+        None => front_matter::parse(&content)
+            .ok()
+            .flatten()
+            .and_then(|fm| fm.title)
+            .unwrap_or_else(|| "Restored note".to_string()),
+    };
+    let parent_id = note_parent_id(&storage.conn, &note_id)?;
+    let version = Local
+        .timestamp_opt(committed_at, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let title = format!("{} ({})", base_title, version);
+
+    // 3. 创建新 note 并写入历史内容
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction().map_err(|e| e.to_string())?;
+    let (created, path, content) = insert_note_copy(
+        &tx,
+        &storage.root,
+        &title,
+        parent_id.as_deref(),
+        &content,
+        now,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    changelog::record(&storage.conn, "Restore note version");
+
+    history::record(ChangeKind::Create, &created.note_id, Some(&title));
+    Ok(created)
+}
@@ -0,0 +1,34 @@
+use crate::service::note_templates::{self, NoteTemplate};
+use crate::service::Storage;
+
+/// 列出所有 note 模板（按名称排序）
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_note_templates() -> Result<Vec<NoteTemplate>, String> {
+    let storage = Storage::open()?;
+    note_templates::list(&storage).map_err(|e| e.to_string())
+}
+
+/// 创建 note 模板，返回模板 id
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_note_template(name: String, content: String) -> Result<String, String> {
+    let mut storage = Storage::open()?;
+    note_templates::create(&mut storage, &name, &content).map_err(|e| e.to_string())
+}
+
+/// 更新 note 模板的名称 / 内容
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_note_template(
+    template_id: String,
+    name: String,
+    content: String,
+) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    note_templates::update(&mut storage, &template_id, &name, &content).map_err(|e| e.to_string())
+}
+
+/// 删除 note 模板（已创建的 note 不受影响）
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_note_template(template_id: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    note_templates::delete(&mut storage, &template_id).map_err(|e| e.to_string())
+}
@@ -1,165 +1,88 @@
-use tauri::AppHandle;
-use tauri::Manager;
-use uuid::Uuid;
-
-use crate::db::connection::get_connection;
-use crate::db::models::{insert_node_note_resource, insert_note, insert_notes_tree_node};
-use crate::fs::notes::{create_note_file, delete_note_file};
-use rusqlite::params;
-use serde::Serialize;
 use std::fs;
 
-#[tauri::command(rename_all = "snake_case")]
-pub fn create_note(app: AppHandle, title: String, parent_id: Option<String>) -> Result<(), String> {
-    let now = chrono::Utc::now().timestamp();
-
-    let note_id = Uuid::new_v4().to_string();
-    let node_id = Uuid::new_v4().to_string();
-
-    // 1. 先创建 Markdown 文件（失败直接返回）
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-
-    let note_file_path =
-        create_note_file(&app_data_dir, &note_id, &title).map_err(|e| e.to_string())?;
+use crate::markdown::outline::Outline;
+use crate::service::notes::{self, CreatedNote, NoteDetail};
+use crate::service::Storage;
 
-    // 2. 打开数据库连接
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-
-    // 3. 开启 transaction
-    let tx = conn.transaction().map_err(|e| {
-        delete_note_file(&note_file_path);
-        e.to_string()
-    })?;
-
-    // 4. 插入 tree_nodes（Notes Scope）
-    if let Err(e) = insert_notes_tree_node(&tx, &node_id, parent_id.as_deref(), &title, 0, now) {
-        delete_note_file(&note_file_path);
-        return Err(e.to_string());
-    }
-
-    // 5. 插入 notes
-    if let Err(e) = insert_note(
-        &tx,
-        &note_id,
+/// 创建 note；指定 template_id 时用模板内容实例化
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_note(
+    title: String,
+    parent_id: Option<String>,
+    template_id: Option<String>,
+) -> Result<CreatedNote, String> {
+    let mut storage = Storage::open()?;
+    notes::create(
+        &mut storage,
         &title,
-        note_file_path.to_string_lossy().as_ref(),
-        now,
-    ) {
-        delete_note_file(&note_file_path);
-        return Err(e.to_string());
-    }
-
-    // 6. 将 Note 挂载到该 tree_node（关键新增）
-    if let Err(e) = insert_node_note_resource(&tx, &node_id, &note_id, now) {
-        delete_note_file(&note_file_path);
-        return Err(e.to_string());
-    }
-
-    // 7. 提交 transaction
-    if let Err(e) = tx.commit() {
-        delete_note_file(&note_file_path);
-        return Err(e.to_string());
-    }
-
-    Ok(())
-}
-
-#[derive(Serialize)]
-pub struct NoteDetail {
-    pub id: String,
-    pub title: String,
-    pub content: String,
-    pub created_at: i64,
-    pub updated_at: i64,
+        parent_id.as_deref(),
+        template_id.as_deref(),
+    )
+    .map_err(|e| e.to_string())
 }
 
 /// 获取笔记详情（标题 + 内容）
+///
+/// 文件可能在应用外被修改：读取时先把 front matter 同步回数据库
 #[tauri::command(rename_all = "snake_case")]
 pub fn get_note(note_id: String) -> Result<NoteDetail, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-
-    let mut stmt = conn
-        .prepare("SELECT id, title, content_path, created_at, updated_at FROM notes WHERE id = ?")
-        .map_err(|e| e.to_string())?;
-
-    let row = stmt
-        .query_row(params![note_id], |r| {
-            Ok((
-                r.get::<_, String>(0)?,
-                r.get::<_, String>(1)?,
-                r.get::<_, String>(2)?,
-                r.get::<_, i64>(3)?,
-                r.get::<_, i64>(4)?,
-            ))
-        })
-        .map_err(|e| e.to_string())?;
-
-    let content = fs::read_to_string(&row.2).map_err(|e| e.to_string())?;
-
-    Ok(NoteDetail {
-        id: row.0,
-        title: row.1,
-        content,
-        created_at: row.3,
-        updated_at: row.4,
-    })
+    let mut storage = Storage::open()?;
+    notes::get(&mut storage, &note_id).map_err(|e| e.to_string())
 }
 
 /// 更新笔记标题，并同步树节点名称
+///
+/// 文件已有 front matter 时同时改写其中的 title
 #[tauri::command(rename_all = "snake_case")]
 pub fn update_note_title(note_id: String, title: String) -> Result<(), String> {
-    let now = chrono::Utc::now().timestamp();
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    // 1) 更新 notes 标题/更新时间
-    tx.execute(
-        "UPDATE notes SET title = ?, updated_at = ? WHERE id = ?",
-        params![title, now, note_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // 2) 找到挂载该 note 的树节点并同步名称/更新时间
-    tx.execute(
-        r#"
-        UPDATE tree_nodes
-        SET name = ?, updated_at = ?
-        WHERE id IN (
-            SELECT node_id FROM node_resources
-            WHERE resource_type = 'note' AND resource_id = ?
-        )
-        "#,
-        params![title, now, note_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let mut storage = Storage::open()?;
+    notes::update_title(&mut storage, &note_id, &title).map_err(|e| e.to_string())
+}
 
-    tx.commit().map_err(|e| e.to_string())?;
-    Ok(())
+/// 更新笔记的标签 / 别名，并写入文件的 front matter（没有时新建）
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_note_metadata(
+    note_id: String,
+    tags: Vec<String>,
+    aliases: Vec<String>,
+) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    notes::update_metadata(&mut storage, &note_id, &tags, &aliases).map_err(|e| e.to_string())
 }
 
 /// 更新笔记内容，并更新 notes.updated_at
+///
+/// 内容中的 front matter 会同步到数据库（格式错误时忽略）
 #[tauri::command(rename_all = "snake_case")]
 pub fn update_note_content(note_id: String, content: String) -> Result<(), String> {
-    let now = chrono::Utc::now().timestamp();
-    let conn = get_connection().map_err(|e| e.to_string())?;
-
-    // 获取内容路径
-    let mut stmt = conn
-        .prepare("SELECT content_path FROM notes WHERE id = ?")
-        .map_err(|e| e.to_string())?;
-    let content_path: String = stmt
-        .query_row(params![note_id.clone()], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
+    let mut storage = Storage::open()?;
+    notes::update_content(&mut storage, &note_id, &content).map_err(|e| e.to_string())
+}
 
-    // 写文件
-    fs::write(&content_path, content).map_err(|e| e.to_string())?;
+/// 将 note 渲染为独立 HTML（表格 / 任务列表 / 脚注 / 代码高亮，本地图片内嵌）
+#[tauri::command(rename_all = "snake_case")]
+pub fn render_note_html(note_id: String, theme: Option<String>) -> Result<String, String> {
+    let storage = Storage::open()?;
+    notes::render_html(&storage, &note_id, theme).map_err(|e| e.to_string())
+}
 
-    // 更新 updated_at
-    conn.execute(
-        "UPDATE notes SET updated_at = ? WHERE id = ?",
-        params![now, note_id],
-    )
-    .map_err(|e| e.to_string())?;
+/// 将 note 导出为 HTML 文件
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_note_html(
+    note_id: String,
+    path: String,
+    theme: Option<String>,
+) -> Result<(), String> {
+    let storage = Storage::open()?;
+    let html = notes::render_html(&storage, &note_id, theme)?;
+    fs::write(&path, html).map_err(|e| e.to_string())
+}
 
-    Ok(())
+/// 获取 note 的标题树（层级 / 文本 / 锚点 / 行号与字节偏移）及文档统计
+///
+/// 按文件修改时间缓存，文件未变时不再读取
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_note_outline(note_id: String) -> Result<Outline, String> {
+    let storage = Storage::open()?;
+    notes::outline(&storage, &note_id).map_err(|e| e.to_string())
 }
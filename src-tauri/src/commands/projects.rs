@@ -0,0 +1,39 @@
+use crate::service::projects::{self, ProjectDetail};
+use crate::service::Storage;
+
+/// 为项目创建描述 note，返回新 note id
+///
+/// 描述 note 不挂载到 notes 树，仅通过 tree_nodes.description_note_id 引用
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_project_description(
+    node_id: String,
+    title: Option<String>,
+) -> Result<String, String> {
+    let mut storage = Storage::open()?;
+    projects::create_description(&mut storage, &node_id, title.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 将已有 note 设为项目描述（替换原有关联，不删除原描述 note）
+#[tauri::command(rename_all = "snake_case")]
+pub fn attach_project_description(node_id: String, note_id: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    projects::attach_description(&mut storage, &node_id, &note_id).map_err(|e| e.to_string())
+}
+
+/// 解除项目描述关联
+///
+/// 若该 note 不再被任何节点引用（例如由 create_project_description 创建），
+/// 会一并删除 note 记录及其 Markdown 文件
+#[tauri::command(rename_all = "snake_case")]
+pub fn detach_project_description(node_id: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    projects::detach_description(&mut storage, &node_id).map_err(|e| e.to_string())
+}
+
+/// 获取项目节点及其描述 note
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_project(node_id: String) -> Result<ProjectDetail, String> {
+    let storage = Storage::open()?;
+    projects::get(&storage, &node_id).map_err(|e| e.to_string())
+}
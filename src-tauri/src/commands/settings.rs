@@ -0,0 +1,56 @@
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::fs;
+use tauri::{AppHandle, Emitter};
+
+use crate::service::Storage;
+use crate::settings::{self, Settings, SETTINGS_CHANGED_EVENT};
+
+/// 通知所有窗口设置已变化
+fn notify(app: &AppHandle, settings: &Settings) {
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, settings.clone());
+}
+
+/// 获取全部设置（未设置的项为默认值）
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_settings() -> Result<Settings, String> {
+    let storage = Storage::open()?;
+    settings::get(&storage.conn)
+}
+
+/// 更新部分设置，例如 `{ "editor.font_size": 16 }`；任一项不合法时全部不生效
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_settings(
+    app: AppHandle,
+    values: JsonMap<String, JsonValue>,
+) -> Result<Settings, String> {
+    let storage = Storage::open()?;
+    let settings = settings::update(&storage.conn, values)?;
+    notify(&app, &settings);
+    Ok(settings)
+}
+
+/// 恢复默认值；keys 为空时恢复全部设置
+#[tauri::command(rename_all = "snake_case")]
+pub fn reset_settings(app: AppHandle, keys: Option<Vec<String>>) -> Result<Settings, String> {
+    let storage = Storage::open()?;
+    let settings = settings::reset(&storage.conn, keys.as_deref())?;
+    notify(&app, &settings);
+    Ok(settings)
+}
+
+/// 将设置导出为 JSON 文件
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_settings(path: String) -> Result<(), String> {
+    let storage = Storage::open()?;
+    fs::write(&path, settings::export(&storage.conn)?).map_err(|e| e.to_string())
+}
+
+/// 从 JSON 文件导入设置（替换当前设置，文件中没有的项恢复默认值）
+#[tauri::command(rename_all = "snake_case")]
+pub fn import_settings(app: AppHandle, path: String) -> Result<Settings, String> {
+    let json = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let storage = Storage::open()?;
+    let settings = settings::import(&storage.conn, &json)?;
+    notify(&app, &settings);
+    Ok(settings)
+}
@@ -0,0 +1,51 @@
+use crate::service::snippets;
+use crate::service::Storage;
+
+/// 为 snippet 新增文件（追加到末尾），返回新文件 id
+///
+/// 未指定语言时根据文件扩展名 / 内容自动识别
+#[tauri::command(rename_all = "snake_case")]
+pub fn add_snippet_file(
+    snippet_id: String,
+    file_name: String,
+    language: Option<String>,
+    content: String,
+) -> Result<String, String> {
+    let mut storage = Storage::open()?;
+    snippets::add_file(&mut storage, &snippet_id, file_name, language, content)
+        .map_err(|e| e.to_string())
+}
+
+/// 更新文件内容 / 语言
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_snippet_file(
+    file_id: String,
+    language: Option<String>,
+    content: String,
+) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    snippets::update_file(&mut storage, &file_id, language, content).map_err(|e| e.to_string())
+}
+
+/// 重命名文件
+///
+/// 语言为自动识别得出时，按新文件名重新识别；用户指定的语言保持不变
+#[tauri::command(rename_all = "snake_case")]
+pub fn rename_snippet_file(file_id: String, file_name: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    snippets::rename_file(&mut storage, &file_id, file_name).map_err(|e| e.to_string())
+}
+
+/// 调整文件顺序（file_ids 必须恰好包含该 snippet 的全部文件），第一个文件即为主文件
+#[tauri::command(rename_all = "snake_case")]
+pub fn reorder_snippet_files(snippet_id: String, file_ids: Vec<String>) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    snippets::reorder_files(&mut storage, &snippet_id, &file_ids).map_err(|e| e.to_string())
+}
+
+/// 删除文件（snippet 至少保留一个文件）
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_snippet_file(file_id: String) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    snippets::delete_file(&mut storage, &file_id).map_err(|e| e.to_string())
+}
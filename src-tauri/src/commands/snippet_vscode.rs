@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use crate::service::{snippet_vscode, Storage};
+
+/// 从 VS Code snippet 文件（`.code-snippets` / `<language>.json`）导入到指定文件夹，返回导入数量
+///
+/// - body 数组按行拼接为内容，`$1` / `${1:default}` 等占位符原样保留
+/// - scope 的第一个语言作为 snippet 语言；没有 scope 时自动识别
+/// - prefix / description / scope 原样保存，用于导出时还原
+#[tauri::command(rename_all = "snake_case")]
+pub fn import_vscode_snippets(parent_id: String, path: String) -> Result<usize, String> {
+    let mut storage = Storage::open()?;
+    snippet_vscode::import(&mut storage, &parent_id, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// 将 snippets 子树（文件夹或单个 snippet 节点）导出为 `.code-snippets` 文件，返回导出数量
+///
+/// 多文件 snippet 只导出主文件内容；没有保存 scope 时按语言生成
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_vscode_snippets(node_id: String, path: String) -> Result<usize, String> {
+    let mut storage = Storage::open()?;
+    snippet_vscode::export(&mut storage, &node_id, Path::new(&path)).map_err(|e| e.to_string())
+}
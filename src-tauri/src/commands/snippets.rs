@@ -1,92 +1,40 @@
-use chrono::Utc;
-use rusqlite::params;
-use serde::Serialize;
+use std::collections::HashMap;
 use tauri::AppHandle;
-use uuid::Uuid;
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
-use crate::db::connection::get_connection;
-use crate::db::models::{
-    delete_snippet, get_snippet, insert_node_snippet_resource, insert_snippet,
-    insert_snippets_tree_node, update_snippet,
-};
-
-#[derive(Serialize)]
-pub struct SnippetDetail {
-    pub id: String,
-    pub title: String,
-    pub language: Option<String>,
-    pub content: String,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+use crate::lang::highlight::{parse_line_ranges, theme_names};
+use crate::lang::HighlightOptions;
+use crate::service::snippets::{self, SnippetDetail};
+use crate::service::Storage;
+use crate::template::snippet::TemplateVariable;
 
 /// 创建 snippet，并在 snippets scope 下创建对应 tree_node + node_resources
 #[tauri::command(rename_all = "snake_case")]
 pub fn create_snippet(
-    _app: AppHandle,
     title: String,
     language: Option<String>,
     content: String,
     parent_id: Option<String>,
 ) -> Result<(), String> {
-    let now = Utc::now().timestamp();
-
-    let snippet_id = Uuid::new_v4().to_string();
-    let node_id = Uuid::new_v4().to_string();
-
-    // 1. 打开数据库连接
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-
-    // 2. 开启 transaction
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    // 3. 插入 tree_nodes（Snippets Scope）
-    insert_snippets_tree_node(
-        &tx,
-        &node_id,
-        parent_id.as_deref(),
+    let mut storage = Storage::open()?;
+    snippets::create(
+        &mut storage,
         &title,
-        0, // order_index，暂固定
-        now,
-    )
-    .map_err(|e| e.to_string())?;
-
-    // 4. 插入 snippets
-    insert_snippet(&tx, &snippet_id, &title, language.as_deref(), &content, now)
-        .map_err(|e| e.to_string())?;
-
-    // 5. 挂载 snippet 到 tree_node
-    insert_node_snippet_resource(&tx, &node_id, &snippet_id, now).map_err(|e| e.to_string())?;
-
-    // 6. 提交 transaction
-    tx.commit().map_err(|e| e.to_string())?;
-
+        language,
+        content,
+        parent_id.as_deref(),
+    )?;
     Ok(())
 }
 
-/// 获取 snippet 详情（不涉及 tree）
+/// 获取 snippet 详情及其全部文件（不涉及 tree）
 #[tauri::command(rename_all = "snake_case")]
 pub fn get_snippet_detail(snippet_id: String) -> Result<SnippetDetail, String> {
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    let (id, title, language, content, created_at, updated_at) =
-        get_snippet(&tx, &snippet_id).map_err(|e| e.to_string())?;
-
-    // 对于只读查询，可以不必须 commit，但这里保持一致性
-    tx.commit().map_err(|e| e.to_string())?;
-
-    Ok(SnippetDetail {
-        id,
-        title,
-        language,
-        content,
-        created_at,
-        updated_at,
-    })
+    let storage = Storage::open()?;
+    snippets::get(&storage, &snippet_id).map_err(|e| e.to_string())
 }
 
-/// 更新 snippet 的标题 / 语言 / 内容
+/// 更新 snippet 的标题 / 语言 / 内容（内容与语言写入主文件）
 #[tauri::command(rename_all = "snake_case")]
 pub fn update_snippet_detail(
     snippet_id: String,
@@ -94,30 +42,28 @@ pub fn update_snippet_detail(
     language: Option<String>,
     content: String,
 ) -> Result<(), String> {
-    let now = Utc::now().timestamp();
-
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    // 先检查是否存在
-    {
-        let mut stmt = tx
-            .prepare("SELECT COUNT(1) FROM snippets WHERE id = ?")
-            .map_err(|e| e.to_string())?;
-        let count: i64 = stmt
-            .query_row(params![&snippet_id], |r| r.get(0))
-            .map_err(|e| e.to_string())?;
-        if count == 0 {
-            return Err("snippet not found".to_string());
-        }
-    }
-
-    update_snippet(&tx, &snippet_id, &title, language.as_deref(), &content, now)
-        .map_err(|e| e.to_string())?;
-
-    tx.commit().map_err(|e| e.to_string())?;
+    let mut storage = Storage::open()?;
+    snippets::update(&mut storage, &snippet_id, &title, language, content)
+        .map_err(|e| e.to_string())
+}
 
-    Ok(())
+/// 更新 snippet 的编辑器元信息（prefix / description / scope，空字符串视为清空）
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_snippet_meta(
+    snippet_id: String,
+    prefix: Option<String>,
+    description: Option<String>,
+    scope: Option<String>,
+) -> Result<(), String> {
+    let mut storage = Storage::open()?;
+    snippets::update_meta(
+        &mut storage,
+        &snippet_id,
+        prefix.as_deref(),
+        description.as_deref(),
+        scope.as_deref(),
+    )
+    .map_err(|e| e.to_string())
 }
 
 /// 删除 snippet：会删除 snippets 表中的记录、对应 node_resources 记录，但不自动删除 tree_node
@@ -125,24 +71,76 @@ pub fn update_snippet_detail(
 /// 让前端通过 tree API 删除 tree_node，可以复用现有「有子节点/有资源」校验逻辑。
 #[tauri::command(rename_all = "snake_case")]
 pub fn delete_snippet_only(snippet_id: String) -> Result<(), String> {
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut storage = Storage::open()?;
+    snippets::delete(&mut storage, &snippet_id).map_err(|e| e.to_string())
+}
 
-    // 删除 node_resources 中引用该 snippet 的记录
-    {
-        let mut stmt = tx
-            .prepare(
-                "DELETE FROM node_resources WHERE resource_id = ? AND resource_type = 'snippet'",
-            )
-            .map_err(|e| e.to_string())?;
-        stmt.execute(params![&snippet_id])
-            .map_err(|e| e.to_string())?;
-    }
+/// 批量识别 snippet 文件语言，返回更新的文件数
+///
+/// - 默认只处理 language 为空的文件
+/// - redetect = true 时，同时重新识别此前自动识别得出的语言（不影响用户指定的语言）
+#[tauri::command(rename_all = "snake_case")]
+pub fn detect_snippet_languages(redetect: Option<bool>) -> Result<usize, String> {
+    let mut storage = Storage::open()?;
+    snippets::detect_languages(&mut storage, redetect.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// 将 snippet 渲染为语法高亮文本
+///
+/// - format："html"（默认，内联样式）或 "ansi"（终端真彩色）
+/// - highlight_lines：需要强调的行，如 "1,3-5"
+#[tauri::command(rename_all = "snake_case")]
+pub fn render_snippet(
+    snippet_id: String,
+    format: Option<String>,
+    theme: Option<String>,
+    line_numbers: Option<bool>,
+    highlight_lines: Option<String>,
+) -> Result<String, String> {
+    let options = HighlightOptions {
+        theme,
+        line_numbers: line_numbers.unwrap_or(false),
+        highlight_lines: parse_line_ranges(highlight_lines.as_deref().unwrap_or(""))?,
+    };
+
+    let storage = Storage::open()?;
+    snippets::render(
+        &storage,
+        &snippet_id,
+        format.as_deref().unwrap_or("html"),
+        options,
+    )
+    .map_err(|e| e.to_string())
+}
 
-    // 删除 snippet 本体
-    delete_snippet(&tx, &snippet_id).map_err(|e| e.to_string())?;
+/// 列出可用的高亮主题
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_highlight_themes() -> Vec<String> {
+    theme_names()
+}
 
-    tx.commit().map_err(|e| e.to_string())?;
+/// 列出 snippet 模板中的变量（`${name}` / `${name:default}`）
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_snippet_variables(snippet_id: String) -> Result<Vec<TemplateVariable>, String> {
+    let storage = Storage::open()?;
+    snippets::variables(&storage, &snippet_id).map_err(|e| e.to_string())
+}
 
-    Ok(())
+/// 用给定变量值渲染 snippet 模板，并记录一次使用
+///
+/// 取值顺序：values → 内置变量（date / time / datetime / timestamp / uuid / clipboard）→ 默认值
+#[tauri::command(rename_all = "snake_case")]
+pub fn render_snippet_template(
+    app: AppHandle,
+    snippet_id: String,
+    values: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let mut storage = Storage::open()?;
+    snippets::render_template(
+        &mut storage,
+        &snippet_id,
+        &values.unwrap_or_default(),
+        || app.clipboard().read_text().ok(),
+    )
+    .map_err(|e| e.to_string())
 }
@@ -0,0 +1,159 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::db::connection::get_connection;
+use crate::db::models::{get_setting, set_setting};
+use crate::service::Storage;
+use crate::sync::{
+    self, transport::FolderTransport, transport::Transport, webdav::WebDavTransport, SyncReport,
+    SYNC_DIR_KEY, SYNC_TRANSPORT_KEY, SYNC_WEBDAV_PASSWORD_KEY, SYNC_WEBDAV_URL_KEY,
+    SYNC_WEBDAV_USERNAME_KEY,
+};
+
+#[derive(Serialize)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub name: Option<String>,
+    pub last_clock: i64,
+    pub last_synced_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// 'folder' | 'webdav'
+    pub transport: Option<String>,
+    pub dir: Option<String>,
+    /// WebDAV 目录 URL 与用户名（密码不返回）
+    pub webdav_url: Option<String>,
+    pub webdav_username: Option<String>,
+    pub device_id: String,
+    pub device_name: String,
+    /// 尚未导出的本机修改条数
+    pub pending_changes: i64,
+    pub peers: Vec<SyncPeer>,
+}
+
+#[derive(Deserialize)]
+struct PeerInfo {
+    name: String,
+}
+
+/// 使用本地目录（Syncthing / 网盘 / 共享盘）作为同步目录并启用同步
+#[tauri::command(rename_all = "snake_case")]
+pub fn configure_folder_sync(dir: String, device_name: Option<String>) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let path = PathBuf::from(&dir);
+    std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+
+    let storage = Storage::open()?;
+    let conn = &storage.conn;
+    set_setting(conn, SYNC_TRANSPORT_KEY, Some("folder"), now).map_err(|e| e.to_string())?;
+    set_setting(conn, SYNC_DIR_KEY, Some(&dir), now).map_err(|e| e.to_string())?;
+
+    sync::enable(conn, &FolderTransport::new(path), device_name.as_deref())
+}
+
+/// 使用 WebDAV 服务器作为同步目录并启用同步
+///
+/// 先以 PROPFIND 验证地址与凭据，通过后再保存到设置中
+#[tauri::command(rename_all = "snake_case")]
+pub fn configure_webdav_sync(
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    device_name: Option<String>,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let transport = WebDavTransport::new(&url, username.as_deref(), password.as_deref())?;
+    transport.list_entries("")?;
+
+    let storage = Storage::open()?;
+    let conn = &storage.conn;
+    set_setting(conn, SYNC_TRANSPORT_KEY, Some("webdav"), now).map_err(|e| e.to_string())?;
+    set_setting(conn, SYNC_WEBDAV_URL_KEY, Some(url.trim()), now).map_err(|e| e.to_string())?;
+    set_setting(conn, SYNC_WEBDAV_USERNAME_KEY, username.as_deref(), now)
+        .map_err(|e| e.to_string())?;
+    set_setting(conn, SYNC_WEBDAV_PASSWORD_KEY, password.as_deref(), now)
+        .map_err(|e| e.to_string())?;
+
+    sync::enable(conn, &transport, device_name.as_deref())
+}
+
+/// 停用同步（本地数据与同步目录中的文件均保留）
+#[tauri::command(rename_all = "snake_case")]
+pub fn disable_sync() -> Result<(), String> {
+    let storage = Storage::open()?;
+    let conn = &storage.conn;
+    sync::disable(conn)
+}
+
+/// 同步状态：配置、待导出数量与已知的其他设备
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_sync_status() -> Result<SyncStatus, String> {
+    // 只读，且会访问同步目录（可能是网络），不持有库锁
+    let conn = &get_connection().map_err(|e| e.to_string())?;
+    let transport = sync::transport_from_settings(conn).ok();
+
+    let pending_changes: i64 = conn
+        .query_row(
+            "SELECT COUNT(DISTINCT table_name || ':' || row_key) FROM sync_log",
+            [],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, last_clock, last_synced_at FROM sync_peers ORDER BY last_synced_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let peers = stmt
+        .query_map(params![], |r| {
+            Ok(SyncPeer {
+                device_id: r.get(0)?,
+                name: None,
+                last_clock: r.get(1)?,
+                last_synced_at: r.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|mut peer| {
+            // 设备名称由各设备写在 devices/<id>.json
+            peer.name = transport.as_ref().and_then(|t| {
+                let data = t.read(&format!("devices/{}.json", peer.device_id)).ok()?;
+                serde_json::from_slice::<PeerInfo>(&data)
+                    .ok()
+                    .map(|i| i.name)
+            });
+            peer
+        })
+        .collect();
+
+    Ok(SyncStatus {
+        enabled: sync::is_enabled(conn)?,
+        transport: get_setting(conn, SYNC_TRANSPORT_KEY).map_err(|e| e.to_string())?,
+        dir: get_setting(conn, SYNC_DIR_KEY).map_err(|e| e.to_string())?,
+        webdav_url: get_setting(conn, SYNC_WEBDAV_URL_KEY).map_err(|e| e.to_string())?,
+        webdav_username: get_setting(conn, SYNC_WEBDAV_USERNAME_KEY).map_err(|e| e.to_string())?,
+        device_id: sync::device_id(conn)?,
+        device_name: sync::device_name(conn)?,
+        pending_changes,
+        peers,
+    })
+}
+
+/// 立即同步：导出本机修改并合并其他设备的修改
+///
+/// 同一 note 的并发修改按时钟决出胜者，本机被覆盖的内容另存为冲突副本；
+/// 附件按 ETag 判断并发修改，本机版本另存为冲突副本文件
+#[tauri::command(rename_all = "snake_case")]
+pub fn sync_now() -> Result<SyncReport, String> {
+    let mut storage = Storage::open()?;
+    let transport = sync::transport_from_settings(&storage.conn)?;
+    sync::sync_now(&mut storage.conn, transport.as_ref(), &storage.root)
+}
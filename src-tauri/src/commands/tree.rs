@@ -1,25 +1,5 @@
-use chrono::Utc;
-use serde::Serialize;
-use uuid::Uuid;
-
-use crate::db::connection::get_connection;
-use crate::db::models as db_models;
-use rusqlite::params;
-
-#[derive(Serialize)]
-pub struct TreeNode {
-    pub id: String,
-    pub parent_id: Option<String>,
-    pub name: String,
-    pub node_type: String,
-    pub scope: String,
-    pub order_index: i64,
-    pub description_note_id: Option<String>,
-    pub resource_id: Option<String>,
-    pub resource_type: Option<String>,
-    pub created_at: i64,
-    pub updated_at: i64,
-}
+use crate::service::tree::{self, ChildrenPage, NodeWithChildren, TreeNode, TreeResponseNode};
+use crate::service::Storage;
 
 #[tauri::command(rename_all = "snake_case")]
 pub fn create_tree_node(
@@ -29,43 +9,16 @@ pub fn create_tree_node(
     parent_id: Option<String>,
     order_index: Option<i64>,
 ) -> Result<String, String> {
-    let now = Utc::now().timestamp();
-    let node_id = Uuid::new_v4().to_string();
-
-    // 验证 parent 是否存在（如果提供）
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    if let Some(ref pid) = parent_id {
-        {
-            let mut stmt = conn
-                .prepare("SELECT COUNT(1) FROM tree_nodes WHERE id = ?")
-                .map_err(|e| e.to_string())?;
-            let count: i64 = stmt
-                .query_row(params![pid], |r| r.get(0))
-                .map_err(|e| e.to_string())?;
-            if count == 0 {
-                return Err("parent node not found".to_string());
-            }
-        }
-    }
-
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    let order = order_index.unwrap_or(0);
-    db_models::insert_tree_node(
-        &tx,
-        &node_id,
-        parent_id.as_deref(),
+    let mut storage = Storage::open()?;
+    tree::create(
+        &mut storage,
         &name,
         &node_type,
         &scope,
-        order,
-        now,
+        parent_id.as_deref(),
+        order_index,
     )
-    .map_err(|e| e.to_string())?;
-
-    tx.commit().map_err(|e| e.to_string())?;
-
-    Ok(node_id)
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -75,394 +28,57 @@ pub fn update_tree_node(
     parent_id: Option<String>,
     order_index: Option<i64>,
 ) -> Result<(), String> {
-    let now = Utc::now().timestamp();
-
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    // 验证节点存在
-    {
-        let mut stmt = conn
-            .prepare("SELECT COUNT(1) FROM tree_nodes WHERE id = ?")
-            .map_err(|e| e.to_string())?;
-        let count: i64 = stmt
-            .query_row(params![&node_id], |r| r.get(0))
-            .map_err(|e| e.to_string())?;
-        if count == 0 {
-            return Err("node not found".to_string());
-        }
-    }
-
-    // 如果提供 parent，验证 parent 存在且不等于自身
-    if let Some(ref pid) = parent_id {
-        if pid == &node_id {
-            return Err("parent cannot be the node itself".to_string());
-        }
-        {
-            let mut stmt2 = conn
-                .prepare("SELECT COUNT(1) FROM tree_nodes WHERE id = ?")
-                .map_err(|e| e.to_string())?;
-            let count2: i64 = stmt2
-                .query_row(params![pid], |r| r.get(0))
-                .map_err(|e| e.to_string())?;
-            if count2 == 0 {
-                return Err("parent node not found".to_string());
-            }
-        }
-    }
-
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-    let order = order_index.unwrap_or(0);
-
-    db_models::update_tree_node(&tx, &node_id, parent_id.as_deref(), &name, order, now)
-        .map_err(|e| e.to_string())?;
-
-    tx.commit().map_err(|e| e.to_string())?;
-
-    Ok(())
+    let mut storage = Storage::open()?;
+    tree::update(
+        &mut storage,
+        &node_id,
+        &name,
+        parent_id.as_deref(),
+        order_index,
+    )
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub fn delete_tree_node(node_id: String) -> Result<(), String> {
-    let mut conn = get_connection().map_err(|e| e.to_string())?;
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-    // 保存要删除的文件路径列表
-    let mut files_to_delete: Vec<String> = Vec::new();
-
-    // 递归删除节点及其所有子节点和资源
-    fn delete_node_recursive(
-        tx: &rusqlite::Transaction,
-        node_id: &str,
-        files_to_delete: &mut Vec<String>,
-    ) -> Result<(), String> {
-        // 获取所有直接子节点
-        let child_ids = db_models::get_child_node_ids(tx, node_id)
-            .map_err(|e| e.to_string())?;
-
-        // 递归删除所有子节点
-        for child_id in child_ids {
-            delete_node_recursive(tx, &child_id, files_to_delete)?;
-        }
-
-        // 获取当前节点的所有挂载资源
-        let resources = db_models::get_node_resources(tx, node_id)
-            .map_err(|e| e.to_string())?;
-
-        // 删除每个资源的数据库记录，同时记录文件路径
-        for (resource_id, resource_type) in &resources {
-            match resource_type.as_str() {
-                "note" => {
-                    // 获取 note 的文件路径
-                    if let Ok((_id, content_path)) = db_models::get_note_detail(tx, resource_id) {
-                        files_to_delete.push(content_path);
-                    }
-                    // 删除 note 记录
-                    db_models::delete_note(tx, resource_id)
-                        .map_err(|e| e.to_string())?;
-                }
-                "snippet" => {
-                    // 删除 snippet 记录
-                    db_models::delete_snippet(tx, resource_id)
-                        .map_err(|e| e.to_string())?;
-                }
-                _ => {}
-            }
-        }
-
-        // 删除节点挂载的所有资源关联
-        db_models::delete_node_resources(tx, node_id)
-            .map_err(|e| e.to_string())?;
-
-        // 删除节点本身
-        db_models::delete_tree_node(tx, node_id)
-            .map_err(|e| e.to_string())?;
-
-        Ok(())
-    }
-
-    // 执行递归删除
-    delete_node_recursive(&tx, &node_id, &mut files_to_delete)?;
-
-    // 提交事务
-    tx.commit().map_err(|e| e.to_string())?;
-
-    // 删除对应的文件（在事务提交后）
-    for file_path in files_to_delete {
-        let _ = std::fs::remove_file(&file_path);
-    }
-
-    Ok(())
-}
-
-#[derive(serde::Serialize)]
-pub struct TreeResponseNode {
-    pub id: String,
-    pub label: String,
-    pub node_type: String,
-    pub resource_id: Option<String>,
-    pub resource_type: Option<String>,
-    pub children: Option<Vec<TreeResponseNode>>,
+    let mut storage = Storage::open()?;
+    tree::delete(&mut storage, &node_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub fn list_tree_nodes(scope: Option<String>) -> Result<Vec<TreeNode>, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-
-    let mut nodes: Vec<TreeNode> = Vec::new();
-
-    if let Some(s) = scope {
-        let mut stmt = conn
-            .prepare(
-                "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, tn.scope, tn.order_index, tn.description_note_id, nr.resource_id, nr.resource_type, tn.created_at, tn.updated_at
-                 FROM tree_nodes tn
-                 LEFT JOIN node_resources nr ON nr.node_id = tn.id
-                 WHERE tn.scope = ?
-                 ORDER BY tn.parent_id, tn.order_index",
-            )
-            .map_err(|e| e.to_string())?;
-
-        let rows = stmt
-            .query_map(params![s], |r| {
-                Ok(TreeNode {
-                    id: r.get(0)?,
-                    parent_id: r.get(1)?,
-                    name: r.get(2)?,
-                    node_type: r.get(3)?,
-                    scope: r.get(4)?,
-                    order_index: r.get(5)?,
-                    description_note_id: r.get(6)?,
-                    resource_id: r.get(7)?,
-                    resource_type: r.get(8)?,
-                    created_at: r.get(9)?,
-                    updated_at: r.get(10)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-
-        for row in rows {
-            nodes.push(row.map_err(|e| e.to_string())?);
-        }
-    } else {
-        let mut stmt = conn
-            .prepare(
-                "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, tn.scope, tn.order_index, tn.description_note_id, nr.resource_id, nr.resource_type, tn.created_at, tn.updated_at
-                 FROM tree_nodes tn
-                 LEFT JOIN node_resources nr ON nr.node_id = tn.id
-                 ORDER BY tn.parent_id, tn.order_index",
-            )
-            .map_err(|e| e.to_string())?;
-
-        let rows = stmt
-            .query_map([], |r| {
-                Ok(TreeNode {
-                    id: r.get(0)?,
-                    parent_id: r.get(1)?,
-                    name: r.get(2)?,
-                    node_type: r.get(3)?,
-                    scope: r.get(4)?,
-                    order_index: r.get(5)?,
-                    description_note_id: r.get(6)?,
-                    resource_id: r.get(7)?,
-                    resource_type: r.get(8)?,
-                    created_at: r.get(9)?,
-                    updated_at: r.get(10)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-
-        for row in rows {
-            nodes.push(row.map_err(|e| e.to_string())?);
-        }
-    }
-
-    Ok(nodes)
+    let storage = Storage::open()?;
+    tree::list(&storage, scope.as_deref()).map_err(|e| e.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub fn list_tree_nodes_tree(scope: Option<String>) -> Result<Vec<TreeResponseNode>, String> {
-    let conn = get_connection().map_err(|e| e.to_string())?;
-
-    // 读取扁平节点
-    let mut rows: Vec<(
-        String,
-        Option<String>,
-        String,
-        String,
-        Option<String>,
-        Option<String>,
-        i64,
-    )> = Vec::new();
-    if let Some(s) = scope {
-        let mut stmt = conn
-            .prepare(
-                "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, nr.resource_id, nr.resource_type, tn.order_index
-                 FROM tree_nodes tn
-                 LEFT JOIN node_resources nr ON nr.node_id = tn.id
-                 WHERE tn.scope = ?",
-            )
-            .map_err(|e| e.to_string())?;
-        let mapped = stmt
-            .query_map(params![s], |r| {
-                Ok((
-                    r.get(0)?,
-                    r.get(1)?,
-                    r.get(2)?,
-                    r.get(3)?,
-                    r.get(4)?,
-                    r.get(5)?,
-                    r.get(6)?,
-                ))
-            })
-            .map_err(|e| e.to_string())?;
-        for row in mapped {
-            rows.push(row.map_err(|e| e.to_string())?);
-        }
-    } else {
-        let mut stmt = conn
-            .prepare(
-                "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, nr.resource_id, nr.resource_type, tn.order_index
-                 FROM tree_nodes tn
-                 LEFT JOIN node_resources nr ON nr.node_id = tn.id",
-            )
-            .map_err(|e| e.to_string())?;
-        let mapped = stmt
-            .query_map([], |r| {
-                Ok((
-                    r.get(0)?,
-                    r.get(1)?,
-                    r.get(2)?,
-                    r.get(3)?,
-                    r.get(4)?,
-                    r.get(5)?,
-                    r.get(6)?,
-                ))
-            })
-            .map_err(|e| e.to_string())?;
-        for row in mapped {
-            rows.push(row.map_err(|e| e.to_string())?);
-        }
-    }
-
-    use std::collections::HashMap;
-
-    // 构建 id -> (name, parent, node_type, resource_id, resource_type, order)
-    let mut meta: HashMap<
-        String,
-        (
-            Option<String>,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            i64,
-        ),
-    > = HashMap::new();
-    for (id, parent, name, node_type, resource_id, resource_type, order) in rows.iter() {
-        meta.insert(
-            id.clone(),
-            (
-                parent.clone(),
-                name.clone(),
-                node_type.clone(),
-                resource_id.clone(),
-                resource_type.clone(),
-                *order,
-            ),
-        );
-    }
-
-    // parent -> children ids
-    let mut children_map: HashMap<Option<String>, Vec<String>> = HashMap::new();
-    for (id, parent, _name, _node_type, _resource_id, _resource_type, _order) in rows.iter() {
-        children_map
-            .entry(parent.clone())
-            .or_default()
-            .push(id.clone());
-    }
-
-    // sort children by order_index
-    for (_parent, child_ids) in children_map.iter_mut() {
-        child_ids.sort_by_key(|cid| meta.get(cid).map(|t| t.5).unwrap_or(0));
-    }
-
-    // build nodes recursively with cycle protection
-    fn build(
-        id: &String,
-        meta: &HashMap<
-            String,
-            (
-                Option<String>,
-                String,
-                String,
-                Option<String>,
-                Option<String>,
-                i64,
-            ),
-        >,
-        children_map: &HashMap<Option<String>, Vec<String>>,
-        visiting: &mut std::collections::HashSet<String>,
-    ) -> TreeResponseNode {
-        if visiting.contains(id) {
-            // cycle detected — stop the recursion
-            return TreeResponseNode {
-                id: id.clone(),
-                label: meta.get(id).map(|t| t.1.clone()).unwrap_or_default(),
-                node_type: meta.get(id).map(|t| t.2.clone()).unwrap_or_default(),
-                resource_id: meta.get(id).and_then(|t| t.3.clone()),
-                resource_type: meta.get(id).and_then(|t| t.4.clone()),
-                children: None,
-            };
-        }
-        visiting.insert(id.clone());
-
-        let label = meta.get(id).map(|t| t.1.clone()).unwrap_or_default();
-        let node_type = meta.get(id).map(|t| t.2.clone()).unwrap_or_default();
-        let resource_id = meta.get(id).and_then(|t| t.3.clone());
-        let resource_type = meta.get(id).and_then(|t| t.4.clone());
-        let child_ids = children_map.get(&Some(id.clone()));
-        let mut children_vec: Vec<TreeResponseNode> = Vec::new();
-        if let Some(child_ids) = child_ids {
-            for cid in child_ids {
-                children_vec.push(build(cid, meta, children_map, visiting));
-            }
-        }
-
-        visiting.remove(id);
-
-        TreeResponseNode {
-            id: id.clone(),
-            label,
-            node_type,
-            resource_id,
-            resource_type,
-            children: if children_vec.is_empty() {
-                None
-            } else {
-                Some(children_vec)
-            },
-        }
-    }
+    let storage = Storage::open()?;
+    tree::list_tree(&storage, scope.as_deref()).map_err(|e| e.to_string())
+}
 
-    // roots are children_map[None]
-    let mut roots: Vec<TreeResponseNode> = Vec::new();
-    if let Some(root_ids) = children_map.get(&None) {
-        let mut visiting = std::collections::HashSet::new();
-        for rid in root_ids {
-            roots.push(build(rid, &meta, &children_map, &mut visiting));
-        }
-    } else {
-        // No explicit roots: treat nodes without parent or invalid parent as roots
-        let mut root_candidates: Vec<String> = Vec::new();
-        for (id, (parent, _name, _node_type, _resource_id, _resource_type, _order)) in meta.iter() {
-            if parent.is_none() || !meta.contains_key(parent.as_ref().unwrap()) {
-                root_candidates.push(id.clone());
-            }
-        }
-        let mut visiting = std::collections::HashSet::new();
-        root_candidates.sort_by_key(|id| meta.get(id).map(|t| t.5).unwrap_or(0));
-        for rid in root_candidates {
-            roots.push(build(&rid, &meta, &children_map, &mut visiting));
-        }
-    }
+/// 分页列出直接子节点（侧边栏按需展开）；parent_id 为空时列出 scope 的根节点
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_children(
+    parent_id: Option<String>,
+    scope: Option<String>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+) -> Result<ChildrenPage, String> {
+    let storage = Storage::open()?;
+    tree::list_children(
+        &storage,
+        parent_id.as_deref(),
+        scope.as_deref(),
+        cursor.as_deref(),
+        limit,
+    )
+    .map_err(|e| e.to_string())
+}
 
-    Ok(roots)
+/// 重新读取单个节点及其直接子节点（新建、重命名、移动后局部刷新）
+#[tauri::command(rename_all = "snake_case")]
+pub fn refresh_tree_node(node_id: String, limit: Option<i64>) -> Result<NodeWithChildren, String> {
+    let storage = Storage::open()?;
+    tree::node_with_children(&storage, &node_id, limit).map_err(|e| e.to_string())
 }
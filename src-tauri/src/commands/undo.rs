@@ -0,0 +1,24 @@
+use crate::changelog::UndoAction;
+use crate::service::undo::{self, UndoState};
+use crate::service::Storage;
+
+/// 撤销最近一次操作；没有可撤销的操作时返回 None
+#[tauri::command(rename_all = "snake_case")]
+pub fn undo() -> Result<Option<UndoAction>, String> {
+    let mut storage = Storage::open()?;
+    undo::undo(&mut storage).map_err(|e| e.to_string())
+}
+
+/// 重做最近一次撤销的操作；没有可重做的操作时返回 None
+#[tauri::command(rename_all = "snake_case")]
+pub fn redo() -> Result<Option<UndoAction>, String> {
+    let mut storage = Storage::open()?;
+    undo::redo(&mut storage).map_err(|e| e.to_string())
+}
+
+/// 当前可撤销 / 可重做的操作
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_undo_state() -> Result<UndoState, String> {
+    let storage = Storage::open()?;
+    undo::state(&storage).map_err(|e| e.to_string())
+}
@@ -0,0 +1,79 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::app::db_path;
+use crate::db::{migrate, vault};
+
+#[derive(Serialize)]
+pub struct VaultStatus {
+    /// db.sqlite 是否以 SQLCipher 加密
+    pub encrypted: bool,
+    /// 是否已输入密钥（明文库始终为 true）
+    pub unlocked: bool,
+}
+
+fn require_key(key: &str) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("vault key is empty".to_string());
+    }
+    Ok(())
+}
+
+/// 查询数据库加密状态（前端启动时据此决定是否显示解锁界面）
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_vault_status() -> Result<VaultStatus, String> {
+    let encrypted = vault::is_encrypted(&db_path());
+    Ok(VaultStatus {
+        encrypted,
+        unlocked: !encrypted || vault::has_key(),
+    })
+}
+
+/// 输入密钥解锁加密库，并完成启动时被推迟的数据库初始化
+#[tauri::command(rename_all = "snake_case")]
+pub fn unlock_vault(app: AppHandle, key: String) -> Result<(), String> {
+    let conn = vault::open_with_key(&db_path(), Some(&key)).map_err(|e| e.to_string())?;
+    migrate::run(&conn).map_err(|e| e.to_string())?;
+
+    vault::set_key(Some(key));
+    crate::api::start_if_enabled(&app);
+    Ok(())
+}
+
+/// 将现有明文库原地加密
+#[tauri::command(rename_all = "snake_case")]
+pub fn encrypt_vault(key: String) -> Result<(), String> {
+    require_key(&key)?;
+    if vault::is_encrypted(&db_path()) {
+        return Err("vault is already encrypted".to_string());
+    }
+
+    vault::encrypt_in_place(&db_path(), &key).map_err(|e| e.to_string())?;
+    vault::set_key(Some(key));
+    Ok(())
+}
+
+/// 更换加密库密钥（需提供当前密钥）
+#[tauri::command(rename_all = "snake_case")]
+pub fn change_vault_key(current_key: String, new_key: String) -> Result<(), String> {
+    require_key(&new_key)?;
+    if !vault::is_encrypted(&db_path()) {
+        return Err("vault is not encrypted".to_string());
+    }
+
+    vault::rekey(&db_path(), &current_key, &new_key).map_err(|e| e.to_string())?;
+    vault::set_key(Some(new_key));
+    Ok(())
+}
+
+/// 将加密库原地解密回明文库（需提供当前密钥）
+#[tauri::command(rename_all = "snake_case")]
+pub fn decrypt_vault(key: String) -> Result<(), String> {
+    if !vault::is_encrypted(&db_path()) {
+        return Err("vault is not encrypted".to_string());
+    }
+
+    vault::decrypt_in_place(&db_path(), &key).map_err(|e| e.to_string())?;
+    vault::set_key(None);
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use tauri::AppHandle;
+
+use crate::vaults::{self, VaultInfo};
+
+/// 列出已登记的库（含当前打开的库）
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_vaults() -> Result<Vec<VaultInfo>, String> {
+    vaults::list()
+}
+
+/// 在指定目录新建（或登记已有的）库，不切换当前库
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_vault(name: String, path: String) -> Result<VaultInfo, String> {
+    vaults::create(&name, &path)
+}
+
+/// 切换到指定库；完成后发送 vault-changed 事件
+#[tauri::command(rename_all = "snake_case")]
+pub fn open_vault(app: AppHandle, vault_id: String) -> Result<VaultInfo, String> {
+    vaults::open(&app, &vault_id)
+}
+
+/// 从列表中移除库（不删除文件）
+#[tauri::command(rename_all = "snake_case")]
+pub fn close_vault(vault_id: String) -> Result<(), String> {
+    vaults::close(&vault_id)
+}
+
+/// 修改库的显示名称
+#[tauri::command(rename_all = "snake_case")]
+pub fn rename_vault(vault_id: String, name: String) -> Result<(), String> {
+    vaults::rename(&vault_id, &name)
+}
@@ -0,0 +1,89 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use zeroize::Zeroizing;
+
+/// 加密文件首行标记（其后为 base64(salt | nonce | ciphertext)，便于文本工具 / 版本管理处理）
+const HEADER: &str = "noto-encrypted:v1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// 由口令派生的 256 位密钥（离开作用域时清零）
+pub type NoteKey = Zeroizing<[u8; 32]>;
+
+/// 文件内容是否为加密格式
+pub fn is_encrypted(content: &str) -> bool {
+    content.starts_with(HEADER)
+}
+
+/// 生成随机 salt
+pub fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Argon2id 派生密钥（19 MiB / 2 轮，OWASP 推荐参数）
+pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<NoteKey, String> {
+    let params = Params::new(19 * 1024, 2, 1, Some(32)).map_err(|e| e.to_string())?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+/// 读取加密文件中的 salt（用于派生密钥）
+pub fn read_salt(content: &str) -> Result<[u8; SALT_LEN], String> {
+    let data = decode(content)?;
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[..SALT_LEN]);
+    Ok(salt)
+}
+
+/// 加密：每次使用新的随机 nonce
+pub fn encrypt(plaintext: &str, key: &NoteKey, salt: &[u8; SALT_LEN]) -> Result<String, String> {
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| "failed to encrypt note".to_string())?;
+
+    let mut data = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+
+    Ok(format!(
+        "{}\n{}\n",
+        HEADER,
+        base64::engine::general_purpose::STANDARD.encode(data)
+    ))
+}
+
+/// 解密；口令错误或内容被篡改时返回错误
+pub fn decrypt(content: &str, key: &NoteKey) -> Result<String, String> {
+    let data = decode(content)?;
+    let nonce = XNonce::from_slice(&data[SALT_LEN..SALT_LEN + NONCE_LEN]);
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    let plaintext = cipher
+        .decrypt(nonce, &data[SALT_LEN + NONCE_LEN..])
+        .map_err(|_| "wrong passphrase or corrupted note".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+fn decode(content: &str) -> Result<Vec<u8>, String> {
+    let body = content
+        .strip_prefix(HEADER)
+        .ok_or_else(|| "note is not encrypted".to_string())?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(body.trim())
+        .map_err(|_| "corrupted encrypted note".to_string())?;
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err("corrupted encrypted note".to_string());
+    }
+    Ok(data)
+}
@@ -0,0 +1,72 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::crypto::note::NoteKey;
+
+/// 默认解锁时长：无操作 5 分钟后自动锁定
+pub const DEFAULT_UNLOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+struct UnlockedNote {
+    key: NoteKey,
+    salt: [u8; 16],
+    timeout: Duration,
+    last_used: Instant,
+}
+
+/// 已解锁的加密 note（仅保存在内存中，应用退出即失效）
+static SESSIONS: Lazy<Mutex<HashMap<String, UnlockedNote>>> = Lazy::new(Default::default);
+
+/// 记录解锁状态
+pub fn unlock(note_id: &str, key: NoteKey, salt: [u8; 16], timeout: Duration) {
+    if let Ok(mut sessions) = SESSIONS.lock() {
+        sessions.insert(
+            note_id.to_string(),
+            UnlockedNote {
+                key,
+                salt,
+                timeout,
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
+
+/// 取出密钥与 salt；已超时则移除并返回 None。每次访问都会刷新计时
+pub fn key_for(note_id: &str) -> Option<(NoteKey, [u8; 16])> {
+    let mut sessions = SESSIONS.lock().ok()?;
+    let session = sessions.get_mut(note_id)?;
+
+    if session.last_used.elapsed() > session.timeout {
+        sessions.remove(note_id);
+        return None;
+    }
+
+    session.last_used = Instant::now();
+    Some((session.key.clone(), session.salt))
+}
+
+/// 是否处于解锁状态（不刷新计时）
+pub fn is_unlocked(note_id: &str) -> bool {
+    let Ok(sessions) = SESSIONS.lock() else {
+        return false;
+    };
+    sessions
+        .get(note_id)
+        .is_some_and(|s| s.last_used.elapsed() <= s.timeout)
+}
+
+/// 锁定单个 note
+pub fn lock(note_id: &str) {
+    if let Ok(mut sessions) = SESSIONS.lock() {
+        sessions.remove(note_id);
+    }
+}
+
+/// 锁定全部 note
+pub fn lock_all() {
+    if let Ok(mut sessions) = SESSIONS.lock() {
+        sessions.clear();
+    }
+}
@@ -1,6 +1,14 @@
 use rusqlite::Connection;
+use std::time::Duration;
+
+use crate::db::vault;
 
 pub fn get_connection() -> anyhow::Result<Connection> {
     let db_path = crate::app::db_path();
-    Ok(Connection::open(db_path)?)
+    let conn = Connection::open(&db_path)?;
+    // 应用与命令行工具可能同时写入：等待对方的写事务结束而不是直接报错
+    conn.busy_timeout(Duration::from_secs(5))?;
+    // 加密库：每个连接都需要先设置密钥
+    vault::apply_key(&conn, &db_path)?;
+    Ok(conn)
 }
@@ -1,4 +1,11 @@
-use rusqlite::Connection;
+use rusqlite::{params, Connection};
+
+use uuid::Uuid;
+
+use crate::db::models::TASK_POSITION_GAP;
+use crate::lang::detect::default_file_name;
+use crate::sync::records::{key_expr, SYNC_TABLES};
+use crate::sync::SYNC_ENABLED_KEY;
 
 pub fn run(conn: &Connection) -> anyhow::Result<()> {
     // 确保外键启用
@@ -7,5 +14,208 @@ pub fn run(conn: &Connection) -> anyhow::Result<()> {
     // 执行 schema
     conn.execute_batch(include_str!("../../schema.sql"))?;
 
+    // 旧库补列（schema.sql 只负责新建表，已有表需要在这里追加）
+    upgrade_tasks_position(conn)?;
+    add_column_if_missing(
+        conn,
+        "snippets",
+        "language_detected",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    if add_column_if_missing(
+        conn,
+        "snippets",
+        "language_confident",
+        "INTEGER NOT NULL DEFAULT 0",
+    )? {
+        // 已有的 language 均为用户手动指定
+        conn.execute_batch(
+            "UPDATE snippets SET language_confident = 1 WHERE language IS NOT NULL;",
+        )?;
+    }
+    add_column_if_missing(conn, "snippets", "use_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "snippets", "last_used_at", "INTEGER")?;
+    add_column_if_missing(conn, "snippets", "prefix", "TEXT")?;
+    add_column_if_missing(conn, "snippets", "description", "TEXT")?;
+    add_column_if_missing(conn, "snippets", "scope", "TEXT")?;
+
+    add_column_if_missing(conn, "notes", "encrypted", "INTEGER NOT NULL DEFAULT 0")?;
+
+    // 旧的单内容 snippet 补建主文件
+    backfill_snippet_files(conn)?;
+
+    create_sync_triggers(conn)?;
+
+    // 偏好设置：旧版本与旧格式的设置升级到当前结构
+    crate::settings::upgrade(conn).map_err(anyhow::Error::msg)?;
+
+    Ok(())
+}
+
+/// 同步变更捕获：启用同步时，参与同步的表每次增删改都写入 sync_log
+///
+/// note_tags / note_aliases 的修改记为所属 note 的修改
+fn create_sync_triggers(conn: &Connection) -> rusqlite::Result<()> {
+    let mut sql = String::new();
+    let mut add_trigger = |table: &str, event: &str, target: &str, key: &str| {
+        sql.push_str(&format!(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS sync_{table}_{event_lower}
+            AFTER {event} ON {table}
+            WHEN EXISTS (SELECT 1 FROM settings WHERE key = '{enabled_key}')
+            BEGIN
+                INSERT INTO sync_log (table_name, row_key) VALUES ('{target}', {key});
+            END;
+            "#,
+            table = table,
+            event = event,
+            event_lower = event.to_lowercase(),
+            target = target,
+            key = key,
+            enabled_key = SYNC_ENABLED_KEY,
+        ));
+    };
+
+    for table in SYNC_TABLES {
+        add_trigger(table.name, "INSERT", table.name, &key_expr(table, "NEW"));
+        add_trigger(table.name, "UPDATE", table.name, &key_expr(table, "NEW"));
+        add_trigger(table.name, "DELETE", table.name, &key_expr(table, "OLD"));
+    }
+    for table in ["note_tags", "note_aliases"] {
+        add_trigger(table, "INSERT", "notes", "NEW.note_id");
+        add_trigger(table, "DELETE", "notes", "OLD.note_id");
+    }
+
+    conn.execute_batch(&sql)
+}
+
+/// 判断某张表是否已有指定列
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let rows = stmt.query_map([], |r| r.get::<_, String>(1))?;
+
+    for row in rows {
+        if row? == column {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// 列不存在时追加（definition 为列类型及约束），返回是否新增
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<bool> {
+    if has_column(conn, table, column)? {
+        return Ok(false);
+    }
+
+    conn.execute_batch(&format!(
+        "ALTER TABLE {} ADD COLUMN {} {};",
+        table, column, definition
+    ))?;
+
+    Ok(true)
+}
+
+/// tasks.position：看板内同一 status 下的排序位置
+///
+/// 旧数据按 created_at DESC 依次分配间隔位置，保持原有展示顺序
+fn upgrade_tasks_position(conn: &Connection) -> rusqlite::Result<()> {
+    if !has_column(conn, "tasks", "position")? {
+        conn.execute_batch("ALTER TABLE tasks ADD COLUMN position REAL NOT NULL DEFAULT 0;")?;
+
+        conn.execute(
+            r#"
+            UPDATE tasks
+            SET position = (
+                SELECT ranked.rn * ?
+                FROM (
+                    SELECT id, ROW_NUMBER() OVER (
+                        PARTITION BY node_id, status
+                        ORDER BY created_at DESC
+                    ) AS rn
+                    FROM tasks
+                ) AS ranked
+                WHERE ranked.id = tasks.id
+            )
+            "#,
+            params![TASK_POSITION_GAP],
+        )?;
+    }
+
+    // 新库/旧库都需要：索引依赖 position 列，不能放进 schema.sql
+    conn.execute_batch(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_tasks_node_status_position
+        ON tasks(node_id, status, position);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// 为没有任何文件的 snippet 创建主文件（内容 / 语言取自 snippets 表）
+fn backfill_snippet_files(conn: &Connection) -> rusqlite::Result<()> {
+    let rows: Vec<SnippetWithoutFiles> = {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT s.id, s.language, s.language_detected, s.language_confident,
+                   s.content, s.created_at, s.updated_at
+            FROM snippets s
+            WHERE NOT EXISTS (SELECT 1 FROM snippet_files f WHERE f.snippet_id = s.id)
+            "#,
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(SnippetWithoutFiles {
+                id: r.get(0)?,
+                language: r.get(1)?,
+                language_detected: r.get(2)?,
+                language_confident: r.get(3)?,
+                content: r.get(4)?,
+                created_at: r.get(5)?,
+                updated_at: r.get(6)?,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for row in rows {
+        conn.execute(
+            r#"
+            INSERT INTO snippet_files (
+                id, snippet_id, file_name, language, language_detected, language_confident,
+                content, order_index, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?)
+            "#,
+            params![
+                Uuid::new_v4().to_string(),
+                row.id,
+                default_file_name(row.language.as_deref()),
+                row.language,
+                row.language_detected,
+                row.language_confident,
+                row.content,
+                row.created_at,
+                row.updated_at,
+            ],
+        )?;
+    }
+
     Ok(())
 }
+
+struct SnippetWithoutFiles {
+    id: String,
+    language: Option<String>,
+    language_detected: bool,
+    language_confident: bool,
+    content: String,
+    created_at: i64,
+    updated_at: i64,
+}
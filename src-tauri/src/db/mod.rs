@@ -1,6 +1,7 @@
 pub mod connection;
 pub mod migrate;
 pub mod models;
+pub mod vault;
 
 pub fn init_db() -> tauri::Result<()> {
     let conn = connection::get_connection().expect("failed to open database");
@@ -1,4 +1,9 @@
-use rusqlite::{params, Transaction};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+/// 任务看板排序的位置间隔
+///
+/// 新位置取相邻两项的中点，间隔耗尽时才整列重排
+pub const TASK_POSITION_GAP: f64 = 1024.0;
 
 /// 插入一条 note 记录
 ///
@@ -116,28 +121,51 @@ pub fn insert_snippet(
     Ok(())
 }
 
+/// snippets 表的一行
+pub struct SnippetRecord {
+    pub id: String,
+    pub title: String,
+    pub language: Option<String>,
+    pub language_detected: bool,
+    pub language_confident: bool,
+    pub content: String,
+    pub use_count: i64,
+    pub last_used_at: Option<i64>,
+    pub prefix: Option<String>,
+    pub description: Option<String>,
+    pub scope: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
 /// 获取单个 snippet 详情
-pub fn get_snippet(
-    tx: &Transaction,
-    snippet_id: &str,
-) -> rusqlite::Result<(String, String, Option<String>, String, i64, i64)> {
+pub fn get_snippet(tx: &Transaction, snippet_id: &str) -> rusqlite::Result<SnippetRecord> {
     let mut stmt = tx.prepare(
         r#"
-        SELECT id, title, language, content, created_at, updated_at
+        SELECT id, title, language, language_detected, language_confident,
+               content, use_count, last_used_at, prefix, description, scope,
+               created_at, updated_at
         FROM snippets
         WHERE id = ?
         "#,
     )?;
 
     let row = stmt.query_row(params![snippet_id], |r| {
-        Ok((
-            r.get(0)?,
-            r.get(1)?,
-            r.get(2)?,
-            r.get(3)?,
-            r.get(4)?,
-            r.get(5)?,
-        ))
+        Ok(SnippetRecord {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            language: r.get(2)?,
+            language_detected: r.get(3)?,
+            language_confident: r.get(4)?,
+            content: r.get(5)?,
+            use_count: r.get(6)?,
+            last_used_at: r.get(7)?,
+            prefix: r.get(8)?,
+            description: r.get(9)?,
+            scope: r.get(10)?,
+            created_at: r.get(11)?,
+            updated_at: r.get(12)?,
+        })
     })?;
 
     Ok(row)
@@ -164,8 +192,70 @@ pub fn update_snippet(
     Ok(())
 }
 
-/// 删除 snippet 本体（不包含 tree / node_resources）
+/// 更新 snippet 的语言识别标记（detected：自动识别得出；confident：结果可信）
+pub fn update_snippet_language(
+    tx: &Transaction,
+    snippet_id: &str,
+    language: Option<&str>,
+    detected: bool,
+    confident: bool,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE snippets
+        SET language = ?, language_detected = ?, language_confident = ?
+        WHERE id = ?
+        "#,
+        params![language, detected, confident, snippet_id],
+    )?;
+
+    Ok(())
+}
+
+/// 更新 snippet 的编辑器元信息（VS Code 的 prefix / description / scope）
+pub fn update_snippet_meta(
+    tx: &Transaction,
+    snippet_id: &str,
+    prefix: Option<&str>,
+    description: Option<&str>,
+    scope: Option<&str>,
+    now: i64,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE snippets
+        SET prefix = ?, description = ?, scope = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        params![prefix, description, scope, now, snippet_id],
+    )?;
+
+    Ok(())
+}
+
+/// 记录一次 snippet 使用（模板渲染）
+pub fn record_snippet_usage(tx: &Transaction, snippet_id: &str, now: i64) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE snippets
+        SET use_count = use_count + 1, last_used_at = ?
+        WHERE id = ?
+        "#,
+        params![now, snippet_id],
+    )?;
+
+    Ok(())
+}
+
+/// 删除 snippet 本体及其文件（不包含 tree / node_resources）
 pub fn delete_snippet(tx: &Transaction, snippet_id: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        DELETE FROM snippet_files WHERE snippet_id = ?
+        "#,
+        params![snippet_id],
+    )?;
+
     tx.execute(
         r#"
         DELETE FROM snippets WHERE id = ?
@@ -176,6 +266,156 @@ pub fn delete_snippet(tx: &Transaction, snippet_id: &str) -> rusqlite::Result<()
     Ok(())
 }
 
+/// snippet_files 表的一行
+#[derive(serde::Serialize)]
+pub struct SnippetFileRecord {
+    pub id: String,
+    pub snippet_id: String,
+    pub file_name: String,
+    pub language: Option<String>,
+    pub language_detected: bool,
+    pub language_confident: bool,
+    pub content: String,
+    pub order_index: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const SNIPPET_FILE_COLUMNS: &str = "id, snippet_id, file_name, language, language_detected, language_confident, content, order_index, created_at, updated_at";
+
+fn map_snippet_file(r: &rusqlite::Row) -> rusqlite::Result<SnippetFileRecord> {
+    Ok(SnippetFileRecord {
+        id: r.get(0)?,
+        snippet_id: r.get(1)?,
+        file_name: r.get(2)?,
+        language: r.get(3)?,
+        language_detected: r.get(4)?,
+        language_confident: r.get(5)?,
+        content: r.get(6)?,
+        order_index: r.get(7)?,
+        created_at: r.get(8)?,
+        updated_at: r.get(9)?,
+    })
+}
+
+/// 插入一个 snippet 文件
+pub fn insert_snippet_file(tx: &Transaction, file: &SnippetFileRecord) -> rusqlite::Result<()> {
+    tx.execute(
+        &format!(
+            "INSERT INTO snippet_files ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            SNIPPET_FILE_COLUMNS
+        ),
+        params![
+            file.id,
+            file.snippet_id,
+            file.file_name,
+            file.language,
+            file.language_detected,
+            file.language_confident,
+            file.content,
+            file.order_index,
+            file.created_at,
+            file.updated_at
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// 获取单个 snippet 文件
+pub fn get_snippet_file(tx: &Transaction, file_id: &str) -> rusqlite::Result<SnippetFileRecord> {
+    tx.query_row(
+        &format!(
+            "SELECT {} FROM snippet_files WHERE id = ?",
+            SNIPPET_FILE_COLUMNS
+        ),
+        params![file_id],
+        map_snippet_file,
+    )
+}
+
+/// 按顺序列出 snippet 的所有文件
+pub fn list_snippet_files(
+    tx: &Transaction,
+    snippet_id: &str,
+) -> rusqlite::Result<Vec<SnippetFileRecord>> {
+    let mut stmt = tx.prepare(&format!(
+        "SELECT {} FROM snippet_files WHERE snippet_id = ? ORDER BY order_index, created_at",
+        SNIPPET_FILE_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map(params![snippet_id], map_snippet_file)?;
+
+    let mut files = Vec::new();
+    for row in rows {
+        files.push(row?);
+    }
+
+    Ok(files)
+}
+
+/// 更新 snippet 文件（名称 / 语言 / 内容 / 顺序）
+pub fn update_snippet_file(
+    tx: &Transaction,
+    file: &SnippetFileRecord,
+    now: i64,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE snippet_files
+        SET file_name = ?, language = ?, language_detected = ?, language_confident = ?,
+            content = ?, order_index = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        params![
+            file.file_name,
+            file.language,
+            file.language_detected,
+            file.language_confident,
+            file.content,
+            file.order_index,
+            now,
+            file.id
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// 删除单个 snippet 文件
+pub fn delete_snippet_file(tx: &Transaction, file_id: &str) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM snippet_files WHERE id = ?", params![file_id])?;
+    Ok(())
+}
+
+/// 将主文件（排序第一的文件）的内容 / 语言同步回 snippets 表
+///
+/// snippets.content / language 始终镜像主文件，旧接口与搜索、渲染可以继续只读 snippets 表
+pub fn sync_snippet_primary_file(
+    tx: &Transaction,
+    snippet_id: &str,
+    now: i64,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE snippets
+        SET (language, language_detected, language_confident, content) = (
+                SELECT language, language_detected, language_confident, content
+                FROM snippet_files
+                WHERE snippet_id = snippets.id
+                ORDER BY order_index, created_at
+                LIMIT 1
+            ),
+            updated_at = ?
+        WHERE id = ?
+          AND EXISTS (SELECT 1 FROM snippet_files WHERE snippet_id = snippets.id)
+        "#,
+        params![now, snippet_id],
+    )?;
+
+    Ok(())
+}
+
 /// 插入一个 tree_node（Snippets Scope）
 ///
 /// 约定：
@@ -354,12 +594,81 @@ pub fn get_note_detail(tx: &Transaction, note_id: &str) -> rusqlite::Result<(Str
     Ok(row)
 }
 
-/// 删除指定的 note 记录
+/// 获取 note 标题（不存在时返回 None）
+pub fn get_note_title(conn: &Connection, note_id: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT title FROM notes WHERE id = ?",
+        params![note_id],
+        |r| r.get(0),
+    )
+    .optional()
+}
+
+/// 删除指定的 note 记录及其标签、别名、日记登记
+///
+/// 普通连接未启用外键约束，子表需显式删除
 pub fn delete_note(tx: &Transaction, note_id: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "DELETE FROM daily_notes WHERE note_id = ?",
+        params![note_id],
+    )?;
+    tx.execute("DELETE FROM note_tags WHERE note_id = ?", params![note_id])?;
+    tx.execute(
+        "DELETE FROM note_aliases WHERE note_id = ?",
+        params![note_id],
+    )?;
     tx.execute("DELETE FROM notes WHERE id = ?", params![note_id])?;
     Ok(())
 }
 
+/// 获取 note 的标签（按字母序）
+pub fn get_note_tags(conn: &Connection, note_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM note_tags WHERE note_id = ? ORDER BY tag")?;
+    let rows = stmt.query_map(params![note_id], |r| r.get::<_, String>(0))?;
+    rows.collect()
+}
+
+/// 覆盖 note 的标签
+pub fn set_note_tags(tx: &Transaction, note_id: &str, tags: &[String]) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM note_tags WHERE note_id = ?", params![note_id])?;
+    for tag in tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag) VALUES (?, ?)",
+            params![note_id, tag],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 获取 note 的别名（按字母序）
+pub fn get_note_aliases(conn: &Connection, note_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT alias FROM note_aliases WHERE note_id = ? ORDER BY alias")?;
+    let rows = stmt.query_map(params![note_id], |r| r.get::<_, String>(0))?;
+    rows.collect()
+}
+
+/// 覆盖 note 的别名
+pub fn set_note_aliases(
+    tx: &Transaction,
+    note_id: &str,
+    aliases: &[String],
+) -> rusqlite::Result<()> {
+    tx.execute(
+        "DELETE FROM note_aliases WHERE note_id = ?",
+        params![note_id],
+    )?;
+    for alias in aliases {
+        tx.execute(
+            "INSERT OR IGNORE INTO note_aliases (note_id, alias) VALUES (?, ?)",
+            params![note_id, alias],
+        )?;
+    }
+
+    Ok(())
+}
+
 /// 获取节点的所有直接子节点ID
 pub fn get_child_node_ids(tx: &Transaction, node_id: &str) -> rusqlite::Result<Vec<String>> {
     let mut stmt = tx.prepare("SELECT id FROM tree_nodes WHERE parent_id = ?")?;
@@ -373,3 +682,92 @@ pub fn get_child_node_ids(tx: &Transaction, node_id: &str) -> rusqlite::Result<V
 
     Ok(child_ids)
 }
+
+/// 获取节点的描述 note id（节点不存在时返回 QueryReturnedNoRows）
+pub fn get_node_description_note_id(
+    tx: &Transaction,
+    node_id: &str,
+) -> rusqlite::Result<Option<String>> {
+    tx.query_row(
+        "SELECT description_note_id FROM tree_nodes WHERE id = ?",
+        params![node_id],
+        |r| r.get(0),
+    )
+}
+
+/// 设置 / 清除节点的描述 note
+pub fn set_node_description_note(
+    tx: &Transaction,
+    node_id: &str,
+    note_id: Option<&str>,
+    now: i64,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        r#"
+        UPDATE tree_nodes
+        SET description_note_id = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        params![note_id, now, node_id],
+    )?;
+
+    Ok(())
+}
+
+/// 清除所有引用该 note 作为描述的节点（note 被删除前调用）
+pub fn clear_description_note_refs(tx: &Transaction, note_id: &str) -> rusqlite::Result<()> {
+    tx.execute(
+        "UPDATE tree_nodes SET description_note_id = NULL WHERE description_note_id = ?",
+        params![note_id],
+    )?;
+
+    Ok(())
+}
+
+/// note 是否仍被引用（挂载到树节点，或作为某个节点的描述）
+pub fn is_note_referenced(tx: &Transaction, note_id: &str) -> rusqlite::Result<bool> {
+    let count: i64 = tx.query_row(
+        r#"
+        SELECT
+            (SELECT COUNT(1) FROM node_resources
+             WHERE resource_type = 'note' AND resource_id = ?1)
+          + (SELECT COUNT(1) FROM tree_nodes
+             WHERE description_note_id = ?1)
+        "#,
+        params![note_id],
+        |r| r.get(0),
+    )?;
+
+    Ok(count > 0)
+}
+
+/// 读取设置项（不存在时返回 None）
+pub fn get_setting(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?",
+        params![key],
+        |r| r.get(0),
+    )
+    .optional()
+}
+
+/// 写入设置项；value 为 None 时删除该项
+pub fn set_setting(
+    conn: &Connection,
+    key: &str,
+    value: Option<&str>,
+    now: i64,
+) -> rusqlite::Result<()> {
+    match value {
+        Some(value) => conn.execute(
+            r#"
+            INSERT INTO settings (key, value, updated_at) VALUES (?, ?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+            params![key, value, now],
+        )?,
+        None => conn.execute("DELETE FROM settings WHERE key = ?", params![key])?,
+    };
+
+    Ok(())
+}
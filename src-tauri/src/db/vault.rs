@@ -0,0 +1,122 @@
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use zeroize::Zeroizing;
+
+/// 明文 SQLite 文件头；SQLCipher 加密后文件头为随机 salt
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// 当前库密钥（仅保存在内存中）
+static VAULT_KEY: Lazy<RwLock<Option<Zeroizing<String>>>> = Lazy::new(Default::default);
+
+/// 数据库文件是否为加密格式（文件不存在或为空时视为明文）
+pub fn is_encrypted(db_path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match fs::File::open(db_path).and_then(|mut f| f.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+/// 加密库且尚未输入密钥
+pub fn is_locked(db_path: &Path) -> bool {
+    !has_key() && is_encrypted(db_path)
+}
+
+pub fn has_key() -> bool {
+    VAULT_KEY.read().map(|k| k.is_some()).unwrap_or(false)
+}
+
+/// 设置 / 清除内存中的密钥
+pub fn set_key(key: Option<String>) {
+    if let Ok(mut guard) = VAULT_KEY.write() {
+        *guard = key.map(Zeroizing::new);
+    }
+}
+
+/// 新打开的连接上应用密钥；加密库未解锁时返回错误
+pub fn apply_key(conn: &Connection, db_path: &Path) -> anyhow::Result<()> {
+    let guard = VAULT_KEY
+        .read()
+        .map_err(|_| anyhow::anyhow!("vault key lock poisoned"))?;
+    match guard.as_ref() {
+        Some(key) => conn.pragma_update(None, "key", key.as_str())?,
+        None if is_encrypted(db_path) => anyhow::bail!("vault is locked"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// 以指定密钥打开并校验（密钥错误时 SQLCipher 在首次读取时报错）
+pub fn open_with_key(db_path: &Path, key: Option<&str>) -> anyhow::Result<Connection> {
+    let conn = Connection::open(db_path)?;
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+    }
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |r| {
+        r.get::<_, i64>(0)
+    })
+    .map_err(|_| anyhow::anyhow!("wrong vault key"))?;
+    Ok(conn)
+}
+
+/// 明文库原地加密：sqlcipher_export 导出到临时文件后替换原文件
+///
+/// 库正在被其他操作或其他进程使用时拒绝执行
+pub fn encrypt_in_place(db_path: &Path, key: &str) -> anyhow::Result<()> {
+    let _lock = crate::lock::acquire_exclusive()?;
+    let conn = open_with_key(db_path, None)?;
+    export_and_replace(conn, db_path, key)
+}
+
+/// 加密库原地解密为明文库；库正在被使用时拒绝执行
+pub fn decrypt_in_place(db_path: &Path, key: &str) -> anyhow::Result<()> {
+    let _lock = crate::lock::acquire_exclusive()?;
+    let conn = open_with_key(db_path, Some(key))?;
+    export_and_replace(conn, db_path, "")
+}
+
+/// 更换密钥（PRAGMA rekey 原地重新加密）；库正在被使用时拒绝执行
+pub fn rekey(db_path: &Path, current_key: &str, new_key: &str) -> anyhow::Result<()> {
+    let _lock = crate::lock::acquire_exclusive()?;
+    let conn = open_with_key(db_path, Some(current_key))?;
+    conn.pragma_update(None, "rekey", new_key)?;
+    Ok(())
+}
+
+/// 导出到 `<db>.tmp`（target_key 为空表示明文），成功后原子替换原文件
+///
+/// 导出与替换期间在原库上持有 EXCLUSIVE 事务，其他连接的写入等待或失败，不会在导出后写入旧文件而丢失
+fn export_and_replace(conn: Connection, db_path: &Path, target_key: &str) -> anyhow::Result<()> {
+    let tmp_path = PathBuf::from(format!("{}.tmp", db_path.display()));
+    if tmp_path.exists() {
+        fs::remove_file(&tmp_path)?;
+    }
+
+    // ATTACH / DETACH 不能在事务内执行
+    conn.execute(
+        "ATTACH DATABASE ? AS vault_export KEY ?",
+        params![tmp_path.to_string_lossy(), target_key],
+    )?;
+    let result = (|| -> anyhow::Result<()> {
+        conn.execute_batch("BEGIN EXCLUSIVE;")?;
+        conn.query_row("SELECT sqlcipher_export('vault_export')", [], |_| Ok(()))?;
+        fs::rename(&tmp_path, db_path)?;
+        Ok(())
+    })();
+    let _ = conn.execute_batch(if result.is_ok() {
+        "COMMIT;"
+    } else {
+        "ROLLBACK;"
+    });
+    let _ = conn.execute_batch("DETACH DATABASE vault_export;");
+    drop(conn);
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
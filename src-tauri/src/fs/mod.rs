@@ -2,19 +2,13 @@ pub mod attachments;
 pub mod notes;
 
 use std::fs;
-use tauri::{AppHandle, Manager};
-
-pub fn init_dirs(app: &AppHandle) -> tauri::Result<()> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .expect("failed to get app data dir");
+use std::path::Path;
+/// 创建数据目录下的子目录
+pub fn create_dirs(app_dir: &Path) -> std::io::Result<()> {
     let dirs = ["notes", "attachments/images", "attachments/files", "cache"];
 
     for dir in dirs {
-        let mut path = app_dir.clone();
-        path.push(dir);
-        fs::create_dir_all(path)?;
+        fs::create_dir_all(app_dir.join(dir))?;
     }
 
     Ok(())
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// 创建一个新的 Note Markdown 文件
 ///
@@ -24,6 +24,24 @@ pub fn create_note_file(
     Ok(file_path)
 }
 
+/// Note 文件路径：app_data_dir/notes/<note_id>.md
+pub fn note_file_path(app_data_dir: &Path, note_id: &str) -> PathBuf {
+    app_data_dir.join("notes").join(format!("{}.md", note_id))
+}
+
+/// 以指定内容创建 Note Markdown 文件（模板实例化）
+pub fn create_note_file_with_content(
+    app_data_dir: &Path,
+    note_id: &str,
+    content: &str,
+) -> Result<PathBuf, std::io::Error> {
+    let file_path = note_file_path(app_data_dir, note_id);
+
+    fs::write(&file_path, content)?;
+
+    Ok(file_path)
+}
+
 /// 删除 Note 文件（用于失败回滚）
 ///
 /// ⚠️
@@ -0,0 +1,194 @@
+pub mod repo;
+
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::db::connection::get_connection;
+use crate::db::models::{get_note_title, get_setting, set_setting};
+
+/// 是否启用 notes/ 版本管理
+pub const HISTORY_ENABLED_KEY: &str = "history.enabled";
+/// 自动提交的合并窗口：窗口内的修改合并为一次提交
+const BATCH_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Delete,
+}
+
+impl ChangeKind {
+    fn verb(self) -> &'static str {
+        match self {
+            ChangeKind::Create => "Create",
+            ChangeKind::Update => "Update",
+            ChangeKind::Delete => "Delete",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Change {
+    kind: ChangeKind,
+    note_id: String,
+    title: Option<String>,
+}
+
+#[derive(Default)]
+struct Pending {
+    changes: Vec<Change>,
+    /// 是否已有等待提交的后台线程
+    scheduled: bool,
+}
+
+static PENDING: Lazy<Mutex<Pending>> = Lazy::new(Default::default);
+/// 串行化提交，避免并发写 index（其他进程的提交由库锁互斥）
+static COMMIT_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn is_enabled() -> bool {
+    get_connection()
+        .ok()
+        .and_then(|conn| get_setting(&conn, HISTORY_ENABLED_KEY).ok().flatten())
+        .is_some_and(|v| v == "1")
+}
+
+/// 记录一次 note 变更；窗口期结束后在后台合并提交
+///
+/// title 为空时按 note_id 从数据库读取（删除前需由调用方传入）
+pub fn record(kind: ChangeKind, note_id: &str, title: Option<&str>) {
+    if !is_enabled() {
+        return;
+    }
+
+    let title = title.map(str::to_string).or_else(|| {
+        let conn = get_connection().ok()?;
+        get_note_title(&conn, note_id).ok().flatten()
+    });
+
+    let Ok(mut pending) = PENDING.lock() else {
+        return;
+    };
+    pending.changes.push(Change {
+        kind,
+        note_id: note_id.to_string(),
+        title,
+    });
+
+    if !pending.scheduled {
+        pending.scheduled = true;
+        thread::spawn(|| {
+            thread::sleep(BATCH_DELAY);
+            let _ = flush();
+        });
+    }
+}
+
+/// 立即提交等待中的变更（也会提交应用外对 notes/ 的修改）
+pub fn flush() -> Result<(), String> {
+    let _guard = COMMIT_LOCK.lock().map_err(|e| e.to_string())?;
+
+    let changes = {
+        let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+        pending.scheduled = false;
+        std::mem::take(&mut pending.changes)
+    };
+
+    if !is_enabled() {
+        return Ok(());
+    }
+
+    let _vault = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let repo = repo::open_or_init(&crate::app::data_dir()).map_err(|e| e.to_string())?;
+    repo::commit_all(&repo, &commit_message(&changes)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 启用版本管理：初始化仓库并提交当前全部 note
+///
+/// 仓库保存的是 notes/ 下的文件原文：已加密的 note 只会提交密文，
+/// 但加密之前提交过的明文版本会留在历史中，因此加密 note 时需调用 [`forget`] 清除
+pub fn enable() -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let _guard = COMMIT_LOCK.lock().map_err(|e| e.to_string())?;
+    let _vault = crate::lock::acquire().map_err(|e| e.to_string())?;
+
+    let repo = repo::open_or_init(&crate::app::data_dir()).map_err(|e| e.to_string())?;
+    repo::commit_all(&repo, "Enable note history").map_err(|e| e.to_string())?;
+
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    set_setting(&conn, HISTORY_ENABLED_KEY, Some("1"), now).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 从版本历史中清除 note 的全部旧版本（停用后仓库仍在时也清除）
+pub fn forget(note_id: &str) -> Result<(), String> {
+    let _guard = COMMIT_LOCK.lock().map_err(|e| e.to_string())?;
+    let _vault = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let Ok(repo) = git2::Repository::open(crate::app::data_dir()) else {
+        return Ok(());
+    };
+    repo::purge_note(&repo, note_id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 停用版本管理：先提交等待中的变更，仓库保留
+pub fn disable() -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    flush()?;
+
+    let _vault = crate::lock::acquire().map_err(|e| e.to_string())?;
+    let conn = get_connection().map_err(|e| e.to_string())?;
+    set_setting(&conn, HISTORY_ENABLED_KEY, None, now).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 合并同一 note 的多次变更，生成提交说明
+///
+/// - 单个 note：`Update note "标题"`
+/// - 多个 note：首行汇总，正文逐条列出
+fn commit_message(changes: &[Change]) -> String {
+    let mut merged: Vec<Change> = Vec::new();
+    for change in changes {
+        match merged.iter().position(|c| c.note_id == change.note_id) {
+            Some(i) => {
+                let previous = merged[i].kind;
+                match (previous, change.kind) {
+                    // 窗口内新建又删除：不留痕迹
+                    (ChangeKind::Create, ChangeKind::Delete) => {
+                        merged.remove(i);
+                        continue;
+                    }
+                    (ChangeKind::Create, _) => {}
+                    (_, kind) => merged[i].kind = kind,
+                }
+                if change.title.is_some() {
+                    merged[i].title = change.title.clone();
+                }
+            }
+            None => merged.push(change.clone()),
+        }
+    }
+
+    let describe = |c: &Change| match &c.title {
+        Some(title) => format!("{} note \"{}\"", c.kind.verb(), title),
+        None => format!("{} note {}", c.kind.verb(), c.note_id),
+    };
+
+    match merged.as_slice() {
+        [] => "Sync note changes".to_string(),
+        [single] => describe(single),
+        many => {
+            let kind = many[0].kind;
+            let subject = if many.iter().all(|c| c.kind == kind) {
+                format!("{} {} notes", kind.verb(), many.len())
+            } else {
+                format!("Change {} notes", many.len())
+            };
+            let body: Vec<String> = many.iter().map(|c| format!("- {}", describe(c))).collect();
+            format!("{}\n\n{}", subject, body.join("\n"))
+        }
+    }
+}
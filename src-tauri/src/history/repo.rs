@@ -0,0 +1,270 @@
+use git2::{IndexAddOption, Oid, Repository, Signature, Sort};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// 只对 notes/ 做版本管理（数据库、附件、缓存均不入库）
+const GITIGNORE: &str = "/*\n!/.gitignore\n!/notes/\n";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Revision {
+    pub commit_id: String,
+    pub summary: String,
+    pub message: String,
+    /// 提交时间（Unix 时间戳）
+    pub timestamp: i64,
+    /// 该提交中 note 文件被删除
+    pub deleted: bool,
+}
+
+/// note 在仓库中的相对路径
+pub fn note_path(note_id: &str) -> String {
+    format!("notes/{}.md", note_id)
+}
+
+/// 打开仓库；不存在时初始化并写入 .gitignore
+pub fn open_or_init(data_dir: &Path) -> Result<Repository, git2::Error> {
+    let repo = match Repository::open(data_dir) {
+        Ok(repo) => repo,
+        Err(_) => Repository::init(data_dir)?,
+    };
+
+    let gitignore = data_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, GITIGNORE).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    }
+
+    Ok(repo)
+}
+
+/// 暂存 notes/ 下的全部变更并提交；与 HEAD 无差异时返回 None
+pub fn commit_all(repo: &Repository, message: &str) -> Result<Option<Oid>, git2::Error> {
+    let mut index = repo.index()?;
+    index.add_all([".gitignore", "notes"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["notes"], None)?;
+    index.write()?;
+
+    let tree_id = index.write_tree()?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+        return Ok(None);
+    }
+
+    let tree = repo.find_tree(tree_id)?;
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("Noto", "noto@localhost"))?;
+    let parents: Vec<_> = parent.iter().collect();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .map(Some)
+}
+
+/// 指定提交中文件的 blob id（不存在时为 None）
+fn entry_id(commit: &git2::Commit, path: &str) -> Option<Oid> {
+    commit
+        .tree()
+        .ok()?
+        .get_path(Path::new(path))
+        .ok()
+        .map(|e| e.id())
+}
+
+/// note 的修改历史（新 → 旧），只返回文件内容有变化的提交
+pub fn note_log(
+    repo: &Repository,
+    note_id: &str,
+    limit: usize,
+) -> Result<Vec<Revision>, git2::Error> {
+    let path = note_path(note_id);
+    let mut revisions = Vec::new();
+
+    // 空仓库（尚无提交）
+    if repo.head().is_err() {
+        return Ok(revisions);
+    }
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push_head()?;
+
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let current = entry_id(&commit, &path);
+        let previous = commit.parent(0).ok().and_then(|p| entry_id(&p, &path));
+        if current == previous {
+            continue;
+        }
+
+        revisions.push(Revision {
+            commit_id: commit.id().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            message: commit.message().unwrap_or_default().to_string(),
+            timestamp: commit.time().seconds(),
+            deleted: current.is_none(),
+        });
+        if revisions.len() >= limit {
+            break;
+        }
+    }
+
+    Ok(revisions)
+}
+
+/// 读取 note 在指定提交中的内容，以及提交时间
+pub fn note_at(
+    repo: &Repository,
+    note_id: &str,
+    commit_id: &str,
+) -> Result<(String, i64), git2::Error> {
+    let commit = repo.find_commit(Oid::from_str(commit_id)?)?;
+    let entry = commit.tree()?.get_path(Path::new(&note_path(note_id)))?;
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    let content = String::from_utf8_lossy(blob.content()).into_owned();
+
+    Ok((content, commit.time().seconds()))
+}
+
+/// 从全部提交中移除 note 文件并重写历史（note 加密后调用，避免明文版本留在仓库中）
+///
+/// 重写后删除只被该文件引用的松散对象与 reflog；手动 `git gc` 打包过的对象需再次 gc 才会清除。
+/// 历史中没有该文件时返回 false
+pub fn purge_note(repo: &Repository, note_id: &str) -> Result<bool, git2::Error> {
+    let file_name = format!("{}.md", note_id);
+    let Ok(head) = repo.head() else {
+        return Ok(false);
+    };
+    let head_name = head.name().unwrap_or("HEAD").to_string();
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    walk.push_head()?;
+
+    let mut rewritten: HashMap<Oid, Oid> = HashMap::new();
+    let mut removed = HashSet::new();
+    let mut kept = HashSet::new();
+    let mut new_head = None;
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let notes = match tree.get_name("notes") {
+            Some(entry) => Some(repo.find_tree(entry.id())?),
+            None => None,
+        };
+
+        let mut tree_id = tree.id();
+        if let Some(notes) = &notes {
+            if let Some(entry) = notes.get_name(&file_name) {
+                removed.insert(entry.id());
+                let mut notes_builder = repo.treebuilder(Some(notes))?;
+                notes_builder.remove(&file_name)?;
+                let mut root_builder = repo.treebuilder(Some(&tree))?;
+                if notes_builder.is_empty() {
+                    root_builder.remove("notes")?;
+                } else {
+                    root_builder.insert("notes", notes_builder.write()?, 0o040000)?;
+                }
+                tree_id = root_builder.write()?;
+            }
+            kept.extend(
+                notes
+                    .iter()
+                    .filter(|e| e.name() != Some(file_name.as_str()))
+                    .map(|e| e.id()),
+            );
+        }
+
+        let parent_ids: Vec<Oid> = commit
+            .parent_ids()
+            .map(|p| rewritten.get(&p).copied().unwrap_or(p))
+            .collect();
+        let unchanged = tree_id == tree.id() && parent_ids.iter().copied().eq(commit.parent_ids());
+        let new_id = if unchanged {
+            commit.id()
+        } else {
+            let parents = parent_ids
+                .iter()
+                .map(|id| repo.find_commit(*id))
+                .collect::<Result<Vec<_>, _>>()?;
+            repo.commit(
+                None,
+                &commit.author(),
+                &commit.committer(),
+                commit.message_raw().unwrap_or_default(),
+                &repo.find_tree(tree_id)?,
+                &parents.iter().collect::<Vec<_>>(),
+            )?
+        };
+        rewritten.insert(commit.id(), new_id);
+        new_head = Some(new_id);
+    }
+
+    if removed.is_empty() {
+        return Ok(false);
+    }
+    if let Some(new_head) = new_head {
+        repo.reference(&head_name, new_head, true, "Purge encrypted note")?;
+    }
+    // reflog 仍引用旧提交
+    repo.reflog_delete(&head_name)?;
+    if head_name != "HEAD" {
+        repo.reflog_delete("HEAD")?;
+    }
+
+    let mut index = repo.index()?;
+    let _ = index.remove_path(Path::new(&note_path(note_id)));
+    index.write()?;
+
+    let objects = repo.path().join("objects");
+    for id in removed.difference(&kept) {
+        let hex = id.to_string();
+        let _ = fs::remove_file(objects.join(&hex[..2]).join(&hex[2..]));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purge_note_removes_every_version() {
+        let dir = std::env::temp_dir().join(format!("noto-history-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        let repo = open_or_init(&dir).unwrap();
+
+        fs::write(dir.join("notes/secret.md"), "plain v1").unwrap();
+        fs::write(dir.join("notes/other.md"), "other").unwrap();
+        commit_all(&repo, "Create notes").unwrap();
+        fs::write(dir.join("notes/secret.md"), "plain v2").unwrap();
+        commit_all(&repo, "Update note").unwrap();
+        assert_eq!(note_log(&repo, "secret", 10).unwrap().len(), 2);
+
+        fs::write(dir.join("notes/secret.md"), "ciphertext").unwrap();
+        assert!(purge_note(&repo, "secret").unwrap());
+        assert!(note_log(&repo, "secret", 10).unwrap().is_empty());
+        assert_eq!(note_log(&repo, "other", 10).unwrap().len(), 1);
+        for old in ["plain v1", "plain v2"] {
+            let id = Oid::hash_object(git2::ObjectType::Blob, old.as_bytes()).unwrap();
+            assert!(!repo.odb().unwrap().exists(id));
+        }
+
+        // 之后的提交只包含密文
+        commit_all(&repo, "Encrypt note").unwrap();
+        let revisions = note_log(&repo, "secret", 10).unwrap();
+        assert_eq!(revisions.len(), 1);
+        let (content, _) = note_at(&repo, "secret", &revisions[0].commit_id).unwrap();
+        assert_eq!(content, "ciphertext");
+        assert!(!purge_note(&repo, "missing").unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
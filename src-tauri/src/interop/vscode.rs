@@ -0,0 +1,214 @@
+use serde_json::{Map, Value};
+
+use crate::lang::detect::normalize_language;
+
+/// VS Code snippet 文件（`.code-snippets` / `<language>.json`）中的一条 snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VscodeSnippet {
+    /// 对象键名，对应 noto 中的 snippet 标题
+    pub name: String,
+    /// 触发前缀，可能有多个
+    pub prefixes: Vec<String>,
+    /// body 数组按行拼接后的内容
+    pub body: String,
+    pub description: Option<String>,
+    /// scope 中的语言 id（逗号分隔列表拆分后）
+    pub scopes: Vec<String>,
+}
+
+/// noto 语言 id 与 VS Code 语言 id 不一致的映射
+const LANGUAGE_IDS: &[(&str, &str)] = &[
+    ("bash", "shellscript"),
+    ("objectivec", "objective-c"),
+    ("protobuf", "proto3"),
+];
+
+/// VS Code 语言 id → noto 语言 id（未知 id 原样保留）
+pub fn language_from_scope(scope: &str) -> String {
+    let scope = scope.trim();
+    if let Some((id, _)) = LANGUAGE_IDS.iter().find(|(_, vscode)| *vscode == scope) {
+        return id.to_string();
+    }
+    match scope {
+        "javascriptreact" => "javascript".to_string(),
+        "typescriptreact" => "typescript".to_string(),
+        other => normalize_language(other)
+            .map(str::to_string)
+            .unwrap_or_else(|| other.to_string()),
+    }
+}
+
+/// noto 语言 id → VS Code 语言 id
+pub fn scope_from_language(language: &str) -> String {
+    LANGUAGE_IDS
+        .iter()
+        .find(|(id, _)| *id == language)
+        .map(|(_, vscode)| vscode.to_string())
+        .unwrap_or_else(|| language.to_string())
+}
+
+/// 去掉 JSONC 中的注释和尾随逗号（VS Code snippet 文件允许两者）
+fn strip_jsonc(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = String::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                // 原样复制字符串（含转义）
+                out.push(c);
+                i += 1;
+                while i < chars.len() {
+                    out.push(chars[i]);
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        out.push(chars[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    if chars[i - 1] == '"' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '}' | ']' => {
+                // 去掉尾随逗号
+                let trimmed = out.trim_end();
+                if trimmed.ends_with(',') {
+                    out.truncate(trimmed.len() - 1);
+                }
+                out.push(c);
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+fn string_or_array(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 解析 VS Code snippet 文件
+///
+/// 没有 body 的条目会被跳过
+pub fn parse(input: &str) -> Result<Vec<VscodeSnippet>, String> {
+    let value: Value = serde_json::from_str(&strip_jsonc(input))
+        .map_err(|e| format!("invalid snippet file: {}", e))?;
+    let Value::Object(entries) = value else {
+        return Err("invalid snippet file: top level must be an object".to_string());
+    };
+
+    let mut snippets = Vec::new();
+    for (name, entry) in entries {
+        let Value::Object(entry) = entry else {
+            continue;
+        };
+        if entry.get("body").is_none() {
+            continue;
+        }
+
+        snippets.push(VscodeSnippet {
+            name,
+            prefixes: string_or_array(entry.get("prefix")),
+            body: string_or_array(entry.get("body")).join("\n"),
+            description: entry
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            scopes: entry
+                .get("scope")
+                .and_then(Value::as_str)
+                .map(|s| {
+                    s.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        });
+    }
+
+    Ok(snippets)
+}
+
+/// 序列化为 `.code-snippets` JSON
+///
+/// - body 按行输出为数组；prefix 只有一个时输出为字符串
+/// - 重名条目追加 " (2)"、" (3)" 等后缀，保证对象键唯一
+pub fn to_json(snippets: &[VscodeSnippet]) -> Result<String, String> {
+    let mut root = Map::new();
+
+    for snippet in snippets {
+        let mut entry = Map::new();
+
+        match snippet.prefixes.as_slice() {
+            [] => {}
+            [single] => {
+                entry.insert("prefix".to_string(), Value::String(single.clone()));
+            }
+            many => {
+                entry.insert(
+                    "prefix".to_string(),
+                    Value::Array(many.iter().cloned().map(Value::String).collect()),
+                );
+            }
+        }
+        entry.insert(
+            "body".to_string(),
+            Value::Array(
+                snippet
+                    .body
+                    .split('\n')
+                    .map(|line| Value::String(line.trim_end_matches('\r').to_string()))
+                    .collect(),
+            ),
+        );
+        if let Some(ref description) = snippet.description {
+            entry.insert(
+                "description".to_string(),
+                Value::String(description.clone()),
+            );
+        }
+        if !snippet.scopes.is_empty() {
+            entry.insert("scope".to_string(), Value::String(snippet.scopes.join(",")));
+        }
+
+        let mut key = snippet.name.clone();
+        let mut n = 2;
+        while root.contains_key(&key) {
+            key = format!("{} ({})", snippet.name, n);
+            n += 1;
+        }
+        root.insert(key, Value::Object(entry));
+    }
+
+    serde_json::to_string_pretty(&Value::Object(root)).map_err(|e| e.to_string())
+}
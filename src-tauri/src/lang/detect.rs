@@ -0,0 +1,904 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// 语言识别结果
+///
+/// - language：规范化后的语言 id（如 "rust" / "python"）
+/// - confident：来自 shebang / modeline / 结构校验，或关键字得分明显领先
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    pub language: &'static str,
+    pub confident: bool,
+}
+
+/// 规范语言 id 及其别名（别名用于 shebang / modeline / 用户输入）
+const LANGUAGES: &[(&str, &[&str])] = &[
+    (
+        "bash",
+        &["sh", "shell", "shellscript", "zsh", "ksh", "dash"],
+    ),
+    ("c", &["h"]),
+    ("clojure", &["clj", "cljs", "cljc", "edn"]),
+    ("cpp", &["c++", "cxx", "cc", "hpp"]),
+    ("csharp", &["cs", "c#"]),
+    ("css", &[]),
+    ("dart", &[]),
+    ("dockerfile", &["docker"]),
+    ("elixir", &["ex", "exs"]),
+    ("erlang", &["erl", "escript"]),
+    ("go", &["golang"]),
+    ("graphql", &["gql"]),
+    ("haskell", &["hs", "runhaskell", "runghc"]),
+    ("html", &["htm", "xhtml"]),
+    ("ini", &["cfg", "conf", "dosini"]),
+    ("java", &[]),
+    ("javascript", &["js", "jsx", "mjs", "cjs", "node", "nodejs"]),
+    ("json", &["jsonc"]),
+    ("kotlin", &["kt", "kts"]),
+    ("lua", &["luajit"]),
+    ("makefile", &["make", "mk"]),
+    ("markdown", &["md", "mkd"]),
+    ("objectivec", &["objc", "objective-c"]),
+    ("ocaml", &["ml"]),
+    ("perl", &["pl"]),
+    ("php", &[]),
+    ("powershell", &["ps1", "pwsh", "posh"]),
+    ("protobuf", &["proto"]),
+    ("python", &["py", "python2", "python3"]),
+    ("r", &["rscript"]),
+    ("ruby", &["rb"]),
+    ("rust", &["rs"]),
+    ("scala", &["sc"]),
+    ("scss", &["sass"]),
+    (
+        "sql",
+        &["mysql", "postgres", "postgresql", "sqlite", "plsql"],
+    ),
+    ("swift", &[]),
+    ("toml", &[]),
+    ("typescript", &["ts", "tsx", "deno", "ts-node"]),
+    ("xml", &["svg", "xsl", "xslt"]),
+    ("yaml", &["yml"]),
+];
+
+/// 将语言名 / 别名规范化为语言 id，无法识别时返回 None
+pub fn normalize_language(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_ascii_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|(id, aliases)| *id == name || aliases.contains(&name.as_str()))
+        .map(|(id, _)| *id)
+}
+
+/// 根据文件名推断语言（Dockerfile / Makefile 等特殊文件名，或扩展名）
+pub fn language_from_file_name(file_name: &str) -> Option<&'static str> {
+    let base = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+
+    match base.to_ascii_lowercase().as_str() {
+        "dockerfile" | "containerfile" => return Some("dockerfile"),
+        "makefile" | "gnumakefile" => return Some("makefile"),
+        _ => {}
+    }
+
+    let (_, ext) = base.rsplit_once('.')?;
+    normalize_language(ext)
+}
+
+/// 语言对应的常用扩展名（用于生成默认文件名）
+pub fn default_extension(language: &str) -> &'static str {
+    match normalize_language(language) {
+        Some("bash") => "sh",
+        Some("c") => "c",
+        Some("clojure") => "clj",
+        Some("cpp") => "cpp",
+        Some("csharp") => "cs",
+        Some("css") => "css",
+        Some("dart") => "dart",
+        Some("elixir") => "ex",
+        Some("erlang") => "erl",
+        Some("go") => "go",
+        Some("graphql") => "graphql",
+        Some("haskell") => "hs",
+        Some("html") => "html",
+        Some("ini") => "ini",
+        Some("java") => "java",
+        Some("javascript") => "js",
+        Some("json") => "json",
+        Some("kotlin") => "kt",
+        Some("lua") => "lua",
+        Some("markdown") => "md",
+        Some("objectivec") => "m",
+        Some("ocaml") => "ml",
+        Some("perl") => "pl",
+        Some("php") => "php",
+        Some("powershell") => "ps1",
+        Some("protobuf") => "proto",
+        Some("python") => "py",
+        Some("r") => "r",
+        Some("ruby") => "rb",
+        Some("rust") => "rs",
+        Some("scala") => "scala",
+        Some("scss") => "scss",
+        Some("sql") => "sql",
+        Some("swift") => "swift",
+        Some("toml") => "toml",
+        Some("typescript") => "ts",
+        Some("xml") => "xml",
+        Some("yaml") => "yaml",
+        _ => "txt",
+    }
+}
+
+/// 默认文件名：Dockerfile / Makefile 保持惯用名，其余为 snippet.<ext>
+pub fn default_file_name(language: Option<&str>) -> String {
+    match language.and_then(normalize_language) {
+        Some("dockerfile") => "Dockerfile".to_string(),
+        Some("makefile") => "Makefile".to_string(),
+        Some(l) => format!("snippet.{}", default_extension(l)),
+        None => "snippet.txt".to_string(),
+    }
+}
+
+/// 自动识别代码片段语言
+///
+/// 依次尝试：shebang → modeline → 结构校验 → 关键字 / 语法打分
+pub fn detect_language(content: &str) -> Option<Detection> {
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    if let Some(language) = detect_shebang(content).or_else(|| detect_modeline(content)) {
+        return Some(Detection {
+            language,
+            confident: true,
+        });
+    }
+
+    if let Some(language) = detect_structure(content) {
+        return Some(Detection {
+            language,
+            confident: true,
+        });
+    }
+
+    detect_by_score(content)
+}
+
+/// `#!/usr/bin/env python3` / `#!/bin/bash`
+fn detect_shebang(content: &str) -> Option<&'static str> {
+    let first = content.lines().next()?.trim();
+    let command = first.strip_prefix("#!")?;
+
+    let mut parts = command.split_whitespace();
+    let mut interpreter = parts.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        // 跳过 env 的参数（如 -S）
+        interpreter = parts.find(|p| !p.starts_with('-'))?;
+    }
+
+    // python3.11 → python
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    normalize_language(name).or_else(|| normalize_language(interpreter))
+}
+
+static VIM_MODELINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:vi|vim|ex):.*?\b(?:ft|filetype|syntax)=([\w+#-]+)").expect("invalid regex")
+});
+
+static EMACS_MODELINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-\*-\s*(?:.*?\bmode:\s*([\w+#-]+).*?|([\w+#-]+)\s*)-\*-").expect("invalid regex")
+});
+
+/// vim / emacs modeline（只检查首尾各 5 行）
+fn detect_modeline(content: &str) -> Option<&'static str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let head = lines.iter().take(5);
+    let tail = lines.iter().skip(lines.len().saturating_sub(5));
+
+    for line in head.chain(tail) {
+        if let Some(c) = VIM_MODELINE.captures(line) {
+            if let Some(language) = normalize_language(&c[1]) {
+                return Some(language);
+            }
+        }
+        if let Some(c) = EMACS_MODELINE.captures(line) {
+            let name = c.get(1).or_else(|| c.get(2))?.as_str();
+            if let Some(language) = normalize_language(name) {
+                return Some(language);
+            }
+        }
+    }
+
+    None
+}
+
+/// 通过结构特征直接判定的格式
+fn detect_structure(content: &str) -> Option<&'static str> {
+    let trimmed = content.trim_start();
+    let lower_head: String = trimmed
+        .chars()
+        .take(64)
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if trimmed.starts_with("<?php") {
+        return Some("php");
+    }
+    if lower_head.starts_with("<?xml") {
+        return Some("xml");
+    }
+    if lower_head.starts_with("<!doctype html") || lower_head.starts_with("<html") {
+        return Some("html");
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+
+    // Dockerfile：首条有效指令为 FROM（ARG 可以出现在 FROM 之前）
+    let first_instruction = trimmed
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("ARG "));
+    if let Some(line) = first_instruction {
+        if line.starts_with("FROM ") && line.split_whitespace().count() >= 2 {
+            return Some("dockerfile");
+        }
+    }
+
+    None
+}
+
+struct Rule {
+    language: &'static str,
+    pattern: Regex,
+    weight: u32,
+}
+
+/// 关键字 / 语法规则：每条规则命中一次计一次权重（不按出现次数累加）
+static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    let table: &[(&str, &[(&str, u32)])] = &[
+        (
+            "rust",
+            &[
+                (r"\bfn\s+\w+\s*(<[^>]*>)?\s*\(", 3),
+                (r"\blet\s+mut\b", 4),
+                (r"(?m)^\s*impl(<.*>)?\s+[\w:<>]+", 3),
+                (r"\bpub(\(crate\))?\s+(fn|struct|enum|mod|trait|use)\b", 4),
+                (r"(?m)^\s*use\s+\w+::", 3),
+                (r"\b(println|format|vec|eprintln)!\(", 4),
+                (r"#\[(derive|cfg|test)\b", 4),
+                (r"&mut\s+\w+", 2),
+                (r"\bmatch\s+[\w.&]+\s*\{", 2),
+            ],
+        ),
+        (
+            "python",
+            &[
+                (r"(?m)^\s*def\s+\w+\s*\(.*\)\s*(->\s*[^:]+)?:\s*$", 4),
+                (r"(?m)^\s*from\s+[\w.]+\s+import\b", 4),
+                (r"(?m)^\s*import\s+\w+(\.\w+)*(\s+as\s+\w+)?\s*$", 2),
+                (r"(?m)^\s*class\s+\w+(\(.*\))?:\s*$", 3),
+                (r"(?m)^\s*(elif|except|with|for|while|if)\b.*:\s*$", 3),
+                (r"\bself\.\w+", 2),
+                (r"\b(None|True|False)\b", 1),
+                (r#"if\s+__name__\s*==\s*['"]__main__['"]"#, 5),
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                (r"\bfunction\s*\w*\s*\(", 2),
+                (r"\b(const|let)\s+\w+\s*=", 1),
+                (r"=>\s*[{(]?", 1),
+                (r"\bconsole\.(log|error|warn)\(", 3),
+                (r#"\brequire\(['"]"#, 3),
+                (r"\bmodule\.exports\b", 4),
+                (r"\b(document|window)\.\w+", 3),
+                (r#"(?m)^\s*import\s+.*\s+from\s+['"]"#, 2),
+                (r"===|!==", 2),
+                (r"\bclassName=", 3),
+            ],
+        ),
+        (
+            "typescript",
+            &[
+                (
+                    r":\s*(string|number|boolean|any|void|unknown|never)(\[\])?\b",
+                    4,
+                ),
+                (r"(?m)^\s*(export\s+)?interface\s+\w+(<.*>)?\s*\{", 4),
+                (r"(?m)^\s*(export\s+)?type\s+\w+(<.*>)?\s*=", 4),
+                (r"\bas\s+(const|string|number|any|unknown)\b", 3),
+                (r"\b(private|public|protected|readonly)\s+\w+\s*[:?]", 3),
+                (r#"(?m)^\s*import\s+.*\s+from\s+['"]"#, 2),
+                (r"\b(const|let)\s+\w+\s*(:\s*[\w<>\[\]]+)?\s*=", 1),
+                (r"===|!==", 1),
+            ],
+        ),
+        (
+            "java",
+            &[
+                (r"\bpublic\s+(abstract\s+|final\s+)?class\s+\w+", 4),
+                (r"\bpublic\s+static\s+void\s+main\s*\(", 5),
+                (r"System\.(out|err)\.print", 5),
+                (r"(?m)^\s*import\s+javax?\.", 5),
+                (r"(?m)^\s*package\s+[\w.]+;", 4),
+                (r"@Override\b", 3),
+                (
+                    r"\bprivate\s+(static\s+)?(final\s+)?\w+(<.*>)?\s+\w+\s*[;=]",
+                    2,
+                ),
+                (r"\bnew\s+\w+(<.*>)?\(", 1),
+            ],
+        ),
+        (
+            "kotlin",
+            &[
+                (
+                    r"(?m)^\s*(private\s+|override\s+|suspend\s+)*fun\s+\w+\s*\(",
+                    4,
+                ),
+                (r"\bval\s+\w+\s*(:\s*\w+)?\s*=", 3),
+                (r"\bvar\s+\w+\s*:\s*\w+", 2),
+                (r"\bprintln\(", 2),
+                (r"(?m)^\s*data\s+class\b", 5),
+                (r"\bcompanion\s+object\b", 5),
+                (r"\bwhen\s*(\(.*\))?\s*\{", 2),
+                (r"(?m)^\s*package\s+[\w.]+\s*$", 2),
+            ],
+        ),
+        (
+            "scala",
+            &[
+                (r"(?m)^\s*(case\s+)?object\s+\w+", 3),
+                (
+                    r"\bdef\s+\w+(\[.*\])?\s*(\(.*\))?\s*(:\s*[\w\[\]]+)?\s*=",
+                    4,
+                ),
+                (r"\bcase\s+class\b", 5),
+                (r"\bval\s+\w+\s*(:\s*\w+)?\s*=", 2),
+                (r"(?m)^\s*import\s+scala\.", 5),
+                (r"\bextends\s+App\b", 4),
+                (r"\bimplicit\b", 3),
+            ],
+        ),
+        (
+            "c",
+            &[
+                (
+                    r"(?m)^\s*#include\s*<(stdio|stdlib|string|unistd|stdint|stdbool|math|errno|assert)\.h>",
+                    5,
+                ),
+                (r"\bint\s+main\s*\(", 3),
+                (r"\bprintf\(", 2),
+                (r"\b(malloc|calloc|free)\(", 3),
+                (r"(?m)^\s*#(define|ifndef|ifdef|endif|pragma)\b", 2),
+                (r"\btypedef\s+", 2),
+                (r"\bstruct\s+\w+\s*\{", 1),
+                (r"\w->\w", 1),
+            ],
+        ),
+        (
+            "cpp",
+            &[
+                (
+                    r"(?m)^\s*#include\s*<(iostream|vector|string|map|memory|algorithm|unordered_map|cstdio|cstdlib)>",
+                    5,
+                ),
+                (r"\bstd::\w+", 4),
+                (r"\b(cout|cerr)\s*<<", 4),
+                (r"(?m)^\s*using\s+namespace\s+\w+;", 5),
+                (r"\btemplate\s*<", 4),
+                (r"\bnullptr\b", 3),
+                (r"\bnamespace\s+\w+\s*\{", 2),
+                (r"(?m)^\s*#(define|ifndef|ifdef|endif|pragma)\b", 1),
+            ],
+        ),
+        (
+            "csharp",
+            &[
+                (r"(?m)^\s*using\s+System(\.[\w.]+)?;", 5),
+                (r"\bConsole\.Write(Line)?\(", 5),
+                (r"\{\s*get;\s*(set;|init;)?\s*\}", 5),
+                (r"\basync\s+Task\b", 4),
+                (r"\bvar\s+\w+\s*=\s*new\b", 3),
+                (r"\bnamespace\s+[\w.]+", 2),
+                (
+                    r"\bpublic\s+(static\s+)?(async\s+)?(void|string|int|bool|Task)\b",
+                    2,
+                ),
+            ],
+        ),
+        (
+            "go",
+            &[
+                (r"(?m)^\s*func\s+(\(\w+\s+\*?\w+\)\s*)?\w+\s*\(", 5),
+                (r"(?m)^\s*package\s+\w+\s*$", 3),
+                (r"\bfmt\.\w+\(", 5),
+                (r"\berr\s*!=\s*nil\b", 5),
+                (r"(?m)^\s*import\s*\(", 3),
+                (r"\bdefer\s+", 3),
+                (r"\bgo\s+func\b|\bchan\s+\w+", 3),
+                (r":=", 2),
+            ],
+        ),
+        (
+            "ruby",
+            &[
+                (r"(?m)^\s*def\s+[\w.]+[?!]?(\(.*\))?\s*$", 3),
+                (r"(?m)^\s*end\s*$", 2),
+                (r#"(?m)^\s*require(_relative)?\s+['"]"#, 3),
+                (r"(?m)^\s*puts\s", 3),
+                (r"\bdo\s*\|[\w, ]+\|", 4),
+                (r"\battr_(accessor|reader|writer)\b", 5),
+                (r"\belsif\b", 4),
+                (r"(?m)^\s*(class|module)\s+\w+(\s*<\s*\w+)?\s*$", 2),
+            ],
+        ),
+        (
+            "php",
+            &[
+                (r"\$\w+\s*=", 2),
+                (r"\bfunction\s+\w+\s*\(\s*(\??\w+\s+)?\$", 4),
+                (r"\becho\s+", 2),
+                (r"\$this->\w+", 4),
+                (r"(?m)^\s*namespace\s+[\w\\]+;", 3),
+                (r"(?m)^\s*use\s+[\w\\]+;", 2),
+            ],
+        ),
+        (
+            "swift",
+            &[
+                (
+                    r"(?m)^\s*import\s+(Foundation|UIKit|SwiftUI|Cocoa|Combine)\b",
+                    5,
+                ),
+                (r"\bfunc\s+\w+\s*(<.*>)?\s*\(", 4),
+                (r"\bguard\s+let\b", 5),
+                (r"\bif\s+let\b", 2),
+                (r"\b(var|let)\s+\w+\s*:\s*\w+", 1),
+                (r"\bstruct\s+\w+\s*:\s*\w+", 2),
+                (r"\b(some|inout)\s+\w+", 2),
+            ],
+        ),
+        (
+            "haskell",
+            &[
+                (r"(?m)^\s*module\s+[\w.]+(\s*\(.*\))?\s+where\b", 5),
+                (r"(?m)^\s*import\s+(qualified\s+)?[A-Z][\w.]*", 2),
+                (r"(?m)^\w+\s+::\s+", 4),
+                (r"\bputStrLn\b", 5),
+                (r"\bdata\s+\w+(\s+\w+)*\s*=", 3),
+                (r"\bderiving\b", 4),
+                (r"<-", 1),
+            ],
+        ),
+        (
+            "elixir",
+            &[
+                (r"(?m)^\s*defmodule\s+[\w.]+\s+do\b", 6),
+                (r"(?m)^\s*defp?\s+\w+.*\bdo\s*$", 4),
+                (r"\bIO\.(puts|inspect)\b", 5),
+                (r"\|>", 2),
+                (r"%\{", 2),
+                (r"\bfn\s+[\w,\s]*->", 4),
+            ],
+        ),
+        (
+            "erlang",
+            &[
+                (r"(?m)^-module\(\w+\)\.", 6),
+                (r"(?m)^-export\(\[", 5),
+                (r"\bio:format\(", 5),
+            ],
+        ),
+        (
+            "lua",
+            &[
+                (r"\blocal\s+\w+\s*=", 3),
+                (r"\blocal\s+function\b", 5),
+                (r"\bfunction\s+[\w.:]+\s*\(", 2),
+                (r"\bthen\s*$", 2),
+                (r"~=", 3),
+                (r"\b(i?pairs)\(", 5),
+                (r"--\[\[", 4),
+                (r"\bnil\b", 1),
+            ],
+        ),
+        (
+            "perl",
+            &[
+                (r"(?m)^\s*use\s+(strict|warnings)\s*;", 6),
+                (r"\bmy\s+[$@%]\w+", 5),
+                (r"\$_\b|@_\b", 3),
+                (r"=~\s*[ms]?/", 3),
+                (r"\bsub\s+\w+\s*\{", 4),
+            ],
+        ),
+        (
+            "r",
+            &[
+                (r"<-\s*(function|c|data\.frame|list|read\.\w+)\s*\(", 5),
+                (r"\w+\s*<-\s*", 2),
+                (r"\blibrary\(\w+\)", 4),
+                (r"\bggplot\(", 5),
+                (r"%>%", 4),
+            ],
+        ),
+        (
+            "bash",
+            &[
+                (r"(?m)^\s*(if|while|for|until)\s+.*;\s*(then|do)\s*$", 4),
+                (r"(?m)^\s*fi\s*$", 5),
+                (r"(?m)^\s*done\s*$", 4),
+                (r"(?m)^\s*esac\s*$", 5),
+                (r"(?m)^\s*export\s+\w+=", 4),
+                (r"\$\{\w+(:-[^}]*)?\}", 2),
+                (r"\$\(\s*\w+", 2),
+                (r"(?m)^\s*echo\s+", 2),
+                (r"\[\[\s+.*\s+\]\]", 3),
+                (r"\|\s*(grep|awk|sed|xargs|sort|head|tail|wc)\b", 3),
+                (
+                    r"(?m)^\s*(sudo\s+)?(apt(-get)?|brew|npm|yarn|pnpm|pip3?|cargo|git|docker|kubectl|cd|ls|mkdir|rm|cp|mv|chmod|chown|curl|wget|tar|ssh)\s+",
+                    2,
+                ),
+            ],
+        ),
+        (
+            "powershell",
+            &[
+                (
+                    r"\b(Get|Set|New|Remove|Write|Invoke|Import|Start|Stop|Test|Add)-[A-Z]\w+",
+                    5,
+                ),
+                (r"\|\s*(ForEach|Where|Select|Sort)-Object\b", 5),
+                (r"\$PSScriptRoot\b|\$env:\w+", 4),
+                (r"\s-(eq|ne|lt|gt|le|ge|like|match)\s", 3),
+                (r"\bparam\s*\(", 3),
+            ],
+        ),
+        (
+            "sql",
+            &[
+                (r"(?is)\bSELECT\b.+?\bFROM\b", 4),
+                (r"(?im)^\s*INSERT\s+INTO\b", 5),
+                (r"(?im)^\s*UPDATE\s+\w+\s+SET\b", 5),
+                (r"(?im)^\s*DELETE\s+FROM\b", 5),
+                (
+                    r"(?im)^\s*CREATE\s+(TABLE|INDEX|VIEW|DATABASE|UNIQUE\s+INDEX|TRIGGER)\b",
+                    5,
+                ),
+                (r"(?im)^\s*ALTER\s+TABLE\b", 5),
+                (r"(?i)\b(INNER|LEFT|RIGHT|OUTER)\s+JOIN\b", 3),
+                (r"(?i)\b(GROUP|ORDER)\s+BY\b", 2),
+                (r"(?i)\bWHERE\b", 1),
+            ],
+        ),
+        (
+            "html",
+            &[
+                (
+                    r"<(div|span|p|a|ul|ol|li|body|head|script|style|table|form|input|button|section|nav)\b[^>]*>",
+                    3,
+                ),
+                (
+                    r"</(div|span|p|a|ul|ol|li|body|head|script|table|form|button|section|nav)>",
+                    3,
+                ),
+                (r"<(meta|link|br|img)\b[^>]*/?>", 2),
+            ],
+        ),
+        (
+            "css",
+            &[
+                (r"(?m)^\s*[.#]?[\w-]+([\s,>+~]*[.#:]?[\w-]+)*\s*\{\s*$", 2),
+                (r"(?m)^\s*[\w-]+\s*:\s*[^;{]+;\s*$", 2),
+                (r"@media\b", 4),
+                (r"\b\d+(px|em|rem|vh|vw)\b", 3),
+                (r"#[0-9a-fA-F]{3,6}\b", 2),
+                (r"!important", 4),
+            ],
+        ),
+        (
+            "scss",
+            &[
+                (r"(?m)^\s*\$[\w-]+\s*:", 5),
+                (r"@(mixin|include|extend)\b", 5),
+                (r"&(:\w+|\.\w+|-\w+)", 4),
+                (r"\b\d+(px|em|rem)\b", 2),
+            ],
+        ),
+        (
+            "json",
+            &[(r#"(?m)^\s*"[\w.-]+"\s*:\s*"#, 3), (r"^\s*[{\[]", 1)],
+        ),
+        (
+            "yaml",
+            &[
+                (r"(?m)^---\s*$", 3),
+                (r"(?m)^\s*[\w.-]+:\s+\S", 2),
+                (r"(?m)^\s*-\s+[\w.-]+:\s", 3),
+                (
+                    r"(?m)^(apiVersion|kind|metadata|spec|services|version|jobs|steps|on):",
+                    4,
+                ),
+            ],
+        ),
+        (
+            "toml",
+            &[
+                (r"(?m)^\s*\[\[[\w.-]+\]\]\s*$", 5),
+                (
+                    r"(?m)^\s*\[(package|dependencies|dev-dependencies|workspace|tool\.[\w-]+)\]\s*$",
+                    5,
+                ),
+                (r#"(?m)^\s*\[[\w."-]+\]\s*$"#, 3),
+                (r#"(?m)^\s*[\w-]+\s*=\s*("|\d|\[|\{|true\b|false\b)"#, 3),
+            ],
+        ),
+        (
+            "ini",
+            &[
+                (r"(?m)^\s*\[[\w .-]+\]\s*$", 2),
+                (r#"(?m)^\s*[\w.-]+\s*=\s*[^"\[{\s]"#, 2),
+                (r"(?m)^\s*;", 2),
+            ],
+        ),
+        (
+            "xml",
+            &[
+                (r"xmlns(:\w+)?=", 5),
+                (r"</\w+:\w+>", 3),
+                (r#"<\w+(\s+[\w:-]+="[^"]*")+\s*/?>"#, 1),
+            ],
+        ),
+        (
+            "markdown",
+            &[
+                (r"(?m)^#{1,6}\s+\S", 2),
+                (r"(?m)^```", 4),
+                (r"\[[^\]]+\]\([^)]+\)", 3),
+                (r"\*\*[^*\n]+\*\*", 2),
+                (r"(?m)^>\s+", 2),
+                (r"(?m)^\s*[-*+]\s+\S", 1),
+            ],
+        ),
+        (
+            "dockerfile",
+            &[(
+                r"(?m)^(RUN|COPY|ADD|WORKDIR|ENTRYPOINT|CMD|EXPOSE|ENV|ARG)\s",
+                3,
+            )],
+        ),
+        (
+            "makefile",
+            &[
+                (r"(?m)^\.PHONY\s*:", 6),
+                (r"(?m)^\t\S", 3),
+                (r"(?m)^[\w.%/-]+\s*:([^=]|$)", 2),
+                (r"\$\(\w+\)", 2),
+            ],
+        ),
+        (
+            "graphql",
+            &[
+                (
+                    r"(?m)^\s*(query|mutation|subscription|fragment)\s+\w+.*\{",
+                    5,
+                ),
+                (r"(?m)^\s*schema\s*\{", 5),
+                (r"(?m)^\s*type\s+\w+\s*(implements\s+\w+\s*)?\{", 3),
+                (r"\b\w+\s*:\s*\[?\w+!?\]?!", 3),
+            ],
+        ),
+        (
+            "dart",
+            &[
+                (r"(?m)^\s*import\s+'package:", 6),
+                (r"\bWidget\s+build\(", 6),
+                (r"\bsetState\(", 4),
+                (r"@override\b", 3),
+                (r"\bvoid\s+main\s*\(\s*\)", 3),
+                (r"\bfinal\s+\w+\s*=", 2),
+            ],
+        ),
+        (
+            "clojure",
+            &[
+                (r"(?m)^\s*\(ns\s+[\w.-]+", 6),
+                (r"\(defn-?\s+[\w-]+", 5),
+                (r"\(def\s+[\w-]+", 3),
+                (r"\(let\s+\[", 4),
+                (r"\(println\b", 2),
+            ],
+        ),
+        (
+            "ocaml",
+            &[
+                (r"(?m)^\s*let\s+rec\s+", 5),
+                (r"\bmatch\s+.+\s+with\b", 5),
+                (r"\bmodule\s+\w+\s*=\s*struct\b", 5),
+                (r"\bPrintf\.printf\b", 5),
+                (r";;\s*$", 4),
+                (r"(?m)^\s*\|\s*\w+.*->", 2),
+            ],
+        ),
+        (
+            "protobuf",
+            &[
+                (r#"(?m)^\s*syntax\s*=\s*"proto[23]"\s*;"#, 8),
+                (r"(?m)^\s*message\s+\w+\s*\{", 4),
+                (r"\brpc\s+\w+\s*\(", 4),
+                (
+                    r"(?m)^\s*(optional|repeated|required)?\s*[\w.]+\s+\w+\s*=\s*\d+\s*;",
+                    3,
+                ),
+                (r"(?m)^\s*service\s+\w+\s*\{", 2),
+            ],
+        ),
+        (
+            "objectivec",
+            &[
+                (r#"(?m)^\s*#import\s+[<"]"#, 5),
+                (r"@(interface|implementation|end|property)\b", 5),
+                (r"\bNSString\b|\bNSLog\(", 5),
+                (r"\[\w+\s+\w+(:\w*)?\]", 2),
+            ],
+        ),
+    ];
+
+    table
+        .iter()
+        .flat_map(|(language, rules)| {
+            rules.iter().map(move |(pattern, weight)| Rule {
+                language,
+                pattern: Regex::new(pattern).expect("invalid language rule"),
+                weight: *weight,
+            })
+        })
+        .collect()
+});
+
+/// 低于该分数不给出结果
+const MIN_SCORE: u32 = 3;
+/// 可信结果的最低分数
+const CONFIDENT_SCORE: u32 = 8;
+
+/// 按规则打分，取最高分语言
+///
+/// 最高分达到 CONFIDENT_SCORE 且不低于第二名的两倍时视为可信
+fn detect_by_score(content: &str) -> Option<Detection> {
+    let mut scores: Vec<(&'static str, u32)> = Vec::new();
+
+    for rule in RULES.iter() {
+        if !rule.pattern.is_match(content) {
+            continue;
+        }
+        match scores.iter_mut().find(|(l, _)| *l == rule.language) {
+            Some((_, score)) => *score += rule.weight,
+            None => scores.push((rule.language, rule.weight)),
+        }
+    }
+
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    let (language, best) = *scores.first()?;
+    if best < MIN_SCORE {
+        return None;
+    }
+
+    let second = scores.get(1).map(|s| s.1).unwrap_or(0);
+
+    Some(Detection {
+        language,
+        confident: best >= CONFIDENT_SCORE && best >= second * 2,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(content: &str) -> Option<&'static str> {
+        detect_language(content).map(|d| d.language)
+    }
+
+    #[test]
+    fn detects_main_languages() {
+        let cases: &[(&str, &str)] = &[
+            (
+                "rust",
+                "use std::fmt;\n\npub fn main() {\n    let mut v = vec![1, 2];\n    println!(\"{:?}\", v);\n}\n",
+            ),
+            (
+                "python",
+                "from os import path\n\ndef main(args):\n    if args:\n        print(path.join('a', 'b'))\n\nif __name__ == '__main__':\n    main(None)\n",
+            ),
+            (
+                "javascript",
+                "const fs = require('fs');\nfunction read(p) {\n  console.log(p);\n}\nmodule.exports = read;\n",
+            ),
+            (
+                "typescript",
+                "interface User {\n  name: string;\n  age: number;\n}\n\nexport function greet(user: User): string {\n  return `hi ${user.name}`;\n}\n",
+            ),
+            (
+                "go",
+                "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tx := 1\n\tfmt.Println(x)\n}\n",
+            ),
+            (
+                "java",
+                "public class Main {\n    public static void main(String[] args) {\n        System.out.println(\"hi\");\n    }\n}\n",
+            ),
+            (
+                "sql",
+                "SELECT id, name\nFROM users\nWHERE age > 18\nORDER BY name;\n",
+            ),
+            ("bash", "#!/bin/bash\necho hello\n"),
+            ("python", "#!/usr/bin/env python3\nprint('x')\n"),
+            ("php", "<?php\necho 'hi';\n"),
+            ("xml", "<?xml version=\"1.0\"?>\n<root/>\n"),
+            ("html", "<!DOCTYPE html>\n<html><body></body></html>\n"),
+            ("json", "{\"a\": [1, 2, 3]}"),
+            ("dockerfile", "# base\nARG VERSION=1\nFROM alpine:3.19\nRUN apk add curl\n"),
+            ("ruby", "# vim: set ft=ruby:\nputs 1\n"),
+            ("lua", "-- -*- mode: lua -*-\nprint(1)\n"),
+        ];
+        for (expected, content) in cases {
+            assert_eq!(detected(content), Some(*expected), "content:\n{}", content);
+        }
+    }
+
+    #[test]
+    fn ambiguous_content_is_not_confident() {
+        // 过短或无特征的内容不给出结果
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language("hello world"), None);
+        // 单行赋值同时符合多种语言，结果不可信
+        assert!(detect_language("x = 1").is_none_or(|d| !d.confident));
+
+        // 不合法的 JSON 不按结构判定
+        assert_ne!(detected("{ not json"), Some("json"));
+
+        // 仅有少量特征时可以给出结果，但不可信
+        let weak = detect_language("const x = 1;\nlet y = x => x;\n");
+        assert!(weak.is_none_or(|d| !d.confident));
+
+        // shebang 与 modeline 优先于关键字打分
+        let d = detect_language("#!/usr/bin/env node\nimport os\n").unwrap();
+        assert_eq!((d.language, d.confident), ("javascript", true));
+    }
+
+    #[test]
+    fn normalizes_names_and_file_names() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("RS", Some("rust")),
+            (" py ", Some("python")),
+            ("c++", Some("cpp")),
+            ("yml", Some("yaml")),
+            ("unknown", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(normalize_language(name), *expected, "name: {}", name);
+        }
+
+        let files: &[(&str, Option<&str>)] = &[
+            ("Dockerfile", Some("dockerfile")),
+            ("src/GNUmakefile", Some("makefile")),
+            ("main.rs", Some("rust")),
+            ("a.test.tsx", Some("typescript")),
+            ("README", None),
+        ];
+        for (name, expected) in files {
+            assert_eq!(language_from_file_name(name), *expected, "file: {}", name);
+        }
+
+        assert_eq!(default_file_name(Some("Rust")), "snippet.rs");
+        assert_eq!(default_file_name(Some("docker")), "Dockerfile");
+        assert_eq!(default_file_name(None), "snippet.txt");
+    }
+}
@@ -0,0 +1,245 @@
+use once_cell::sync::Lazy;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Style, Theme, ThemeSet};
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+/// 未指定主题时使用的默认主题
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// 高亮渲染选项
+#[derive(Default)]
+pub struct HighlightOptions {
+    /// 主题名（见 theme_names），None 时使用 DEFAULT_THEME
+    pub theme: Option<String>,
+    /// 是否输出行号
+    pub line_numbers: bool,
+    /// 需要强调的行区间（1 起始，闭区间）
+    pub highlight_lines: Vec<(usize, usize)>,
+}
+
+impl HighlightOptions {
+    fn is_highlighted(&self, line_no: usize) -> bool {
+        self.highlight_lines
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&line_no))
+    }
+}
+
+/// 所有可用主题名
+pub fn theme_names() -> Vec<String> {
+    THEME_SET.themes.keys().cloned().collect()
+}
+
+/// 解析行区间字符串，如 "1,3-5,8"
+pub fn parse_line_ranges(spec: &str) -> Result<Vec<(usize, usize)>, String> {
+    let mut ranges = Vec::new();
+
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((a, b)) => (a.trim(), b.trim()),
+            None => (part, part),
+        };
+        let start: usize = start
+            .parse()
+            .map_err(|_| format!("invalid line range: {}", part))?;
+        let end: usize = end
+            .parse()
+            .map_err(|_| format!("invalid line range: {}", part))?;
+        if start == 0 || end < start {
+            return Err(format!("invalid line range: {}", part));
+        }
+        ranges.push((start, end));
+    }
+
+    Ok(ranges)
+}
+
+/// 按语言 id 查找语法定义（找不到时退化为纯文本）
+fn find_syntax(language: Option<&str>) -> &'static SyntaxReference {
+    let ss = &*SYNTAX_SET;
+
+    let Some(language) = language.map(str::trim).filter(|l| !l.is_empty()) else {
+        return ss.find_syntax_plain_text();
+    };
+
+    // 语言 id 与 syntect 语法名 / 扩展名不一致的映射
+    let language = language.to_ascii_lowercase();
+    let token = match language.as_str() {
+        "bash" => "sh",
+        "csharp" => "cs",
+        "objectivec" => "m",
+        "typescript" => "js",
+        "ocaml" => "ml",
+        "scss" => "css",
+        "toml" => "ini",
+        other => other,
+    };
+
+    ss.find_syntax_by_token(token)
+        .unwrap_or_else(|| ss.find_syntax_plain_text())
+}
+
+fn find_theme(name: Option<&str>) -> Result<&'static Theme, String> {
+    let name = name.unwrap_or(DEFAULT_THEME);
+    THEME_SET
+        .themes
+        .get(name)
+        .ok_or_else(|| format!("unknown theme: {}", name))
+}
+
+fn css_color(c: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+
+/// 逐行高亮，返回去掉行尾换行符后的片段
+fn highlight_lines<'a>(
+    code: &'a str,
+    language: Option<&str>,
+    theme: &Theme,
+) -> Result<Vec<Vec<(Style, &'a str)>>, String> {
+    let mut highlighter = HighlightLines::new(find_syntax(language), theme);
+    let mut lines = Vec::new();
+
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter
+            .highlight_line(line, &SYNTAX_SET)
+            .map_err(|e| e.to_string())?;
+        let regions = regions
+            .into_iter()
+            .map(|(style, text)| (style, text.trim_end_matches(['\n', '\r'])))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        lines.push(regions);
+    }
+
+    Ok(lines)
+}
+
+/// 渲染为带内联样式的 HTML 片段（`<pre><code>...</code></pre>`）
+///
+/// 只使用内联样式，可直接粘贴到文档 / 邮件，或嵌入导出的 HTML
+pub fn highlight_html(
+    code: &str,
+    language: Option<&str>,
+    options: &HighlightOptions,
+) -> Result<String, String> {
+    let theme = find_theme(options.theme.as_deref())?;
+    let lines = highlight_lines(code, language, theme)?;
+
+    let background = theme
+        .settings
+        .background
+        .map(css_color)
+        .unwrap_or_else(|| "#ffffff".to_string());
+    let foreground = theme
+        .settings
+        .foreground
+        .map(css_color)
+        .unwrap_or_else(|| "#000000".to_string());
+    let line_highlight = theme
+        .settings
+        .line_highlight
+        .map(css_color)
+        .unwrap_or_else(|| "rgba(255,235,59,0.25)".to_string());
+    let gutter = theme
+        .settings
+        .gutter_foreground
+        .map(css_color)
+        .unwrap_or_else(|| foreground.clone());
+    let width = lines.len().max(1).to_string().len();
+
+    let mut html = format!(
+        r#"<pre class="noto-code" data-language="{}" style="background-color:{};color:{};padding:12px;overflow:auto;"><code>"#,
+        html_escape(language.unwrap_or("text")),
+        background,
+        foreground
+    );
+
+    for (i, regions) in lines.iter().enumerate() {
+        let line_no = i + 1;
+        let mut style = "display:block;min-height:1.2em;".to_string();
+        if options.is_highlighted(line_no) {
+            style.push_str(&format!("background-color:{};", line_highlight));
+        }
+
+        html.push_str(&format!(r#"<span class="noto-line" style="{}">"#, style));
+        if options.line_numbers {
+            html.push_str(&format!(
+                r#"<span class="noto-ln" style="color:{};opacity:0.6;user-select:none;padding-right:12px;">{:>width$}</span>"#,
+                gutter,
+                line_no,
+                width = width
+            ));
+        }
+        html.push_str(
+            &styled_line_to_highlighted_html(regions, IncludeBackground::No)
+                .map_err(|e| e.to_string())?,
+        );
+        html.push_str("</span>");
+    }
+
+    html.push_str("</code></pre>");
+
+    Ok(html)
+}
+
+/// 渲染为 24 位真彩色 ANSI 文本（终端使用）
+pub fn highlight_ansi(
+    code: &str,
+    language: Option<&str>,
+    options: &HighlightOptions,
+) -> Result<String, String> {
+    let theme = find_theme(options.theme.as_deref())?;
+    let lines = highlight_lines(code, language, theme)?;
+
+    let line_highlight = theme.settings.line_highlight.unwrap_or(Color {
+        r: 0x44,
+        g: 0x44,
+        b: 0x44,
+        a: 0xff,
+    });
+    let width = lines.len().max(1).to_string().len();
+
+    let mut out = String::new();
+    for (i, regions) in lines.iter().enumerate() {
+        let line_no = i + 1;
+        if options.is_highlighted(line_no) {
+            out.push_str(&format!(
+                "\x1b[48;2;{};{};{}m",
+                line_highlight.r, line_highlight.g, line_highlight.b
+            ));
+        }
+        if options.line_numbers {
+            out.push_str(&format!(
+                "\x1b[2m{:>width$} │\x1b[22m ",
+                line_no,
+                width = width
+            ));
+        }
+        out.push_str(&as_24_bit_terminal_escaped(regions, false));
+        out.push_str("\x1b[0m\n");
+    }
+
+    Ok(out)
+}
+
+/// 最小 HTML 转义（属性 / 文本通用）
+pub fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
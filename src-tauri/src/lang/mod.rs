@@ -0,0 +1,5 @@
+pub mod detect;
+pub mod highlight;
+
+pub use detect::detect_language;
+pub use highlight::{highlight_ansi, highlight_html, HighlightOptions};
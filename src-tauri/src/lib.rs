@@ -1,14 +1,46 @@
+mod api;
 mod app;
+mod changelog;
+mod cli;
 mod commands;
+mod crypto;
 mod db;
 mod fs;
+mod history;
+mod interop;
+mod lang;
+mod location;
+mod lock;
+mod markdown;
+mod service;
+mod settings;
+mod sync;
+mod template;
+mod util;
+mod vaults;
 
 use commands::{
-    create_note, create_snippet, create_task, create_time_entry, create_tree_node,
-    delete_snippet_only, delete_task, delete_time_entry, delete_tree_node, get_note,
-    get_snippet_detail, get_task, list_tasks, list_time_entries, list_tree_nodes,
-    list_tree_nodes_tree, update_note_content, update_note_title, update_snippet_detail,
-    update_task, update_time_entry, update_tree_node,
+    add_snippet_file, attach_project_description, change_vault_key, close_vault,
+    configure_folder_sync, configure_webdav_sync, create_note, create_note_template,
+    create_project_description, create_snippet, create_task, create_time_entry, create_tree_node,
+    create_vault, decrypt_note, decrypt_vault, delete_note_template, delete_snippet_file,
+    delete_snippet_only, delete_task, delete_time_entry, delete_tree_node,
+    detach_project_description, detect_snippet_languages, disable_api, disable_note_history,
+    disable_sync, enable_api, enable_note_history, encrypt_note, encrypt_vault, export_note_html,
+    export_settings, export_vscode_snippets, get_api_status, get_data_dir_info,
+    get_journal_settings, get_next_daily_note, get_note, get_note_history, get_note_outline,
+    get_previous_daily_note, get_project, get_settings, get_snippet_detail, get_sync_status,
+    get_task, get_undo_state, get_vault_status, import_settings, import_vscode_snippets,
+    is_note_history_enabled, is_note_unlocked, list_children, list_daily_note_days,
+    list_highlight_themes, list_note_templates, list_snippet_variables, list_tasks,
+    list_time_entries, list_tree_nodes, list_tree_nodes_tree, list_vaults, lock_all_notes,
+    lock_note, move_task, move_vault, open_daily_note, open_vault, redo, refresh_tree_node,
+    regenerate_api_token, rename_snippet_file, rename_vault, render_note_html, render_snippet,
+    render_snippet_template, reorder_snippet_files, reset_settings, restore_note_version,
+    set_data_dir, sync_now, undo, unlock_note, unlock_vault, update_journal_settings,
+    update_note_content, update_note_metadata, update_note_template, update_note_title,
+    update_settings, update_snippet_detail, update_snippet_file, update_snippet_meta, update_task,
+    update_time_entry, update_tree_node,
 };
 
 #[tauri::command]
@@ -16,6 +48,11 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// 命令行工具 noto-cli 的入口
+pub fn run_cli() -> std::process::ExitCode {
+    cli::run(std::env::args().skip(1).collect())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -26,30 +63,106 @@ pub fn run() {
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             create_note,
+            list_note_templates,
+            create_note_template,
+            update_note_template,
+            delete_note_template,
+            open_daily_note,
+            get_previous_daily_note,
+            get_next_daily_note,
+            list_daily_note_days,
+            get_journal_settings,
+            update_journal_settings,
             create_snippet,
             get_snippet_detail,
             update_snippet_detail,
             delete_snippet_only,
+            detect_snippet_languages,
+            render_snippet,
+            list_highlight_themes,
+            list_snippet_variables,
+            render_snippet_template,
+            add_snippet_file,
+            update_snippet_file,
+            rename_snippet_file,
+            reorder_snippet_files,
+            delete_snippet_file,
+            update_snippet_meta,
+            import_vscode_snippets,
+            export_vscode_snippets,
             create_tree_node,
             update_tree_node,
             delete_tree_node,
             list_tree_nodes,
             list_tree_nodes_tree,
             get_note,
+            get_note_outline,
+            render_note_html,
+            export_note_html,
             update_note_content,
             update_note_title,
+            update_note_metadata,
             create_task,
             delete_task,
             get_task,
             list_tasks,
             update_task,
+            move_task,
+            create_project_description,
+            attach_project_description,
+            detach_project_description,
+            get_project,
             create_time_entry,
             list_time_entries,
             delete_time_entry,
             update_time_entry,
+            encrypt_note,
+            decrypt_note,
+            unlock_note,
+            lock_note,
+            lock_all_notes,
+            is_note_unlocked,
+            get_vault_status,
+            unlock_vault,
+            encrypt_vault,
+            change_vault_key,
+            decrypt_vault,
+            enable_note_history,
+            disable_note_history,
+            is_note_history_enabled,
+            get_note_history,
+            restore_note_version,
+            configure_folder_sync,
+            disable_sync,
+            get_sync_status,
+            sync_now,
+            configure_webdav_sync,
+            undo,
+            redo,
+            get_undo_state,
+            get_api_status,
+            enable_api,
+            disable_api,
+            regenerate_api_token,
+            get_data_dir_info,
+            set_data_dir,
+            move_vault,
+            list_vaults,
+            create_vault,
+            open_vault,
+            close_vault,
+            rename_vault,
+            get_settings,
+            update_settings,
+            reset_settings,
+            export_settings,
+            import_settings,
+            list_children,
+            refresh_tree_node,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
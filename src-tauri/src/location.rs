@@ -0,0 +1,361 @@
+//! 数据目录（库）的位置
+//!
+//! 优先级：命令行 `--data-dir` > 环境变量 `NOTO_DATA_DIR` > 便携模式 > 位置设置 > 系统默认目录
+//!
+//! 位置设置不能存在数据库里（数据库本身就在数据目录中），
+//! 因此保存在系统配置目录下的 `location.json`
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app::APP_IDENTIFIER;
+use crate::lock::{VaultLock, LOCK_FILE};
+
+/// 指定数据目录的环境变量
+pub const DATA_DIR_ENV: &str = "NOTO_DATA_DIR";
+/// 指定数据目录的命令行选项
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+/// 可执行文件旁存在该文件时启用便携模式
+const PORTABLE_MARKER: &str = "portable";
+/// 便携模式下的数据目录（位于可执行文件旁）
+const PORTABLE_DIR: &str = "noto-data";
+/// 位置设置文件
+const LOCATION_FILE: &str = "location.json";
+
+/// 数据目录的来源
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Flag,
+    Env,
+    Portable,
+    Setting,
+    Default,
+}
+
+impl Source {
+    /// 由设置决定的位置才能在应用内修改；命令行、环境变量和便携模式以启动方式为准
+    pub fn is_configurable(self) -> bool {
+        matches!(self, Source::Setting | Source::Default)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LocationSetting {
+    data_dir: PathBuf,
+}
+
+/// 按优先级确定数据目录
+pub fn resolve(flag: Option<PathBuf>, default: PathBuf) -> (PathBuf, Source) {
+    if let Some(dir) = flag {
+        return (dir, Source::Flag);
+    }
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+        return (PathBuf::from(dir), Source::Env);
+    }
+    if let Some(dir) = portable_dir() {
+        return (dir, Source::Portable);
+    }
+    if let Some(dir) = load_setting() {
+        return (dir, Source::Setting);
+    }
+    (default, Source::Default)
+}
+
+/// 从应用启动参数中读取 `--data-dir <path>` / `--data-dir=<path>`
+pub fn flag_from_args() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg
+            .strip_prefix(DATA_DIR_FLAG)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+/// 便携模式：可执行文件旁有 `portable` 标记文件时，使用旁边的 `noto-data/`
+fn portable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let exe_dir = exe.parent()?;
+    exe_dir
+        .join(PORTABLE_MARKER)
+        .is_file()
+        .then(|| exe_dir.join(PORTABLE_DIR))
+}
+
+/// 系统配置目录下的应用配置文件（不随库移动的设置）
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join(name))
+}
+
+/// 写入配置文件：先写临时文件再替换，避免写到一半时丢失设置
+pub fn write_config_file(name: &str, content: &str) -> Result<(), String> {
+    let path =
+        config_file(name).ok_or_else(|| "cannot determine the config directory".to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+}
+
+fn setting_path() -> Option<PathBuf> {
+    config_file(LOCATION_FILE)
+}
+
+fn load_setting() -> Option<PathBuf> {
+    let content = fs::read_to_string(setting_path()?).ok()?;
+    let setting: LocationSetting = serde_json::from_str(&content).ok()?;
+    Some(setting.data_dir)
+}
+
+/// 保存位置设置；None 表示恢复系统默认目录
+pub fn save_setting(data_dir: Option<&Path>) -> Result<(), String> {
+    let Some(data_dir) = data_dir else {
+        let path =
+            setting_path().ok_or_else(|| "cannot determine the config directory".to_string())?;
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    };
+
+    let content = serde_json::to_string_pretty(&LocationSetting {
+        data_dir: data_dir.to_path_buf(),
+    })
+    .map_err(|e| e.to_string())?;
+    write_config_file(LOCATION_FILE, &content)
+}
+
+/// 校验新的库位置：必须是绝对路径，且不能与当前目录互相嵌套
+pub fn validate_target(current: &Path, target: &Path) -> Result<(), String> {
+    if !target.is_absolute() {
+        return Err("data directory must be an absolute path".to_string());
+    }
+    let current = current
+        .canonicalize()
+        .unwrap_or_else(|_| current.to_path_buf());
+    let target = normalize(target);
+    if target == current {
+        return Err("the vault is already in this directory".to_string());
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err(
+            "the new location cannot be inside the current vault or contain it".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// 规范化尚不存在的路径：解析已存在的最长前缀
+fn normalize(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    while let Some(parent) = existing.parent() {
+        if existing.exists() {
+            break;
+        }
+        if let Some(name) = existing.file_name() {
+            rest.push(name.to_os_string());
+        }
+        existing = parent;
+    }
+    let mut normalized = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    normalized.extend(rest.iter().rev());
+    normalized
+}
+
+/// 把数据目录整体复制到新位置，校验后更新数据库中的绝对路径并删除旧文件
+///
+/// 复制前独占库锁（库正在被使用时拒绝移动），并持有数据库写锁，避免同时写入导致新旧副本不一致；
+/// 返回的库锁由调用方持有到切换到新位置（重启）为止。
+/// 任一步骤失败时删除新位置已复制的内容，旧数据保持不变
+pub fn move_vault(from: &Path, to: &Path) -> Result<VaultLock, String> {
+    validate_target(from, to)?;
+    if to.exists()
+        && fs::read_dir(to)
+            .map_err(|e| e.to_string())?
+            .next()
+            .is_some()
+    {
+        return Err("the new location must be an empty directory".to_string());
+    }
+
+    // 1. 持有库锁与写锁后复制（其他连接的写入会等待或超时失败）；锁文件不复制
+    let lock = crate::lock::acquire_exclusive().map_err(|e| e.to_string())?;
+    let conn = crate::db::connection::get_connection().map_err(|e| e.to_string())?;
+    conn.execute_batch("BEGIN IMMEDIATE;")
+        .map_err(|e| e.to_string())?;
+    let entries = fs::read_dir(from)
+        .and_then(|dir| dir.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|entry| entry.file_name() != LOCK_FILE)
+        .collect::<Vec<_>>();
+
+    let copied = (|| -> Result<(), String> {
+        fs::create_dir_all(to).map_err(|e| e.to_string())?;
+        for entry in &entries {
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // 2. 逐个文件校验大小
+        for entry in &entries {
+            verify_copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // 3. 新库中的 note 路径指向新位置
+        rewrite_paths(&to.join("db.sqlite"), from, to)
+    })();
+    let _ = conn.execute_batch("ROLLBACK;");
+    drop(conn);
+
+    if let Err(e) = copied {
+        for entry in &entries {
+            let _ = remove_path(&to.join(entry.file_name()));
+        }
+        return Err(e);
+    }
+
+    // 4. 记住新位置，再删除旧文件（只删除复制过的内容）
+    save_setting(Some(to))?;
+    for entry in &entries {
+        let _ = remove_path(&entry.path());
+    }
+    let _ = fs::remove_file(from.join(LOCK_FILE));
+    let _ = fs::remove_dir(from);
+    Ok(lock)
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<(), String> {
+    if from.is_dir() {
+        fs::create_dir_all(to).map_err(|e| e.to_string())?;
+        for entry in fs::read_dir(from).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to).map_err(|e| format!("{}: {}", from.display(), e))?;
+    }
+    Ok(())
+}
+
+fn verify_copy(from: &Path, to: &Path) -> Result<(), String> {
+    if from.is_dir() {
+        for entry in fs::read_dir(from).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            verify_copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    let expected = fs::metadata(from).map_err(|e| e.to_string())?.len();
+    match fs::metadata(to) {
+        Ok(meta) if meta.len() == expected => Ok(()),
+        _ => Err(format!("copy of {} is incomplete", from.display())),
+    }
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// 新库中把 notes.content_path 以及撤销历史中的文件路径从旧目录改到新目录
+///
+/// 路径变化不作为同步修改导出
+fn rewrite_paths(db_path: &Path, from: &Path, to: &Path) -> Result<(), String> {
+    let mut conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
+    crate::db::vault::apply_key(&conn, db_path).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let relocate = |path: &str| {
+        Path::new(path)
+            .strip_prefix(from)
+            .ok()
+            .map(|relative| to.join(relative).to_string_lossy().into_owned())
+    };
+
+    let last_seq: i64 = tx
+        .query_row("SELECT COALESCE(MAX(seq), 0) FROM sync_log", [], |r| {
+            r.get(0)
+        })
+        .map_err(|e| e.to_string())?;
+
+    // 1. note 文件路径
+    let notes: Vec<(String, String)> = query_pairs(&tx, "SELECT id, content_path FROM notes")?;
+    for (id, content_path) in notes {
+        if let Some(path) = relocate(&content_path) {
+            tx.execute(
+                "UPDATE notes SET content_path = ? WHERE id = ?",
+                rusqlite::params![path, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    // 2. 撤销历史：文件快照的路径，以及 note 记录快照中的 content_path
+    let files: Vec<(i64, String)> = query_pairs(&tx, "SELECT rowid, path FROM undo_files")?;
+    for (rowid, path) in files {
+        if let Some(path) = relocate(&path) {
+            tx.execute(
+                "UPDATE undo_files SET path = ? WHERE rowid = ?",
+                rusqlite::params![path, rowid],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    for column in ["before_row", "after_row"] {
+        let rows: Vec<(i64, String)> = query_pairs(
+            &tx,
+            &format!(
+                "SELECT rowid, {0} FROM undo_changes WHERE table_name = 'notes' AND {0} IS NOT NULL",
+                column
+            ),
+        )?;
+        for (rowid, json) in rows {
+            let Ok(mut row) = serde_json::from_str::<serde_json::Value>(&json) else {
+                continue;
+            };
+            let Some(path) = row
+                .get("content_path")
+                .and_then(|p| p.as_str())
+                .and_then(relocate)
+            else {
+                continue;
+            };
+            row["content_path"] = serde_json::Value::from(path);
+            tx.execute(
+                &format!("UPDATE undo_changes SET {} = ? WHERE rowid = ?", column),
+                rusqlite::params![row.to_string(), rowid],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.execute("DELETE FROM sync_log WHERE seq > ?", [last_seq])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn query_pairs<A, B>(conn: &rusqlite::Connection, sql: &str) -> Result<Vec<(A, B)>, String>
+where
+    A: rusqlite::types::FromSql,
+    B: rusqlite::types::FromSql,
+{
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,170 @@
+//! 库级别的建议锁 `<data_dir>/.noto.lock`：应用与命令行工具修改同一个库时互斥
+//!
+//! 锁按进程计数：同一进程内可重入（例如持有锁时提交版本历史），只在不同进程之间互斥；
+//! 进程异常退出时由系统释放。替换整个数据库文件（加密 / 解密库）或切换库时使用独占锁，
+//! 期间本进程的其他操作也需等待。锁状态按库目录记录，切换库后旧库的锁仍由其持有者释放
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 锁文件（位于数据目录下）
+pub(crate) const LOCK_FILE: &str = ".noto.lock";
+/// 等待其他进程释放锁的最长时间
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+struct Held {
+    file: File,
+    count: usize,
+    /// 独占：本进程内也不可重入
+    exclusive: bool,
+}
+
+/// 本进程持有的锁，键为库目录
+static HELD: Lazy<Mutex<HashMap<PathBuf, Held>>> = Lazy::new(Default::default);
+
+/// 持有期间其他进程无法获得该库的锁；该库最后一个 guard 释放时解锁
+pub struct VaultLock {
+    root: PathBuf,
+}
+
+/// 获取当前库的锁；其他进程持有（或本进程正在独占）时等待，超时返回错误
+pub fn acquire() -> io::Result<VaultLock> {
+    lock(&crate::app::data_dir(), false, true)
+}
+
+/// 独占当前库：本进程或其他进程正在使用时立即返回错误，不等待
+///
+/// 持有期间本进程的 [`acquire`] 会等待，其他进程无法获得锁
+pub fn acquire_exclusive() -> io::Result<VaultLock> {
+    lock(&crate::app::data_dir(), true, false)
+}
+
+/// 独占当前库：先等待本进程与其他进程正在进行的操作结束，超时返回错误
+pub fn wait_exclusive() -> io::Result<VaultLock> {
+    lock(&crate::app::data_dir(), true, true)
+}
+
+fn in_use(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::WouldBlock, message.to_string())
+}
+
+fn lock(root: &Path, exclusive: bool, wait: bool) -> io::Result<VaultLock> {
+    let started = Instant::now();
+    loop {
+        let mut held = HELD
+            .lock()
+            .map_err(|_| io::Error::other("vault lock poisoned"))?;
+        match held.get_mut(root) {
+            Some(state) if !exclusive && !state.exclusive => {
+                state.count += 1;
+                return Ok(VaultLock {
+                    root: root.to_path_buf(),
+                });
+            }
+            Some(_) if !wait => {
+                return Err(in_use(
+                    "the vault is in use: wait for running operations to finish",
+                ));
+            }
+            Some(_) => {}
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(root.join(LOCK_FILE))?;
+                match file.try_lock() {
+                    Ok(()) => {
+                        held.insert(
+                            root.to_path_buf(),
+                            Held {
+                                file,
+                                count: 1,
+                                exclusive,
+                            },
+                        );
+                        return Ok(VaultLock {
+                            root: root.to_path_buf(),
+                        });
+                    }
+                    Err(TryLockError::WouldBlock) if !wait => {
+                        return Err(in_use("the vault is in use by another noto process"));
+                    }
+                    Err(TryLockError::WouldBlock) => {}
+                    Err(TryLockError::Error(e)) => return Err(e),
+                }
+            }
+        }
+        drop(held);
+
+        if started.elapsed() >= WAIT_TIMEOUT {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the vault is busy: another operation or noto process is using it",
+            ));
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+impl Drop for VaultLock {
+    fn drop(&mut self) {
+        let Ok(mut held) = HELD.lock() else {
+            return;
+        };
+        if let Some(state) = held.get_mut(&self.root) {
+            state.count -= 1;
+            if state.count == 0 {
+                if let Some(state) = held.remove(&self.root) {
+                    let _ = state.file.unlock();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("noto-lock-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn shared_locks_are_reentrant_and_block_exclusive() {
+        let root = temp_root();
+        let first = lock(&root, false, true).unwrap();
+        let second = lock(&root, false, true).unwrap();
+        assert!(lock(&root, true, false).is_err());
+
+        drop(first);
+        assert!(lock(&root, true, false).is_err());
+        drop(second);
+        let exclusive = lock(&root, true, false).unwrap();
+        assert!(lock(&root, false, false).is_err());
+        drop(exclusive);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn locks_are_kept_per_vault() {
+        let (a, b) = (temp_root(), temp_root());
+        let held_a = lock(&a, false, true).unwrap();
+        let exclusive_b = lock(&b, true, false).unwrap();
+        drop(held_a);
+        assert!(lock(&a, true, false).is_ok());
+        drop(exclusive_b);
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+}
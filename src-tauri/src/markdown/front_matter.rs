@@ -0,0 +1,196 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use serde_yaml::{Mapping, Value};
+
+/// 解析后的 YAML front matter
+///
+/// title / tags / aliases / created 与数据库同步，其余键作为 properties 原样暴露
+#[derive(Debug, Default)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// created 的 Unix 时间戳（无时区的日期时间按本地时间解释）
+    pub created: Option<i64>,
+    pub properties: JsonMap<String, JsonValue>,
+}
+
+/// 拆分 front matter：返回 (YAML 文本, 正文)
+///
+/// front matter 必须从第一行 `---` 开始，以单独一行 `---` 或 `...` 结束
+pub fn split(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((yaml, body));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+/// 去掉 front matter，只保留正文
+pub fn strip(content: &str) -> &str {
+    split(content).map(|(_, body)| body).unwrap_or(content)
+}
+
+/// 解析 front matter；没有 front matter 时返回 Ok(None)
+pub fn parse(content: &str) -> Result<Option<FrontMatter>, String> {
+    let Some((yaml, _)) = split(content) else {
+        return Ok(None);
+    };
+
+    let mapping = match serde_yaml::from_str::<Value>(yaml)
+        .map_err(|e| format!("invalid front matter: {}", e))?
+    {
+        Value::Mapping(m) => m,
+        Value::Null => Mapping::new(),
+        _ => return Err("invalid front matter: expected a mapping".to_string()),
+    };
+
+    let mut fm = FrontMatter::default();
+    for (key, value) in mapping {
+        let Some(key) = key.as_str().map(str::to_string) else {
+            continue;
+        };
+        match key.as_str() {
+            "title" => fm.title = scalar_string(&value).filter(|t| !t.trim().is_empty()),
+            "tags" => fm.tags = string_list(&value, true),
+            "aliases" => fm.aliases = string_list(&value, false),
+            "created" => fm.created = scalar_string(&value).and_then(|s| parse_datetime(&s)),
+            _ => {
+                if let Ok(json) = serde_json::to_value(&value) {
+                    fm.properties.insert(key, json);
+                }
+            }
+        }
+    }
+
+    Ok(Some(fm))
+}
+
+/// 修改 front matter 中的若干键（value 为 None 时删除该键），返回新的完整内容
+///
+/// 没有 front matter 时：create = true 则新建，否则返回 None（保持纯 Markdown 文件不变）
+pub fn set_keys(
+    content: &str,
+    keys: &[(&str, Option<Value>)],
+    create: bool,
+) -> Result<Option<String>, String> {
+    let (mut mapping, body) = match split(content) {
+        Some((yaml, body)) => match serde_yaml::from_str::<Value>(yaml)
+            .map_err(|e| format!("invalid front matter: {}", e))?
+        {
+            Value::Mapping(m) => (m, body),
+            Value::Null => (Mapping::new(), body),
+            _ => return Err("invalid front matter: expected a mapping".to_string()),
+        },
+        None if create => (Mapping::new(), content),
+        None => return Ok(None),
+    };
+
+    for (key, value) in keys {
+        let key = Value::String(key.to_string());
+        match value {
+            Some(v) => {
+                mapping.insert(key, v.clone());
+            }
+            None => {
+                mapping.shift_remove(&key);
+            }
+        }
+    }
+
+    if mapping.is_empty() {
+        return Ok(Some(body.to_string()));
+    }
+
+    let yaml = serde_yaml::to_string(&mapping).map_err(|e| e.to_string())?;
+    Ok(Some(format!("---\n{}---\n{}", yaml, body)))
+}
+
+/// 字符串列表转为 YAML 序列（空列表返回 None，即删除该键）
+pub fn list_value(items: &[String]) -> Option<Value> {
+    if items.is_empty() {
+        return None;
+    }
+    Some(Value::Sequence(
+        items.iter().cloned().map(Value::String).collect(),
+    ))
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// 列表或逗号分隔字符串；tags 额外按空白拆分并去掉前导 `#`
+fn string_list(value: &Value, is_tags: bool) -> Vec<String> {
+    let raw: Vec<String> = match value {
+        Value::Sequence(items) => items.iter().filter_map(scalar_string).collect(),
+        other => scalar_string(other)
+            .map(|s| {
+                s.split(',')
+                    .flat_map(|part| {
+                        if is_tags {
+                            part.split_whitespace().map(str::to_string).collect()
+                        } else {
+                            vec![part.to_string()]
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let mut out: Vec<String> = Vec::new();
+    for item in raw {
+        let item = item.trim();
+        let item = if is_tags {
+            item.trim_start_matches('#')
+        } else {
+            item
+        };
+        if !item.is_empty() && !out.iter().any(|x| x == item) {
+            out.push(item.to_string());
+        }
+    }
+    out
+}
+
+/// 解析 created：RFC 3339、`YYYY-MM-DD HH:MM[:SS]`、`YYYY-MM-DD` 或 Unix 时间戳
+fn parse_datetime(s: &str) -> Option<i64> {
+    let s = s.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.timestamp());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()
+            .map(|dt| dt.timestamp());
+    }
+
+    s.parse::<i64>().ok()
+}
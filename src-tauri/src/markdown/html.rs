@@ -0,0 +1,198 @@
+use base64::Engine;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::path::{Path, PathBuf};
+
+use crate::lang::detect::normalize_language;
+use crate::lang::highlight::{highlight_html, html_escape, HighlightOptions};
+use crate::util::percent_decode;
+
+/// 导出页面的基础样式（打印友好，不依赖外部资源）
+const STYLE: &str = r#"
+body { margin: 0; background: #fff; color: #24292f; }
+.noto-note { max-width: 860px; margin: 0 auto; padding: 32px 24px; font: 16px/1.6 -apple-system, "Segoe UI", "PingFang SC", "Microsoft YaHei", sans-serif; }
+.noto-note h1, .noto-note h2 { border-bottom: 1px solid #d8dee4; padding-bottom: .3em; }
+.noto-note a { color: #0969da; }
+.noto-note code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 90%; }
+.noto-note :not(pre) > code { background: #f6f8fa; padding: .2em .4em; border-radius: 4px; }
+.noto-note pre.noto-code { border-radius: 6px; line-height: 1.45; }
+.noto-note blockquote { margin: 0; padding: 0 1em; color: #57606a; border-left: .25em solid #d0d7de; }
+.noto-note table { border-collapse: collapse; }
+.noto-note th, .noto-note td { border: 1px solid #d0d7de; padding: 6px 13px; }
+.noto-note img { max-width: 100%; }
+.noto-note li > input[type=checkbox] { margin-right: .5em; }
+.noto-note .footnote-definition { font-size: 90%; color: #57606a; }
+.noto-note .footnote-definition p { display: inline; }
+@media print { .noto-note { max-width: none; padding: 0; } }
+"#;
+
+/// 渲染选项
+pub struct RenderOptions<'a> {
+    /// note 文件所在目录（相对链接以此为基准）
+    pub note_dir: &'a Path,
+    /// 应用数据目录（附件位于其下的 attachments/）
+    pub app_data_dir: &'a Path,
+    /// 代码高亮主题，None 时使用默认主题
+    pub theme: Option<String>,
+}
+
+/// 将 Markdown 渲染为完整的独立 HTML 页面
+pub fn render_document(
+    title: &str,
+    markdown: &str,
+    options: &RenderOptions,
+) -> Result<String, String> {
+    let body = render_body(markdown, options)?;
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<article class=\"noto-note\">\n{}</article>\n</body>\n</html>\n",
+        html_escape(title),
+        STYLE,
+        body
+    ))
+}
+
+/// 将 Markdown 渲染为 HTML 片段
+///
+/// - GFM 表格 / 任务列表 / 脚注 / 删除线
+/// - 围栏代码块按语言高亮（内联样式）
+/// - 指向 attachments/ 的本地图片内嵌为 data URI，其他本地附件链接改写为 file:// 绝对路径
+pub fn render_body(markdown: &str, options: &RenderOptions) -> Result<String, String> {
+    let mut parser_options = Options::empty();
+    parser_options.insert(Options::ENABLE_TABLES);
+    parser_options.insert(Options::ENABLE_TASKLISTS);
+    parser_options.insert(Options::ENABLE_FOOTNOTES);
+    parser_options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let highlight = HighlightOptions {
+        theme: options.theme.clone(),
+        ..Default::default()
+    };
+
+    let mut events = Vec::new();
+    // 正在收集的代码块：(语言, 代码)
+    let mut code_block: Option<(Option<String>, String)> = None;
+
+    for event in Parser::new_ext(markdown, parser_options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .map(|l| normalize_language(l).unwrap_or(l).to_string()),
+                    CodeBlockKind::Indented => None,
+                };
+                code_block = Some((language, String::new()));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, ref mut code)) = code_block {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((language, code)) = code_block.take() {
+                    let html = highlight_html(&code, language.as_deref(), &highlight)?;
+                    events.push(Event::Html(CowStr::from(html)));
+                }
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let dest_url = match resolve_attachment(&dest_url, options) {
+                    Some(path) => data_uri(&path).unwrap_or_else(|| file_url(&path)),
+                    None => dest_url.to_string(),
+                };
+                events.push(Event::Start(Tag::Image {
+                    link_type,
+                    dest_url: CowStr::from(dest_url),
+                    title,
+                    id,
+                }));
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let dest_url = match resolve_attachment(&dest_url, options) {
+                    Some(path) => file_url(&path),
+                    None => dest_url.to_string(),
+                };
+                events.push(Event::Start(Tag::Link {
+                    link_type,
+                    dest_url: CowStr::from(dest_url),
+                    title,
+                    id,
+                }));
+            }
+            other => events.push(other),
+        }
+    }
+
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, events.into_iter());
+
+    Ok(out)
+}
+
+/// 解析本地附件链接：只接受指向 `<app_data_dir>/attachments/` 下已存在文件的相对路径
+///
+/// 依次尝试相对 note 所在目录（如 `../attachments/images/a.png`）
+/// 和相对应用数据目录（如 `attachments/images/a.png`）
+fn resolve_attachment(url: &str, options: &RenderOptions) -> Option<PathBuf> {
+    if url.is_empty() || url.starts_with('#') || url.starts_with('/') || url.contains(':') {
+        return None;
+    }
+
+    let relative = percent_decode(url.split(['?', '#']).next().unwrap_or(url));
+    let attachments_dir = options
+        .app_data_dir
+        .join("attachments")
+        .canonicalize()
+        .ok()?;
+
+    [options.note_dir, options.app_data_dir]
+        .iter()
+        .filter_map(|base| base.join(&relative).canonicalize().ok())
+        .find(|path| path.starts_with(&attachments_dir) && path.is_file())
+}
+
+fn file_url(path: &Path) -> String {
+    // Windows 上 canonicalize 返回 `\\?\` 前缀的扩展路径
+    let path = path.to_string_lossy();
+    let path = path
+        .strip_prefix(r"\\?\")
+        .unwrap_or(&path)
+        .replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+/// 图片内嵌为 data URI（非图片扩展名返回 None）
+fn data_uri(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    };
+
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
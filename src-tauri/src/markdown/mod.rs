@@ -0,0 +1,3 @@
+pub mod front_matter;
+pub mod html;
+pub mod outline;
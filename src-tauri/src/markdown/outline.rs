@@ -0,0 +1,189 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::markdown::front_matter;
+
+/// 英文阅读速度（词 / 分钟）
+const WORDS_PER_MINUTE: usize = 200;
+/// 中日韩文字阅读速度（字 / 分钟）
+const CJK_CHARS_PER_MINUTE: usize = 400;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// 锚点（GitHub 风格，重复时追加 -1、-2）
+    pub slug: String,
+    /// 行号（1 起始，相对完整文件内容）
+    pub line: usize,
+    /// 标题起止字节偏移（相对完整文件内容）
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub children: Vec<Heading>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct DocumentStats {
+    pub words: usize,
+    pub characters: usize,
+    pub code_blocks: usize,
+    pub reading_minutes: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Outline {
+    pub headings: Vec<Heading>,
+    pub stats: DocumentStats,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // 平假名 / 片假名
+        | 0x3400..=0x4DBF   // 扩展 A
+        | 0x4E00..=0x9FFF   // 基本汉字
+        | 0xAC00..=0xD7AF   // 韩文
+        | 0xF900..=0xFAFF)
+}
+
+/// GitHub 风格 slug：小写，去掉标点，空白转为 `-`
+fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c)
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// 按层级把扁平标题列表组装为树（跳级的标题挂到最近的上级下）
+fn build_tree(flat: Vec<Heading>) -> Vec<Heading> {
+    let mut roots: Vec<Heading> = Vec::new();
+    let mut stack: Vec<Heading> = Vec::new();
+
+    for heading in flat {
+        while let Some(top) = stack.last() {
+            if top.level < heading.level {
+                break;
+            }
+            let done = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(done),
+                None => roots.push(done),
+            }
+        }
+        stack.push(heading);
+    }
+
+    while let Some(done) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    }
+
+    roots
+}
+
+/// 提取标题树与文档统计（front matter 不计入，偏移仍相对完整内容）
+pub fn extract(content: &str) -> Outline {
+    let body = front_matter::strip(content);
+    let base = content.len() - body.len();
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut flat = Vec::new();
+    let mut slugs: HashMap<String, usize> = HashMap::new();
+    let mut stats = DocumentStats::default();
+    let mut cjk_chars = 0;
+    let mut latin_words = 0;
+
+    // 正在收集的标题：(level, text, range)
+    let mut current: Option<(u8, String, std::ops::Range<usize>)> = None;
+    let mut in_code_block = false;
+
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((heading_level(level), String::new(), range));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text, range)) = current.take() {
+                    let text = text.trim().to_string();
+                    let mut slug = slugify(&text);
+                    let seen = slugs.entry(slug.clone()).or_insert(0);
+                    if *seen > 0 {
+                        slug = format!("{}-{}", slug, seen);
+                    }
+                    *seen += 1;
+
+                    let byte_start = base + range.start;
+                    flat.push(Heading {
+                        level,
+                        text,
+                        slug,
+                        line: content[..byte_start].matches('\n').count() + 1,
+                        byte_start,
+                        byte_end: base + range.end,
+                        children: Vec::new(),
+                    });
+                }
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                stats.code_blocks += 1;
+            }
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, ref mut heading_text, _)) = current {
+                    heading_text.push_str(&text);
+                }
+                if in_code_block {
+                    continue;
+                }
+
+                stats.characters += text.chars().filter(|c| !c.is_whitespace()).count();
+                cjk_chars += text.chars().filter(|c| is_cjk(*c)).count();
+                latin_words += text
+                    .split(|c: char| c.is_whitespace() || is_cjk(c))
+                    .filter(|w| w.chars().any(char::is_alphanumeric))
+                    .count();
+            }
+            _ => {}
+        }
+    }
+
+    stats.words = latin_words + cjk_chars;
+    if stats.words > 0 {
+        let minutes = latin_words as f64 / WORDS_PER_MINUTE as f64
+            + cjk_chars as f64 / CJK_CHARS_PER_MINUTE as f64;
+        stats.reading_minutes = (minutes.ceil() as usize).max(1);
+    }
+
+    Outline {
+        headings: build_tree(flat),
+        stats,
+    }
+}
@@ -0,0 +1,286 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use uuid::Uuid;
+
+use super::notes::insert_note_with_node;
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models as db_models;
+use crate::fs::notes::delete_note_file;
+use crate::history::ChangeKind;
+use crate::settings::{self, Settings};
+
+/// 日记根文件夹名称（notes scope 根级文件夹）
+const JOURNAL_FOLDER_KEY: &str = "journal.folder";
+/// 新建日记默认使用的 note 模板
+const JOURNAL_TEMPLATE_KEY: &str = "journal.template_id";
+
+#[derive(Serialize)]
+pub struct JournalSettings {
+    pub folder_name: String,
+    pub template_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DailyNote {
+    pub date: String,
+    pub note_id: String,
+    pub node_id: Option<String>,
+    /// 本次调用是否新建
+    pub created: bool,
+    /// 模板中 `{{cursor}}` 的位置（仅新建时）
+    pub cursor: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct DailyNoteEntry {
+    pub date: String,
+    pub note_id: String,
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| Error::Invalid(format!("invalid date: {}", date)))
+}
+
+/// 查找或创建 notes scope 下的文件夹（parent_id 为 None 时为根级）
+fn ensure_notes_folder(
+    tx: &Transaction,
+    parent_id: Option<&str>,
+    name: &str,
+    order_index: i64,
+    now: i64,
+) -> Result<String> {
+    let existing: Option<String> = tx
+        .query_row(
+            r#"
+            SELECT id FROM tree_nodes
+            WHERE parent_id IS ? AND name = ? AND node_type = 'folder' AND scope = 'notes'
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+            params![parent_id, name],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let node_id = Uuid::new_v4().to_string();
+    db_models::insert_tree_node(
+        tx,
+        &node_id,
+        parent_id,
+        name,
+        "folder",
+        "notes",
+        order_index,
+        now,
+    )?;
+
+    Ok(node_id)
+}
+
+/// 从偏好设置中读取日记设置
+fn load_settings(conn: &Connection) -> Result<JournalSettings> {
+    Ok(JournalSettings {
+        folder_name: settings::value(conn, JOURNAL_FOLDER_KEY)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        template_id: settings::value(conn, JOURNAL_TEMPLATE_KEY)?
+            .as_str()
+            .map(str::to_string),
+    })
+}
+
+/// 获取日记设置
+pub fn get_settings(storage: &Storage) -> Result<JournalSettings> {
+    load_settings(&storage.conn)
+}
+
+/// 更新日记设置（只影响之后新建的日记，已有日记保持原位置），返回更新后的全部设置
+pub fn update_settings(
+    storage: &mut Storage,
+    folder_name: &str,
+    template_id: Option<&str>,
+) -> Result<Settings> {
+    let mut values = JsonMap::new();
+    values.insert(JOURNAL_FOLDER_KEY.to_string(), JsonValue::from(folder_name));
+    values.insert(
+        JOURNAL_TEMPLATE_KEY.to_string(),
+        JsonValue::from(template_id),
+    );
+    settings::update(&storage.conn, values).map_err(Error::Invalid)
+}
+
+/// 打开某天的日记（date 格式 YYYY-MM-DD），不存在时创建
+///
+/// 新日记位于 `<Journal>/<YYYY>/<MM>/`，标题为日期；
+/// template_id 为空时使用日记设置中的默认模板
+pub fn open_daily(
+    storage: &mut Storage,
+    date: &str,
+    template_id: Option<&str>,
+) -> Result<DailyNote> {
+    let now = Utc::now().timestamp();
+    let day = parse_date(date)?;
+    let date = day.format("%Y-%m-%d").to_string();
+
+    let journal = load_settings(&storage.conn)?;
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    // 1. 已有日记直接返回
+    let existing: Option<String> = tx
+        .query_row(
+            r#"
+            SELECT d.note_id FROM daily_notes d
+            JOIN notes n ON n.id = d.note_id
+            WHERE d.date = ?
+            "#,
+            params![&date],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    if let Some(note_id) = existing {
+        let node_id: Option<String> = tx
+            .query_row(
+                "SELECT node_id FROM node_resources WHERE resource_type = 'note' AND resource_id = ? LIMIT 1",
+                params![&note_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+
+        return Ok(DailyNote {
+            date,
+            note_id,
+            node_id,
+            created: false,
+            cursor: None,
+        });
+    }
+
+    // 2. 确保 Journal / 年 / 月 文件夹存在
+    let template_id = template_id
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .or(journal.template_id);
+
+    let journal_id = ensure_notes_folder(&tx, None, &journal.folder_name, 0, now)?;
+    let year_id = ensure_notes_folder(
+        &tx,
+        Some(&journal_id),
+        &day.format("%Y").to_string(),
+        day.year() as i64,
+        now,
+    )?;
+    let month_id = ensure_notes_folder(
+        &tx,
+        Some(&year_id),
+        &day.format("%m").to_string(),
+        day.month() as i64,
+        now,
+    )?;
+
+    // 3. 创建 note 并登记到 daily_notes（清理指向已删除 note 的旧记录）
+    let (created, note_file_path) = insert_note_with_node(
+        &tx,
+        &storage.root,
+        &date,
+        Some(&month_id),
+        template_id.as_deref(),
+        now,
+    )?;
+
+    let result = tx
+        .execute("DELETE FROM daily_notes WHERE date = ?", params![&date])
+        .and_then(|_| {
+            tx.execute(
+                "INSERT INTO daily_notes (date, note_id, created_at) VALUES (?, ?, ?)",
+                params![&date, &created.note_id, now],
+            )
+        })
+        .and_then(|_| tx.commit());
+
+    if let Err(e) = result {
+        delete_note_file(&note_file_path);
+        return Err(e.into());
+    }
+
+    changelog::record(&storage.conn, "Create daily note");
+    storage.note_changed(ChangeKind::Create, &created.note_id, Some(&date));
+    Ok(DailyNote {
+        date,
+        note_id: created.note_id,
+        node_id: Some(created.node_id),
+        created: true,
+        cursor: created.cursor,
+    })
+}
+
+/// 查找相邻的已有日记（previous = true 时向前）
+pub fn adjacent(storage: &Storage, date: &str, previous: bool) -> Result<Option<DailyNoteEntry>> {
+    let date = parse_date(date)?.format("%Y-%m-%d").to_string();
+    let sql = if previous {
+        r#"
+        SELECT d.date, d.note_id FROM daily_notes d
+        JOIN notes n ON n.id = d.note_id
+        WHERE d.date < ?
+        ORDER BY d.date DESC
+        LIMIT 1
+        "#
+    } else {
+        r#"
+        SELECT d.date, d.note_id FROM daily_notes d
+        JOIN notes n ON n.id = d.note_id
+        WHERE d.date > ?
+        ORDER BY d.date ASC
+        LIMIT 1
+        "#
+    };
+
+    Ok(storage
+        .conn
+        .query_row(sql, params![date], |r| {
+            Ok(DailyNoteEntry {
+                date: r.get(0)?,
+                note_id: r.get(1)?,
+            })
+        })
+        .optional()?)
+}
+
+/// 列出某月有日记的日期（1..=31，升序）
+pub fn list_days(storage: &Storage, year: i32, month: u32) -> Result<Vec<u32>> {
+    if !(1..=12).contains(&month) {
+        return Err(Error::Invalid(format!("invalid month: {}", month)));
+    }
+
+    let mut stmt = storage.conn.prepare(
+        r#"
+        SELECT d.date FROM daily_notes d
+        JOIN notes n ON n.id = d.note_id
+        WHERE d.date LIKE ?
+        ORDER BY d.date
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![format!("{:04}-{:02}-%", year, month)], |r| {
+        r.get::<_, String>(0)
+    })?;
+
+    let mut days = Vec::new();
+    for row in rows {
+        if let Ok(day) = parse_date(&row?) {
+            days.push(day.day());
+        }
+    }
+
+    Ok(days)
+}
@@ -0,0 +1,139 @@
+//! 与 Tauri 无关的业务逻辑：note / 树 / snippet / 任务 / 工时 / 日记 / 项目 / 撤销
+//!
+//! 所有操作都通过显式的 [`Storage`]（数据库连接 + 数据目录）访问数据，
+//! Tauri 命令、HTTP API 与命令行工具只负责参数转换
+
+pub mod journal;
+pub mod note_templates;
+pub mod notes;
+pub mod projects;
+pub mod search;
+pub mod snippet_vscode;
+pub mod snippets;
+pub mod tasks;
+pub mod time_entries;
+pub mod tree;
+pub mod undo;
+
+#[cfg(test)]
+mod tests;
+
+use rusqlite::Connection;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::history::{self, ChangeKind};
+use crate::lock::VaultLock;
+
+/// 存储上下文：数据库连接与数据目录（notes/、attachments/ 所在的目录）
+pub struct Storage {
+    pub conn: Connection,
+    pub root: PathBuf,
+    /// 是否登记 note 版本历史（历史仓库属于应用数据目录，测试等场景不启用）
+    history: bool,
+    /// 应用数据目录的库锁，与同时运行的应用 / 命令行工具互斥
+    _lock: Option<VaultLock>,
+}
+
+impl Storage {
+    /// 以给定的连接和数据目录创建上下文（数据库需已完成迁移）
+    pub fn new(conn: Connection, root: impl Into<PathBuf>) -> Self {
+        Self {
+            conn,
+            root: root.into(),
+            history: false,
+            _lock: None,
+        }
+    }
+
+    /// 应用当前使用的数据库与数据目录；持有库锁直到 Storage 释放
+    pub fn open() -> Result<Self> {
+        let lock = crate::lock::acquire()?;
+        let conn = crate::db::connection::get_connection()?;
+        Ok(Self {
+            history: true,
+            _lock: Some(lock),
+            ..Self::new(conn, crate::app::data_dir())
+        })
+    }
+
+    /// 登记一次 note 变更到版本历史
+    fn note_changed(&self, kind: ChangeKind, note_id: &str, title: Option<&str>) {
+        if self.history {
+            history::record(kind, note_id, title);
+        }
+    }
+
+    /// 从版本历史中清除 note 的旧版本（note 加密后不再保留明文版本）
+    fn forget_note_history(&self, note_id: &str) -> Result<()> {
+        if self.history {
+            history::forget(note_id)
+                .map_err(|e| format!("note encrypted, but clearing its history failed: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// 记录不存在，参数为记录类型（"task"、"note" 等）
+    NotFound(&'static str),
+    /// 参数不合法
+    Invalid(String),
+    /// note 已加密且未解锁
+    Locked,
+    Database(rusqlite::Error),
+    Io(std::io::Error),
+    Other(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// note 已加密且未解锁时的错误信息
+pub const NOTE_LOCKED: &str = "note is locked";
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(what) => write!(f, "{} not found", what),
+            Error::Invalid(message) | Error::Other(message) => f.write_str(message),
+            Error::Locked => f.write_str(NOTE_LOCKED),
+            Error::Database(e) => e.fmt(f),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Error::Other(e.to_string())
+    }
+}
+
+/// 其余模块（changelog、front matter、加密等）以字符串返回错误
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Other(message)
+    }
+}
+
+/// Tauri 命令以字符串返回错误
+impl From<Error> for String {
+    fn from(e: Error) -> Self {
+        e.to_string()
+    }
+}
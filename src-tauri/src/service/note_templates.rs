@@ -0,0 +1,83 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Error, Result, Storage};
+
+#[derive(Serialize)]
+pub struct NoteTemplate {
+    pub id: String,
+    pub name: String,
+    pub content: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn template_from_row(r: &Row) -> rusqlite::Result<NoteTemplate> {
+    Ok(NoteTemplate {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        content: r.get(2)?,
+        created_at: r.get(3)?,
+        updated_at: r.get(4)?,
+    })
+}
+
+/// 读取单个模板
+pub(crate) fn load(conn: &Connection, template_id: &str) -> Result<NoteTemplate> {
+    conn.query_row(
+        "SELECT id, name, content, created_at, updated_at FROM note_templates WHERE id = ?",
+        params![template_id],
+        template_from_row,
+    )
+    .optional()?
+    .ok_or(Error::NotFound("note template"))
+}
+
+/// 列出所有 note 模板（按名称排序）
+pub fn list(storage: &Storage) -> Result<Vec<NoteTemplate>> {
+    let mut stmt = storage.conn.prepare(
+        "SELECT id, name, content, created_at, updated_at FROM note_templates ORDER BY name COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map([], template_from_row)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 创建 note 模板，返回模板 id
+pub fn create(storage: &mut Storage, name: &str, content: &str) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let template_id = Uuid::new_v4().to_string();
+
+    storage.conn.execute(
+        "INSERT INTO note_templates (id, name, content, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
+        params![template_id, name, content, now, now],
+    )?;
+
+    Ok(template_id)
+}
+
+/// 更新 note 模板的名称 / 内容
+pub fn update(storage: &mut Storage, template_id: &str, name: &str, content: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    let updated = storage.conn.execute(
+        "UPDATE note_templates SET name = ?, content = ?, updated_at = ? WHERE id = ?",
+        params![name, content, now, template_id],
+    )?;
+    if updated == 0 {
+        return Err(Error::NotFound("note template"));
+    }
+
+    Ok(())
+}
+
+/// 删除 note 模板（已创建的 note 不受影响）
+pub fn delete(storage: &mut Storage, template_id: &str) -> Result<()> {
+    storage.conn.execute(
+        "DELETE FROM note_templates WHERE id = ?",
+        params![template_id],
+    )?;
+
+    Ok(())
+}
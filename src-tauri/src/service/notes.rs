@@ -0,0 +1,676 @@
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use super::{note_templates, Error, Result, Storage};
+use crate::changelog;
+use crate::crypto::{note as note_crypto, session as crypto_session};
+use crate::db::models::{
+    get_note_aliases, get_note_tags, insert_node_note_resource, insert_note,
+    insert_notes_tree_node, set_note_aliases, set_note_tags,
+};
+use crate::fs::notes::{
+    create_note_file, create_note_file_with_content, delete_note_file, note_file_path,
+};
+use crate::history::ChangeKind;
+use crate::markdown::front_matter::{self, FrontMatter};
+use crate::markdown::html::{self as markdown_html, RenderOptions};
+use crate::markdown::outline::{self, Outline};
+use crate::settings;
+use crate::template::note::{self as note_template, NoteTemplateContext};
+
+#[derive(Serialize)]
+pub struct CreatedNote {
+    pub note_id: String,
+    pub node_id: String,
+    /// 模板中 `{{cursor}}` 的位置（UTF-16 偏移），没有时为 None
+    pub cursor: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct NoteDetail {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// front matter 中除 title / tags / aliases / created 以外的键
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    /// 文件是否以口令加密保存
+    pub encrypted: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 最近的 project 祖先节点名称（从 node_id 自身开始向上查找）
+fn find_project_name(conn: &Connection, node_id: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            r#"
+            WITH RECURSIVE ancestors(id, parent_id, name, node_type, depth) AS (
+                SELECT id, parent_id, name, node_type, 0 FROM tree_nodes WHERE id = ?
+                UNION ALL
+                SELECT t.id, t.parent_id, t.name, t.node_type, a.depth + 1
+                FROM tree_nodes t JOIN ancestors a ON t.id = a.parent_id
+            )
+            SELECT name FROM ancestors WHERE node_type = 'project' ORDER BY depth LIMIT 1
+            "#,
+            params![node_id],
+            |r| r.get(0),
+        )
+        .optional()?)
+}
+
+/// 创建 note；指定 template_id 时用模板内容实例化
+pub fn create(
+    storage: &mut Storage,
+    title: &str,
+    parent_id: Option<&str>,
+    template_id: Option<&str>,
+) -> Result<CreatedNote> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let (created, note_file_path) =
+        insert_note_with_node(&tx, &storage.root, title, parent_id, template_id, now)?;
+
+    if let Err(e) = tx.commit() {
+        delete_note_file(&note_file_path);
+        return Err(e.into());
+    }
+
+    changelog::record(&storage.conn, "Create note");
+    storage.note_changed(ChangeKind::Create, &created.note_id, Some(title));
+    Ok(created)
+}
+
+/// 在事务内创建 note：写 Markdown 文件，插入 tree_node / notes 并挂载
+///
+/// 返回创建结果与文件路径；事务内步骤失败时会删除文件，
+/// 提交失败时需由调用方删除
+pub(crate) fn insert_note_with_node(
+    tx: &Transaction,
+    root: &Path,
+    title: &str,
+    parent_id: Option<&str>,
+    template_id: Option<&str>,
+    now: i64,
+) -> Result<(CreatedNote, PathBuf)> {
+    let note_id = Uuid::new_v4().to_string();
+    let node_id = Uuid::new_v4().to_string();
+
+    // 1. 实例化模板（如果指定）
+    let rendered = match template_id {
+        Some(id) => {
+            let template = note_templates::load(tx, id)?;
+            let project = match parent_id {
+                Some(pid) => find_project_name(tx, pid)?,
+                None => None,
+            };
+            Some(note_template::render(
+                &template.content,
+                &NoteTemplateContext {
+                    title,
+                    project: project.as_deref(),
+                },
+            ))
+        }
+        None => None,
+    };
+
+    // 2. 先创建 Markdown 文件（失败直接返回）
+    let note_file_path = match rendered {
+        Some(ref r) => create_note_file_with_content(root, &note_id, &r.content),
+        None => create_note_file(&root.to_path_buf(), &note_id, title),
+    }?;
+
+    // 3. 插入 tree_nodes（Notes Scope）、notes，并将 Note 挂载到该 tree_node
+    let result = insert_notes_tree_node(tx, &node_id, parent_id, title, 0, now)
+        .and_then(|_| {
+            insert_note(
+                tx,
+                &note_id,
+                title,
+                note_file_path.to_string_lossy().as_ref(),
+                now,
+            )
+        })
+        .and_then(|_| insert_node_note_resource(tx, &node_id, &note_id, now));
+
+    if let Err(e) = result {
+        delete_note_file(&note_file_path);
+        return Err(e.into());
+    }
+
+    Ok((
+        CreatedNote {
+            note_id,
+            node_id,
+            cursor: rendered.and_then(|r| r.cursor),
+        },
+        note_file_path,
+    ))
+}
+
+/// 以已有内容新建 note（历史版本检出 / 同步冲突副本）
+///
+/// 明文内容中 front matter 的 title 改为新标题；加密内容原样保留并标记 encrypted。
+/// 只写入数据库，返回文件路径与内容，由调用方在事务提交后落盘
+pub(crate) fn insert_note_copy(
+    tx: &Transaction,
+    root: &Path,
+    title: &str,
+    parent_id: Option<&str>,
+    content: &str,
+    now: i64,
+) -> Result<(CreatedNote, PathBuf, String)> {
+    let encrypted = note_crypto::is_encrypted(content);
+    let retitled = if encrypted {
+        None
+    } else {
+        front_matter::set_keys(
+            content,
+            &[("title", Some(serde_yaml::Value::String(title.to_string())))],
+            false,
+        )
+        .ok()
+        .flatten()
+    };
+    let content = retitled.unwrap_or_else(|| content.to_string());
+
+    let note_id = Uuid::new_v4().to_string();
+    let node_id = Uuid::new_v4().to_string();
+    let path = note_file_path(root, &note_id);
+
+    insert_notes_tree_node(tx, &node_id, parent_id, title, 0, now)?;
+    insert_note(tx, &note_id, title, path.to_string_lossy().as_ref(), now)?;
+    insert_node_note_resource(tx, &node_id, &note_id, now)?;
+    tx.execute(
+        "UPDATE notes SET encrypted = ? WHERE id = ?",
+        params![encrypted, &note_id],
+    )?;
+
+    Ok((
+        CreatedNote {
+            note_id,
+            node_id,
+            cursor: None,
+        },
+        path,
+        content,
+    ))
+}
+
+/// note 所在文件夹（挂载节点的父节点）；未挂载或位于根级时为 None
+pub(crate) fn note_parent_id(conn: &Connection, note_id: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            r#"
+            SELECT t.parent_id
+            FROM node_resources r
+            JOIN tree_nodes t ON t.id = r.node_id
+            WHERE r.resource_id = ? AND r.resource_type = 'note'
+            LIMIT 1
+            "#,
+            params![note_id],
+            |r| r.get(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+/// 获取笔记详情（标题 + 内容）
+///
+/// 文件可能在应用外被修改：front matter 与数据库不一致时，先在短事务中同步回数据库
+pub fn get(storage: &mut Storage, note_id: &str) -> Result<NoteDetail> {
+    let detail = load_detail(&storage.conn, note_id)?;
+    let Ok(Some(fm)) = front_matter::parse(&detail.content) else {
+        return Ok(detail);
+    };
+    if in_sync(&detail, &fm) {
+        return Ok(detail);
+    }
+
+    let now = Utc::now().timestamp();
+    let tx = storage.conn.transaction()?;
+    sync_front_matter(&tx, note_id, &fm, now)?;
+    tx.commit()?;
+    load_detail(&storage.conn, note_id)
+}
+
+/// 数据库中的 title / tags / aliases / created 是否已与 front matter 一致
+fn in_sync(detail: &NoteDetail, fm: &FrontMatter) -> bool {
+    // 数据库中的标签、别名按字母序保存且不重复
+    let sorted = |items: &[String]| {
+        let mut items = items.to_vec();
+        items.sort();
+        items.dedup();
+        items
+    };
+    fm.title.as_ref().is_none_or(|title| *title == detail.title)
+        && sorted(&fm.tags) == detail.tags
+        && sorted(&fm.aliases) == detail.aliases
+        && fm
+            .created
+            .is_none_or(|created| created == detail.created_at)
+}
+
+/// 读取 note 元信息并加载 Markdown 内容
+pub(crate) fn load_detail(conn: &Connection, note_id: &str) -> Result<NoteDetail> {
+    let row = conn
+        .query_row(
+            "SELECT id, title, content_path, encrypted, created_at, updated_at FROM notes WHERE id = ?",
+            params![note_id],
+            |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                    r.get::<_, bool>(3)?,
+                    r.get::<_, i64>(4)?,
+                    r.get::<_, i64>(5)?,
+                ))
+            },
+        )
+        .optional()?
+        .ok_or(Error::NotFound("note"))?;
+
+    let content = read_content(&row.2, note_id)?;
+
+    // front matter 格式错误时不影响读取，只是没有 properties
+    let properties = front_matter::parse(&content)
+        .ok()
+        .flatten()
+        .map(|fm| fm.properties)
+        .unwrap_or_default();
+
+    Ok(NoteDetail {
+        tags: get_note_tags(conn, note_id)?,
+        aliases: get_note_aliases(conn, note_id)?,
+        properties,
+        id: row.0,
+        title: row.1,
+        content,
+        encrypted: row.3,
+        created_at: row.4,
+        updated_at: row.5,
+    })
+}
+
+/// 读取 note 文件内容；加密文件使用当前解锁会话的密钥解密，未解锁时返回 Error::Locked
+pub(crate) fn read_content(content_path: &str, note_id: &str) -> Result<String> {
+    let raw = fs::read_to_string(content_path)?;
+    if !note_crypto::is_encrypted(&raw) {
+        return Ok(raw);
+    }
+
+    let (key, _) = crypto_session::key_for(note_id).ok_or(Error::Locked)?;
+    Ok(note_crypto::decrypt(&raw, &key)?)
+}
+
+/// 写入 note 文件内容；原文件已加密时用解锁会话的密钥重新加密，未解锁时返回 Error::Locked
+pub(crate) fn write_content(content_path: &str, note_id: &str, content: &str) -> Result<()> {
+    let encrypted = fs::read_to_string(content_path)
+        .map(|raw| note_crypto::is_encrypted(&raw))
+        .unwrap_or(false);
+    if !encrypted {
+        return Ok(fs::write(content_path, content)?);
+    }
+
+    let (key, salt) = crypto_session::key_for(note_id).ok_or(Error::Locked)?;
+    let sealed = note_crypto::encrypt(content, &key, &salt)?;
+    Ok(fs::write(content_path, sealed)?)
+}
+
+fn content_path(conn: &Connection, note_id: &str) -> Result<String> {
+    conn.query_row(
+        "SELECT content_path FROM notes WHERE id = ?",
+        params![note_id],
+        |r| r.get(0),
+    )
+    .optional()?
+    .ok_or(Error::NotFound("note"))
+}
+
+/// 更新 notes 标题，并同步挂载该 note 的树节点名称
+fn rename(tx: &Transaction, note_id: &str, title: &str, now: i64) -> Result<()> {
+    tx.execute(
+        "UPDATE notes SET title = ?, updated_at = ? WHERE id = ?",
+        params![title, now, note_id],
+    )?;
+    tx.execute(
+        r#"
+        UPDATE tree_nodes
+        SET name = ?, updated_at = ?
+        WHERE id IN (
+            SELECT node_id FROM node_resources
+            WHERE resource_type = 'note' AND resource_id = ?
+        )
+        "#,
+        params![title, now, note_id],
+    )?;
+
+    Ok(())
+}
+
+/// front matter → 数据库：title / tags / aliases / created
+///
+/// 文件中有 front matter 时以其为准（缺少 tags / aliases 键视为清空）
+fn sync_front_matter(tx: &Transaction, note_id: &str, fm: &FrontMatter, now: i64) -> Result<()> {
+    if let Some(ref title) = fm.title {
+        let current: String = tx.query_row(
+            "SELECT title FROM notes WHERE id = ?",
+            params![note_id],
+            |r| r.get(0),
+        )?;
+        if *title != current {
+            rename(tx, note_id, title, now)?;
+        }
+    }
+
+    set_note_tags(tx, note_id, &fm.tags)?;
+    set_note_aliases(tx, note_id, &fm.aliases)?;
+
+    if let Some(created) = fm.created {
+        tx.execute(
+            "UPDATE notes SET created_at = ? WHERE id = ?",
+            params![created, note_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 更新笔记标题，并同步树节点名称
+///
+/// 文件已有 front matter 时同时改写其中的 title
+pub fn update_title(storage: &mut Storage, note_id: &str, title: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    rename(&tx, note_id, title, now)?;
+
+    // 加密且未解锁的 note 无法改写文件，只更新数据库
+    let content_path = content_path(&tx, note_id)?;
+    let updated = read_content(&content_path, note_id)
+        .ok()
+        .and_then(|content| {
+            front_matter::set_keys(
+                &content,
+                &[("title", Some(serde_yaml::Value::String(title.to_string())))],
+                false,
+            )
+            .ok()
+            .flatten()
+        });
+
+    tx.commit()?;
+
+    // 改写文件（在事务提交后），写入前保存原内容以便撤销
+    if let Some(updated) = updated {
+        changelog::capture_file(&storage.conn, &content_path)?;
+        write_content(&content_path, note_id, &updated)?;
+    }
+    changelog::record(&storage.conn, "Rename note");
+    storage.note_changed(ChangeKind::Update, note_id, None);
+    Ok(())
+}
+
+/// 去除首尾空白与重复项；strip_hash 时去掉标签开头的 `#`
+fn normalize_list(items: &[String], strip_hash: bool) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in items {
+        let item = item.trim();
+        let item = if strip_hash {
+            item.trim_start_matches('#')
+        } else {
+            item
+        };
+        if !item.is_empty() && !out.iter().any(|x| x == item) {
+            out.push(item.to_string());
+        }
+    }
+    out
+}
+
+/// 更新笔记的标签 / 别名，并写入文件的 front matter（没有时新建）
+pub fn update_metadata(
+    storage: &mut Storage,
+    note_id: &str,
+    tags: &[String],
+    aliases: &[String],
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let tags = normalize_list(tags, true);
+    let aliases = normalize_list(aliases, false);
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    set_note_tags(&tx, note_id, &tags)?;
+    set_note_aliases(&tx, note_id, &aliases)?;
+    tx.execute(
+        "UPDATE notes SET updated_at = ? WHERE id = ?",
+        params![now, note_id],
+    )?;
+
+    let content_path = content_path(&tx, note_id)?;
+    let content = read_content(&content_path, note_id)?;
+    let create = !tags.is_empty() || !aliases.is_empty();
+    let updated = front_matter::set_keys(
+        &content,
+        &[
+            ("tags", front_matter::list_value(&tags)),
+            ("aliases", front_matter::list_value(&aliases)),
+        ],
+        create,
+    )?;
+
+    tx.commit()?;
+
+    // 改写 front matter（在事务提交后），写入前保存原内容以便撤销
+    if let Some(updated) = updated {
+        changelog::capture_file(&storage.conn, &content_path)?;
+        write_content(&content_path, note_id, &updated)?;
+    }
+    changelog::record(&storage.conn, "Edit note tags");
+    storage.note_changed(ChangeKind::Update, note_id, None);
+    Ok(())
+}
+
+/// 更新笔记内容，并更新 notes.updated_at
+///
+/// 内容中的 front matter 会同步到数据库（格式错误时忽略）
+pub fn update_content(storage: &mut Storage, note_id: &str, content: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let content_path = content_path(&storage.conn, note_id)?;
+
+    // 写文件（加密 note 需处于解锁状态），写入前保存原内容以便撤销
+    changelog::begin(&storage.conn)?;
+    changelog::capture_file(&storage.conn, &content_path)?;
+    write_content(&content_path, note_id, content)?;
+
+    // 更新 updated_at，并同步 front matter
+    let tx = storage.conn.transaction()?;
+    tx.execute(
+        "UPDATE notes SET updated_at = ? WHERE id = ?",
+        params![now, note_id],
+    )?;
+    if let Ok(Some(fm)) = front_matter::parse(content) {
+        sync_front_matter(&tx, note_id, &fm, now)?;
+    }
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Edit note");
+    storage.note_changed(ChangeKind::Update, note_id, None);
+
+    Ok(())
+}
+
+/// 以口令加密 note 文件（XChaCha20-Poly1305，密钥由 Argon2id 派生），加密后处于锁定状态
+///
+/// 版本历史中该 note 的明文旧版本一并清除
+pub fn encrypt(storage: &mut Storage, note_id: &str, passphrase: &str) -> Result<()> {
+    if passphrase.is_empty() {
+        return Err(Error::Invalid("passphrase is empty".to_string()));
+    }
+    let content_path = content_path(&storage.conn, note_id)?;
+    let raw = fs::read_to_string(&content_path)?;
+    if note_crypto::is_encrypted(&raw) {
+        return Err(Error::Invalid("note is already encrypted".to_string()));
+    }
+
+    let salt = note_crypto::new_salt();
+    let key = note_crypto::derive_key(passphrase, &salt)?;
+    let sealed = note_crypto::encrypt(&raw, &key, &salt)?;
+
+    replace_encrypted(storage, note_id, &content_path, &raw, &sealed, true)?;
+    storage.forget_note_history(note_id)?;
+    storage.note_changed(ChangeKind::Update, note_id, None);
+    Ok(())
+}
+
+/// 校验口令并解除 note 加密，文件恢复为明文
+pub fn decrypt(storage: &mut Storage, note_id: &str, passphrase: &str) -> Result<()> {
+    let content_path = content_path(&storage.conn, note_id)?;
+    let raw = fs::read_to_string(&content_path)?;
+    if !note_crypto::is_encrypted(&raw) {
+        return Err(Error::Invalid("note is not encrypted".to_string()));
+    }
+
+    let salt = note_crypto::read_salt(&raw)?;
+    let key = note_crypto::derive_key(passphrase, &salt)?;
+    let plaintext = note_crypto::decrypt(&raw, &key)?;
+
+    replace_encrypted(storage, note_id, &content_path, &raw, &plaintext, false)?;
+    storage.note_changed(ChangeKind::Update, note_id, None);
+    Ok(())
+}
+
+/// 在同一事务中更新 encrypted 标记并替换文件内容
+///
+/// 新内容先写入临时文件，标记更新后再替换原文件；提交失败时恢复原文件，
+/// 保证文件是否为密文与标记一致。撤销历史中涉及该文件的操作一并丢弃
+fn replace_encrypted(
+    storage: &mut Storage,
+    note_id: &str,
+    content_path: &str,
+    original: &str,
+    replacement: &str,
+    encrypted: bool,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let tmp_path = format!("{}.tmp", content_path);
+    fs::write(&tmp_path, replacement)?;
+
+    let tx = storage.conn.transaction()?;
+    let result = (|| -> Result<()> {
+        tx.execute(
+            "UPDATE notes SET encrypted = ?, updated_at = ? WHERE id = ?",
+            params![encrypted, now, note_id],
+        )?;
+        fs::rename(&tmp_path, content_path)?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    if let Err(e) = tx.commit() {
+        fs::write(content_path, original)?;
+        return Err(e.into());
+    }
+
+    crypto_session::lock(note_id);
+    changelog::forget_file(&storage.conn, content_path)?;
+    Ok(())
+}
+
+/// 渲染 note 为完整 HTML 页面（本地图片相对 note 文件或数据目录解析）
+///
+/// theme 为空时使用设置中的代码高亮主题
+pub fn render_html(storage: &Storage, note_id: &str, theme: Option<String>) -> Result<String> {
+    let (title, content_path): (String, String) = storage
+        .conn
+        .query_row(
+            "SELECT title, content_path FROM notes WHERE id = ?",
+            params![note_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+        .ok_or(Error::NotFound("note"))?;
+    let content = read_content(&content_path, note_id)?;
+    let theme = match theme {
+        Some(theme) => theme,
+        None => settings::highlight_theme(&storage.conn)?,
+    };
+
+    let content_path = PathBuf::from(content_path);
+    let note_dir = content_path.parent().unwrap_or(&storage.root);
+
+    Ok(markdown_html::render_document(
+        &title,
+        front_matter::strip(&content),
+        &RenderOptions {
+            note_dir,
+            app_data_dir: &storage.root,
+            theme: Some(theme),
+        },
+    )?)
+}
+
+/// 文件修订：修改时间与大小
+type Revision = (SystemTime, u64);
+
+/// 大纲缓存：note_id -> (文件修订, 大纲)；文件未变时不再读取和解密
+static OUTLINE_CACHE: Lazy<Mutex<HashMap<String, (Revision, Outline)>>> =
+    Lazy::new(Default::default);
+/// 缓存条目上限，超出时整体清空
+const OUTLINE_CACHE_CAPACITY: usize = 256;
+
+/// note 的标题树（层级 / 文本 / 锚点 / 行号与字节偏移）及文档统计
+///
+/// 按文件修改时间缓存；加密 note 需处于解锁状态，命中缓存时同样检查
+pub fn outline(storage: &Storage, note_id: &str) -> Result<Outline> {
+    let (content_path, encrypted): (String, bool) = storage
+        .conn
+        .query_row(
+            "SELECT content_path, encrypted FROM notes WHERE id = ?",
+            params![note_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+        .ok_or(Error::NotFound("note"))?;
+    if encrypted && !crypto_session::is_unlocked(note_id) {
+        return Err(Error::Locked);
+    }
+
+    let metadata = fs::metadata(&content_path)?;
+    let revision = (metadata.modified()?, metadata.len());
+    {
+        let cache = OUTLINE_CACHE.lock().map_err(|e| e.to_string())?;
+        if let Some((cached_revision, outline)) = cache.get(note_id) {
+            if *cached_revision == revision {
+                return Ok(outline.clone());
+            }
+        }
+    }
+
+    let outline = outline::extract(&read_content(&content_path, note_id)?);
+    let mut cache = OUTLINE_CACHE.lock().map_err(|e| e.to_string())?;
+    if cache.len() >= OUTLINE_CACHE_CAPACITY && !cache.contains_key(note_id) {
+        cache.clear();
+    }
+    cache.insert(note_id.to_string(), (revision, outline.clone()));
+    Ok(outline)
+}
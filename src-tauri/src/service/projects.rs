@@ -0,0 +1,197 @@
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Transaction};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::notes::{load_detail, NoteDetail};
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models as db_models;
+use crate::fs::notes::{create_note_file, delete_note_file};
+use crate::history::ChangeKind;
+
+#[derive(Serialize)]
+pub struct ProjectDetail {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub node_type: String,
+    pub scope: String,
+    pub order_index: i64,
+    pub description_note_id: Option<String>,
+    pub description: Option<NoteDetail>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 校验节点存在且为 project，返回当前的描述 note id
+fn ensure_project_node(tx: &Transaction, node_id: &str) -> Result<Option<String>> {
+    let row: Option<(String, Option<String>)> = tx
+        .query_row(
+            "SELECT node_type, description_note_id FROM tree_nodes WHERE id = ?",
+            params![node_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+
+    match row {
+        None => Err(Error::NotFound("node")),
+        Some((node_type, _)) if node_type != "project" => {
+            Err(Error::Invalid("node is not a project".to_string()))
+        }
+        Some((_, description_note_id)) => Ok(description_note_id),
+    }
+}
+
+/// 为项目创建描述 note，返回新 note id
+///
+/// 描述 note 不挂载到 notes 树，仅通过 tree_nodes.description_note_id 引用
+pub fn create_description(
+    storage: &mut Storage,
+    node_id: &str,
+    title: Option<&str>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let note_id = Uuid::new_v4().to_string();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    if ensure_project_node(&tx, node_id)?.is_some() {
+        return Err(Error::Invalid(
+            "project already has a description note".to_string(),
+        ));
+    }
+
+    let title = match title {
+        Some(t) => t.to_string(),
+        None => tx.query_row(
+            "SELECT name FROM tree_nodes WHERE id = ?",
+            params![node_id],
+            |r| r.get::<_, String>(0),
+        )?,
+    };
+
+    // 1. 创建 Markdown 文件
+    let note_file_path = create_note_file(&storage.root, &note_id, &title)?;
+
+    // 2. 插入 notes 并关联到项目（失败时回滚文件）
+    let result = db_models::insert_note(
+        &tx,
+        &note_id,
+        &title,
+        note_file_path.to_string_lossy().as_ref(),
+        now,
+    )
+    .and_then(|_| db_models::set_node_description_note(&tx, node_id, Some(&note_id), now))
+    .and_then(|_| tx.commit());
+
+    if let Err(e) = result {
+        delete_note_file(&note_file_path);
+        return Err(e.into());
+    }
+
+    changelog::record(&storage.conn, "Create project description");
+    storage.note_changed(ChangeKind::Create, &note_id, Some(&title));
+    Ok(note_id)
+}
+
+/// 将已有 note 设为项目描述（替换原有关联，不删除原描述 note）
+pub fn attach_description(storage: &mut Storage, node_id: &str, note_id: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    ensure_project_node(&tx, node_id)?;
+
+    // 确认 note 存在
+    db_models::get_note_detail(&tx, note_id).map_err(|_| Error::NotFound("note"))?;
+
+    db_models::set_node_description_note(&tx, node_id, Some(note_id), now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Attach project description");
+
+    Ok(())
+}
+
+/// 解除项目描述关联
+///
+/// 若该 note 不再被任何节点引用（例如由 create_description 创建），
+/// 会一并删除 note 记录及其 Markdown 文件
+pub fn detach_description(storage: &mut Storage, node_id: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let Some(note_id) = ensure_project_node(&tx, node_id)? else {
+        return Ok(());
+    };
+
+    db_models::set_node_description_note(&tx, node_id, None, now)?;
+
+    let mut file_to_delete: Option<String> = None;
+    let mut deleted_title: Option<String> = None;
+    if !db_models::is_note_referenced(&tx, &note_id)? {
+        if let Ok((_id, content_path)) = db_models::get_note_detail(&tx, &note_id) {
+            file_to_delete = Some(content_path);
+        }
+        deleted_title = db_models::get_note_title(&tx, &note_id)?;
+        db_models::delete_note(&tx, &note_id)?;
+    }
+
+    tx.commit()?;
+
+    // 文件在事务提交后删除
+    if let Some(path) = file_to_delete {
+        changelog::capture_file(&storage.conn, &path)?;
+        let _ = std::fs::remove_file(path);
+        storage.note_changed(ChangeKind::Delete, &note_id, deleted_title.as_deref());
+    }
+    changelog::record(&storage.conn, "Detach project description");
+
+    Ok(())
+}
+
+/// 获取项目节点及其描述 note
+pub fn get(storage: &Storage, node_id: &str) -> Result<ProjectDetail> {
+    let mut project = storage
+        .conn
+        .query_row(
+            "SELECT id, parent_id, name, node_type, scope, order_index, description_note_id, created_at, updated_at FROM tree_nodes WHERE id = ?",
+            params![node_id],
+            |r| {
+                Ok(ProjectDetail {
+                    id: r.get(0)?,
+                    parent_id: r.get(1)?,
+                    name: r.get(2)?,
+                    node_type: r.get(3)?,
+                    scope: r.get(4)?,
+                    order_index: r.get(5)?,
+                    description_note_id: r.get(6)?,
+                    description: None,
+                    created_at: r.get(7)?,
+                    updated_at: r.get(8)?,
+                })
+            },
+        )
+        .optional()?
+        .ok_or(Error::NotFound("node"))?;
+
+    if project.node_type != "project" {
+        return Err(Error::Invalid("node is not a project".to_string()));
+    }
+
+    if let Some(ref note_id) = project.description_note_id {
+        // 描述 note 已加密且未解锁时不返回内容，不影响项目本身的读取
+        project.description = match load_detail(&storage.conn, note_id) {
+            Ok(detail) => Some(detail),
+            Err(Error::Locked) => None,
+            Err(e) => return Err(e),
+        };
+    }
+
+    Ok(project)
+}
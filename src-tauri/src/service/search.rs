@@ -0,0 +1,96 @@
+//! 按标题与内容搜索 note / 任务 / snippet
+
+use serde::Serialize;
+
+use super::notes::read_content;
+use super::{Result, Storage};
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    /// 'note' | 'task' | 'snippet'
+    pub kind: &'static str,
+    pub id: String,
+    pub title: String,
+    /// 内容中第一处匹配所在的行
+    pub excerpt: Option<String>,
+}
+
+/// 不区分大小写地搜索，每类按最近修改排序；加密 note 未解锁时只匹配标题
+pub fn search(storage: &Storage, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    let query = query.to_lowercase();
+    let mut hits = Vec::new();
+
+    // 1. note：文件内容不在数据库中，逐个读取（加密 note 用解锁会话的密钥解密）
+    let mut stmt = storage
+        .conn
+        .prepare("SELECT id, title, content_path FROM notes ORDER BY updated_at DESC")?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (id, title, content_path) = row?;
+        let excerpt = read_content(&content_path, &id)
+            .ok()
+            .and_then(|content| matching_line(&content, &query));
+        if excerpt.is_some() || title.to_lowercase().contains(&query) {
+            hits.push(SearchHit {
+                kind: "note",
+                id,
+                title,
+                excerpt,
+            });
+        }
+    }
+
+    // 2. 任务与 snippet
+    let sources: [(&'static str, &str); 2] = [
+        (
+            "task",
+            "SELECT id, title, COALESCE(description, '') FROM tasks ORDER BY updated_at DESC",
+        ),
+        (
+            "snippet",
+            r#"
+            SELECT s.id, s.title,
+                   COALESCE(s.description, '') || char(10) ||
+                   COALESCE((SELECT group_concat(f.content, char(10)) FROM snippet_files f WHERE f.snippet_id = s.id), s.content)
+            FROM snippets s ORDER BY s.updated_at DESC
+            "#,
+        ),
+    ];
+    for (kind, sql) in sources {
+        let mut stmt = storage.conn.prepare(sql)?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, title, body) = row?;
+            let excerpt = matching_line(&body, &query);
+            if excerpt.is_some() || title.to_lowercase().contains(&query) {
+                hits.push(SearchHit {
+                    kind,
+                    id,
+                    title,
+                    excerpt,
+                });
+            }
+        }
+    }
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+fn matching_line(content: &str, query: &str) -> Option<String> {
+    content
+        .lines()
+        .find(|line| line.to_lowercase().contains(query))
+        .map(|line| line.trim().chars().take(120).collect())
+}
@@ -0,0 +1,150 @@
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Transaction};
+use std::fs;
+use std::path::Path;
+
+use super::snippets::insert_snippet_with_node;
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models as db_models;
+use crate::interop::vscode::{self, VscodeSnippet};
+
+/// 校验节点存在且属于 snippets scope，返回 node_type
+fn ensure_snippets_node(tx: &Transaction, node_id: &str) -> Result<String> {
+    let row: Option<(String, String)> = tx
+        .query_row(
+            "SELECT node_type, scope FROM tree_nodes WHERE id = ?",
+            params![node_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+
+    match row {
+        None => Err(Error::NotFound("node")),
+        Some((_, scope)) if scope != "snippets" => {
+            Err(Error::Invalid("node is not in snippets scope".to_string()))
+        }
+        Some((node_type, _)) => Ok(node_type),
+    }
+}
+
+/// 按树顺序（深度优先，兄弟按 order_index）收集子树中的 snippet id
+fn collect_subtree_snippets(tx: &Transaction, node_id: &str, out: &mut Vec<String>) -> Result<()> {
+    for (resource_id, resource_type) in db_models::get_node_resources(tx, node_id)? {
+        if resource_type == "snippet" {
+            out.push(resource_id);
+        }
+    }
+
+    let child_ids: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT id FROM tree_nodes WHERE parent_id = ? ORDER BY order_index, created_at",
+        )?;
+        let rows = stmt.query_map(params![node_id], |r| r.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for child_id in child_ids {
+        collect_subtree_snippets(tx, &child_id, out)?;
+    }
+
+    Ok(())
+}
+
+/// 从 VS Code snippet 文件（`.code-snippets` / `<language>.json`）导入到指定文件夹，返回导入数量
+///
+/// - body 数组按行拼接为内容，`$1` / `${1:default}` 等占位符原样保留
+/// - scope 的第一个语言作为 snippet 语言；没有 scope 时自动识别
+/// - prefix / description / scope 原样保存，用于导出时还原
+pub fn import(storage: &mut Storage, parent_id: &str, path: &Path) -> Result<usize> {
+    let now = Utc::now().timestamp();
+
+    let input = fs::read_to_string(path)?;
+    let snippets = vscode::parse(&input).map_err(Error::Invalid)?;
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    if ensure_snippets_node(&tx, parent_id)? != "folder" {
+        return Err(Error::Invalid("target node is not a folder".to_string()));
+    }
+
+    let first_order: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(order_index) + 1, 0) FROM tree_nodes WHERE parent_id = ?",
+        params![parent_id],
+        |r| r.get(0),
+    )?;
+
+    for (i, snippet) in snippets.iter().enumerate() {
+        let language = snippet
+            .scopes
+            .first()
+            .map(|scope| vscode::language_from_scope(scope));
+
+        let snippet_id = insert_snippet_with_node(
+            &tx,
+            Some(parent_id),
+            &snippet.name,
+            language,
+            snippet.body.clone(),
+            first_order + i as i64,
+            now,
+        )?;
+
+        let prefix = snippet.prefixes.join("\n");
+        let scope = snippet.scopes.join(",");
+        db_models::update_snippet_meta(
+            &tx,
+            &snippet_id,
+            Some(prefix.as_str()).filter(|p| !p.is_empty()),
+            snippet.description.as_deref(),
+            Some(scope.as_str()).filter(|s| !s.is_empty()),
+            now,
+        )?;
+    }
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Import VS Code snippets");
+
+    Ok(snippets.len())
+}
+
+/// 将 snippets 子树（文件夹或单个 snippet 节点）导出为 `.code-snippets` 文件，返回导出数量
+///
+/// 多文件 snippet 只导出主文件内容；没有保存 scope 时按语言生成
+pub fn export(storage: &mut Storage, node_id: &str, path: &Path) -> Result<usize> {
+    let tx = storage.conn.transaction()?;
+
+    ensure_snippets_node(&tx, node_id)?;
+
+    let mut snippet_ids = Vec::new();
+    collect_subtree_snippets(&tx, node_id, &mut snippet_ids)?;
+
+    let mut snippets = Vec::with_capacity(snippet_ids.len());
+    for snippet_id in snippet_ids {
+        let snippet = db_models::get_snippet(&tx, &snippet_id)?;
+
+        let scopes = match (snippet.scope, snippet.language) {
+            (Some(scope), _) => scope.split(',').map(|s| s.trim().to_string()).collect(),
+            (None, Some(language)) => vec![vscode::scope_from_language(&language)],
+            (None, None) => Vec::new(),
+        };
+
+        snippets.push(VscodeSnippet {
+            name: snippet.title,
+            prefixes: snippet
+                .prefix
+                .map(|p| p.lines().map(str::to_string).collect())
+                .unwrap_or_default(),
+            body: snippet.content,
+            description: snippet.description,
+            scopes,
+        });
+    }
+
+    tx.commit()?;
+
+    fs::write(path, vscode::to_json(&snippets)?)?;
+
+    Ok(snippets.len())
+}
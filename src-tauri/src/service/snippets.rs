@@ -0,0 +1,564 @@
+use chrono::Utc;
+use rusqlite::{params, Transaction};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models::{self as db_models, SnippetFileRecord};
+use crate::lang::detect::{default_file_name, language_from_file_name};
+use crate::lang::{detect_language, highlight_ansi, highlight_html, HighlightOptions};
+use crate::settings;
+use crate::template::snippet::{self as snippet_template, TemplateVariable};
+
+#[derive(Serialize)]
+pub struct SnippetDetail {
+    pub id: String,
+    pub title: String,
+    pub language: Option<String>,
+    pub language_detected: bool,
+    pub language_confident: bool,
+    pub content: String,
+    pub use_count: i64,
+    pub last_used_at: Option<i64>,
+    pub prefix: Option<String>,
+    pub description: Option<String>,
+    pub scope: Option<String>,
+    pub files: Vec<SnippetFileRecord>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 最终写入的语言：(language, detected, confident)
+///
+/// 用户指定的语言优先；未指定（或为空）时依次根据文件名、内容自动识别
+pub(crate) fn resolve_language(
+    language: Option<String>,
+    file_name: Option<&str>,
+    content: &str,
+) -> (Option<String>, bool, bool) {
+    if let Some(l) = language.filter(|l| !l.trim().is_empty()) {
+        return (Some(l), false, true);
+    }
+
+    if let Some(l) = file_name.and_then(language_from_file_name) {
+        return (Some(l.to_string()), true, true);
+    }
+
+    match detect_language(content) {
+        Some(d) => (Some(d.language.to_string()), true, d.confident),
+        None => (None, false, false),
+    }
+}
+
+/// 前端回传的语言与已保存的自动识别结果相同时，不视为用户指定（之后编辑仍会重新识别）
+fn user_language(
+    language: Option<String>,
+    stored: Option<&str>,
+    stored_detected: bool,
+) -> Option<String> {
+    language.filter(|l| !(stored_detected && stored == Some(l.as_str())))
+}
+
+/// 创建 snippet，并在 snippets scope 下创建对应 tree_node + node_resources，返回 snippet id
+pub fn create(
+    storage: &mut Storage,
+    title: &str,
+    language: Option<String>,
+    content: String,
+    parent_id: Option<&str>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+    let snippet_id = insert_snippet_with_node(&tx, parent_id, title, language, content, 0, now)?;
+    tx.commit()?;
+    changelog::record(&storage.conn, "Create snippet");
+
+    Ok(snippet_id)
+}
+
+/// 在事务内创建 snippet 及其 tree_node、主文件和挂载关系，返回 snippet id
+pub(crate) fn insert_snippet_with_node(
+    tx: &Transaction,
+    parent_id: Option<&str>,
+    title: &str,
+    language: Option<String>,
+    content: String,
+    order_index: i64,
+    now: i64,
+) -> Result<String> {
+    let snippet_id = Uuid::new_v4().to_string();
+    let node_id = Uuid::new_v4().to_string();
+
+    // 1. 插入 tree_nodes（Snippets Scope）
+    db_models::insert_snippets_tree_node(tx, &node_id, parent_id, title, order_index, now)?;
+
+    // 2. 插入 snippets（未指定语言时自动识别）
+    let (language, detected, confident) = resolve_language(language, None, &content);
+    db_models::insert_snippet(tx, &snippet_id, title, language.as_deref(), &content, now)?;
+    db_models::update_snippet_language(tx, &snippet_id, language.as_deref(), detected, confident)?;
+
+    // 3. 创建主文件
+    db_models::insert_snippet_file(
+        tx,
+        &SnippetFileRecord {
+            id: Uuid::new_v4().to_string(),
+            snippet_id: snippet_id.clone(),
+            file_name: default_file_name(language.as_deref()),
+            language,
+            language_detected: detected,
+            language_confident: confident,
+            content,
+            order_index: 0,
+            created_at: now,
+            updated_at: now,
+        },
+    )?;
+
+    // 4. 挂载 snippet 到 tree_node
+    db_models::insert_node_snippet_resource(tx, &node_id, &snippet_id, now)?;
+
+    Ok(snippet_id)
+}
+
+/// 获取 snippet 详情及其全部文件（不涉及 tree）
+pub fn get(storage: &Storage, snippet_id: &str) -> Result<SnippetDetail> {
+    // 只读查询：在同一事务内读取，保证 snippet 与文件一致
+    let tx = storage.conn.unchecked_transaction()?;
+    let snippet =
+        db_models::get_snippet(&tx, snippet_id).map_err(|_| Error::NotFound("snippet"))?;
+    let files = db_models::list_snippet_files(&tx, snippet_id)?;
+    tx.commit()?;
+
+    Ok(SnippetDetail {
+        id: snippet.id,
+        title: snippet.title,
+        language: snippet.language,
+        language_detected: snippet.language_detected,
+        language_confident: snippet.language_confident,
+        content: snippet.content,
+        use_count: snippet.use_count,
+        last_used_at: snippet.last_used_at,
+        prefix: snippet.prefix,
+        description: snippet.description,
+        scope: snippet.scope,
+        files,
+        created_at: snippet.created_at,
+        updated_at: snippet.updated_at,
+    })
+}
+
+/// 更新 snippet 的标题 / 语言 / 内容（内容与语言写入主文件）
+pub fn update(
+    storage: &mut Storage,
+    snippet_id: &str,
+    title: &str,
+    language: Option<String>,
+    content: String,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let snippet =
+        db_models::get_snippet(&tx, snippet_id).map_err(|_| Error::NotFound("snippet"))?;
+
+    let mut files = db_models::list_snippet_files(&tx, snippet_id)?;
+    let primary_name = files.first().map(|f| f.file_name.clone());
+
+    let language = user_language(
+        language,
+        snippet.language.as_deref(),
+        snippet.language_detected,
+    );
+    let (language, detected, confident) =
+        resolve_language(language, primary_name.as_deref(), &content);
+    db_models::update_snippet(&tx, snippet_id, title, language.as_deref(), &content, now)?;
+    db_models::update_snippet_language(&tx, snippet_id, language.as_deref(), detected, confident)?;
+
+    // 同步主文件
+    if !files.is_empty() {
+        let mut primary = files.remove(0);
+        primary.language = language;
+        primary.language_detected = detected;
+        primary.language_confident = confident;
+        primary.content = content;
+        db_models::update_snippet_file(&tx, &primary, now)?;
+    }
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Edit snippet");
+
+    Ok(())
+}
+
+/// 更新 snippet 的编辑器元信息（prefix / description / scope，空字符串视为清空）
+pub fn update_meta(
+    storage: &mut Storage,
+    snippet_id: &str,
+    prefix: Option<&str>,
+    description: Option<&str>,
+    scope: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    fn non_empty(v: Option<&str>) -> Option<&str> {
+        v.filter(|s| !s.trim().is_empty())
+    }
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    db_models::get_snippet(&tx, snippet_id).map_err(|_| Error::NotFound("snippet"))?;
+    db_models::update_snippet_meta(
+        &tx,
+        snippet_id,
+        non_empty(prefix),
+        non_empty(description),
+        non_empty(scope),
+        now,
+    )?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Edit snippet details");
+
+    Ok(())
+}
+
+/// 删除 snippet 及其 node_resources 记录，但不删除 tree_node
+///
+/// 由调用方通过树操作删除 tree_node，复用其「有子节点/有资源」校验逻辑
+pub fn delete(storage: &mut Storage, snippet_id: &str) -> Result<()> {
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    tx.execute(
+        "DELETE FROM node_resources WHERE resource_id = ? AND resource_type = 'snippet'",
+        params![snippet_id],
+    )?;
+    db_models::delete_snippet(&tx, snippet_id)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Delete snippet");
+
+    Ok(())
+}
+
+/// 批量识别 snippet 文件语言，返回更新的文件数
+///
+/// - 默认只处理 language 为空的文件
+/// - redetect = true 时，同时重新识别此前自动识别得出的语言（不影响用户指定的语言）
+pub fn detect_languages(storage: &mut Storage, redetect: bool) -> Result<usize> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let rows: Vec<(String, String, String, String)> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, snippet_id, file_name, content FROM snippet_files WHERE language IS NULL OR (? AND language_detected = 1)",
+        )?;
+        let mapped = stmt.query_map(params![redetect], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+        })?;
+        mapped.collect::<rusqlite::Result<_>>()?
+    };
+
+    let mut updated = 0;
+    let mut touched_snippets: Vec<String> = Vec::new();
+    for (file_id, snippet_id, file_name, content) in rows {
+        let (Some(language), detected, confident) =
+            resolve_language(None, Some(&file_name), &content)
+        else {
+            continue;
+        };
+
+        tx.execute(
+            "UPDATE snippet_files SET language = ?, language_detected = ?, language_confident = ? WHERE id = ?",
+            params![language, detected, confident, file_id],
+        )?;
+        updated += 1;
+
+        if !touched_snippets.contains(&snippet_id) {
+            touched_snippets.push(snippet_id);
+        }
+    }
+
+    // 主文件语言变化时同步回 snippets 表
+    for snippet_id in touched_snippets {
+        db_models::sync_snippet_primary_file(&tx, &snippet_id, now)?;
+    }
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Detect snippet languages");
+
+    Ok(updated)
+}
+
+/// 将 snippet 渲染为语法高亮文本，format 为 "html"（内联样式）或 "ansi"（终端真彩色）
+///
+/// 未指定主题时使用设置中的代码高亮主题
+pub fn render(
+    storage: &Storage,
+    snippet_id: &str,
+    format: &str,
+    mut options: HighlightOptions,
+) -> Result<String> {
+    let snippet = get(storage, snippet_id)?;
+    if options.theme.is_none() {
+        options.theme = Some(settings::highlight_theme(&storage.conn)?);
+    }
+    let language = snippet.language.as_deref();
+    match format {
+        "html" => highlight_html(&snippet.content, language, &options).map_err(Error::Invalid),
+        "ansi" => highlight_ansi(&snippet.content, language, &options).map_err(Error::Invalid),
+        other => Err(Error::Invalid(format!(
+            "unsupported render format: {}",
+            other
+        ))),
+    }
+}
+
+/// 列出 snippet 模板中的变量（`${name}` / `${name:default}`）
+pub fn variables(storage: &Storage, snippet_id: &str) -> Result<Vec<TemplateVariable>> {
+    let snippet = get(storage, snippet_id)?;
+    Ok(snippet_template::variables(&snippet.content))
+}
+
+/// 用给定变量值渲染 snippet 模板，并记录一次使用
+///
+/// 取值顺序：values → 内置变量（date / time / datetime / timestamp / uuid，
+/// clipboard 由调用方提供）→ 默认值
+pub fn render_template(
+    storage: &mut Storage,
+    snippet_id: &str,
+    values: &HashMap<String, String>,
+    clipboard: impl Fn() -> Option<String>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let snippet = get(storage, snippet_id)?;
+
+    let rendered = snippet_template::render(&snippet.content, |name| {
+        if let Some(v) = values.get(name) {
+            return Some(v.clone());
+        }
+        match name {
+            "clipboard" => clipboard(),
+            _ => snippet_template::builtin_value(name),
+        }
+    })?;
+
+    let tx = storage.conn.transaction()?;
+    db_models::record_snippet_usage(&tx, snippet_id, now)?;
+    tx.commit()?;
+
+    Ok(rendered)
+}
+
+/// 校验文件名：非空、不含路径分隔符、同一 snippet 内唯一
+fn validate_file_name(
+    tx: &Transaction,
+    snippet_id: &str,
+    file_name: &str,
+    exclude_file_id: Option<&str>,
+) -> Result<()> {
+    if file_name.trim().is_empty() {
+        return Err(Error::Invalid("file name is empty".to_string()));
+    }
+    if file_name.contains('/') || file_name.contains('\\') {
+        return Err(Error::Invalid(
+            "file name must not contain path separators".to_string(),
+        ));
+    }
+
+    let exists: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM snippet_files WHERE snippet_id = ? AND file_name = ? AND id != ?)",
+        params![snippet_id, file_name, exclude_file_id.unwrap_or("")],
+        |r| r.get(0),
+    )?;
+    if exists {
+        return Err(Error::Invalid(format!(
+            "file already exists: {}",
+            file_name
+        )));
+    }
+
+    Ok(())
+}
+
+/// 为 snippet 新增文件（追加到末尾），返回新文件 id
+///
+/// 未指定语言时根据文件扩展名 / 内容自动识别
+pub fn add_file(
+    storage: &mut Storage,
+    snippet_id: &str,
+    file_name: String,
+    language: Option<String>,
+    content: String,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let file_id = Uuid::new_v4().to_string();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    db_models::get_snippet(&tx, snippet_id).map_err(|_| Error::NotFound("snippet"))?;
+    validate_file_name(&tx, snippet_id, &file_name, None)?;
+
+    let order_index: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(order_index) + 1, 0) FROM snippet_files WHERE snippet_id = ?",
+        params![snippet_id],
+        |r| r.get(0),
+    )?;
+
+    let (language, detected, confident) = resolve_language(language, Some(&file_name), &content);
+    db_models::insert_snippet_file(
+        &tx,
+        &SnippetFileRecord {
+            id: file_id.clone(),
+            snippet_id: snippet_id.to_string(),
+            file_name,
+            language,
+            language_detected: detected,
+            language_confident: confident,
+            content,
+            order_index,
+            created_at: now,
+            updated_at: now,
+        },
+    )?;
+    db_models::sync_snippet_primary_file(&tx, snippet_id, now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Add snippet file");
+
+    Ok(file_id)
+}
+
+/// 更新文件内容 / 语言
+pub fn update_file(
+    storage: &mut Storage,
+    file_id: &str,
+    language: Option<String>,
+    content: String,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let mut file =
+        db_models::get_snippet_file(&tx, file_id).map_err(|_| Error::NotFound("file"))?;
+
+    let language = user_language(language, file.language.as_deref(), file.language_detected);
+    let (language, detected, confident) =
+        resolve_language(language, Some(&file.file_name), &content);
+    file.language = language;
+    file.language_detected = detected;
+    file.language_confident = confident;
+    file.content = content;
+
+    db_models::update_snippet_file(&tx, &file, now)?;
+    db_models::sync_snippet_primary_file(&tx, &file.snippet_id, now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Edit snippet file");
+
+    Ok(())
+}
+
+/// 重命名文件
+///
+/// 语言为自动识别得出时，按新文件名重新识别；用户指定的语言保持不变
+pub fn rename_file(storage: &mut Storage, file_id: &str, file_name: String) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let mut file =
+        db_models::get_snippet_file(&tx, file_id).map_err(|_| Error::NotFound("file"))?;
+    validate_file_name(&tx, &file.snippet_id, &file_name, Some(file_id))?;
+
+    if file.language.is_none() || file.language_detected {
+        let (language, detected, confident) =
+            resolve_language(None, Some(&file_name), &file.content);
+        file.language = language;
+        file.language_detected = detected;
+        file.language_confident = confident;
+    }
+    file.file_name = file_name;
+
+    db_models::update_snippet_file(&tx, &file, now)?;
+    db_models::sync_snippet_primary_file(&tx, &file.snippet_id, now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Rename snippet file");
+
+    Ok(())
+}
+
+/// 调整文件顺序（file_ids 必须恰好包含该 snippet 的全部文件），第一个文件即为主文件
+pub fn reorder_files(storage: &mut Storage, snippet_id: &str, file_ids: &[String]) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let files = db_models::list_snippet_files(&tx, snippet_id)?;
+
+    let mut expected: Vec<&str> = files.iter().map(|f| f.id.as_str()).collect();
+    let mut given: Vec<&str> = file_ids.iter().map(String::as_str).collect();
+    expected.sort_unstable();
+    given.sort_unstable();
+    if expected != given {
+        return Err(Error::Invalid(
+            "file_ids must list every file of the snippet exactly once".to_string(),
+        ));
+    }
+
+    for (index, file_id) in file_ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE snippet_files SET order_index = ?, updated_at = ? WHERE id = ?",
+            params![index as i64, now, file_id],
+        )?;
+    }
+    db_models::sync_snippet_primary_file(&tx, snippet_id, now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Reorder snippet files");
+
+    Ok(())
+}
+
+/// 删除文件（snippet 至少保留一个文件）
+pub fn delete_file(storage: &mut Storage, file_id: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let file = db_models::get_snippet_file(&tx, file_id).map_err(|_| Error::NotFound("file"))?;
+
+    let count: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM snippet_files WHERE snippet_id = ?",
+        params![&file.snippet_id],
+        |r| r.get(0),
+    )?;
+    if count <= 1 {
+        return Err(Error::Invalid(
+            "cannot delete the last file of a snippet".to_string(),
+        ));
+    }
+
+    db_models::delete_snippet_file(&tx, file_id)?;
+    db_models::sync_snippet_primary_file(&tx, &file.snippet_id, now)?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Delete snippet file");
+
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row, Transaction};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models::TASK_POSITION_GAP;
+
+#[derive(Serialize)]
+pub struct TaskDetail {
+    pub id: String,
+    pub node_id: String,
+    pub title: String,
+    pub status: String,
+    pub priority: i64,
+    pub due_date: Option<i64>,
+    pub description: Option<String>,
+    pub position: f64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const TASK_COLUMNS: &str =
+    "id, node_id, title, status, priority, due_date, description, position, created_at, updated_at";
+
+fn task_from_row(r: &Row) -> rusqlite::Result<TaskDetail> {
+    Ok(TaskDetail {
+        id: r.get(0)?,
+        node_id: r.get(1)?,
+        title: r.get(2)?,
+        status: r.get(3)?,
+        priority: r.get(4)?,
+        due_date: r.get(5)?,
+        description: r.get(6)?,
+        position: r.get(7)?,
+        created_at: r.get(8)?,
+        updated_at: r.get(9)?,
+    })
+}
+
+/// 列出某个项目下的所有任务
+pub fn list(storage: &Storage, node_id: &str) -> Result<Vec<TaskDetail>> {
+    let mut stmt = storage.conn.prepare(&format!(
+        "SELECT {} FROM tasks WHERE node_id = ? ORDER BY position ASC, created_at DESC",
+        TASK_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![node_id], task_from_row)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 创建任务（追加到所在列末尾），返回新 id
+pub fn create(
+    storage: &mut Storage,
+    node_id: &str,
+    title: &str,
+    status: Option<&str>,
+    priority: Option<i64>,
+    due_date: Option<i64>,
+    description: Option<&str>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let task_id = Uuid::new_v4().to_string();
+    let status = status.unwrap_or("todo");
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let position = next_position(&tx, node_id, status)?;
+    tx.execute(
+        "INSERT INTO tasks (id, node_id, title, status, priority, due_date, description, position, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            &task_id,
+            node_id,
+            title,
+            status,
+            priority.unwrap_or(0),
+            due_date,
+            description,
+            position,
+            now,
+            now,
+        ],
+    )?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Create task");
+
+    Ok(task_id)
+}
+
+/// 获取单个任务
+pub fn get(storage: &Storage, task_id: &str) -> Result<TaskDetail> {
+    storage
+        .conn
+        .query_row(
+            &format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS),
+            params![task_id],
+            task_from_row,
+        )
+        .optional()?
+        .ok_or(Error::NotFound("task"))
+}
+
+/// 更新任务：title / status / priority 为 None 时保持不变，due_date / description 按传入值写入
+pub fn update(
+    storage: &mut Storage,
+    task_id: &str,
+    title: Option<&str>,
+    status: Option<&str>,
+    priority: Option<i64>,
+    due_date: Option<i64>,
+    description: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let (node_id, current_status): (String, String) = tx
+        .query_row(
+            "SELECT node_id, status FROM tasks WHERE id = ?",
+            params![task_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+        .ok_or(Error::NotFound("task"))?;
+
+    // status 变化时放到目标列末尾，而不是沿用旧列中的位置
+    let position = match status {
+        Some(status) if status != current_status => Some(next_position(&tx, &node_id, status)?),
+        _ => None,
+    };
+
+    tx.execute(
+        "UPDATE tasks SET title = COALESCE(?, title), status = COALESCE(?, status), priority = COALESCE(?, priority), position = COALESCE(?, position), due_date = ?, description = ?, updated_at = ? WHERE id = ?",
+        params![title, status, priority, position, due_date, description, now, task_id],
+    )?;
+    tx.commit()?;
+    changelog::record(&storage.conn, "Update task");
+
+    Ok(())
+}
+
+/// 删除任务
+pub fn delete(storage: &mut Storage, task_id: &str) -> Result<()> {
+    changelog::begin(&storage.conn)?;
+    storage
+        .conn
+        .execute("DELETE FROM tasks WHERE id = ?", params![task_id])?;
+    changelog::record(&storage.conn, "Delete task");
+    Ok(())
+}
+
+/// 移动任务（看板拖拽）：同时修改 status 与 position
+///
+/// - before_task_id 为 None：放到目标列末尾
+/// - 否则放到 before_task_id 之前（必须位于同一项目、同一 status）
+/// - 取相邻位置中点，只更新被移动的一行；间隔耗尽时整列重排
+pub fn move_to(
+    storage: &mut Storage,
+    task_id: &str,
+    status: &str,
+    before_task_id: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    if before_task_id == Some(task_id) {
+        return Err(Error::Invalid(
+            "task cannot be moved before itself".to_string(),
+        ));
+    }
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let node_id: String = tx
+        .query_row(
+            "SELECT node_id FROM tasks WHERE id = ?",
+            params![task_id],
+            |r| r.get(0),
+        )
+        .optional()?
+        .ok_or(Error::NotFound("task"))?;
+
+    let position = match before_task_id {
+        None => next_position(&tx, &node_id, status)?,
+        Some(before_id) => {
+            let mut position = position_before(&tx, &node_id, status, task_id, before_id)?;
+            if position.is_none() {
+                // 相邻位置已无可用间隔：整列重排后重新计算
+                renumber_column(&tx, &node_id, status)?;
+                position = position_before(&tx, &node_id, status, task_id, before_id)?;
+            }
+            position.ok_or_else(|| Error::Other("failed to allocate task position".to_string()))?
+        }
+    };
+
+    tx.execute(
+        "UPDATE tasks SET status = ?, position = ?, updated_at = ? WHERE id = ?",
+        params![status, position, now, task_id],
+    )?;
+
+    tx.commit()?;
+    changelog::record(&storage.conn, "Move task");
+
+    Ok(())
+}
+
+/// 目标列末尾的下一个位置
+fn next_position(tx: &Transaction, node_id: &str, status: &str) -> rusqlite::Result<f64> {
+    let max: Option<f64> = tx.query_row(
+        "SELECT MAX(position) FROM tasks WHERE node_id = ? AND status = ?",
+        params![node_id, status],
+        |r| r.get(0),
+    )?;
+
+    Ok(max.unwrap_or(0.0) + TASK_POSITION_GAP)
+}
+
+/// 计算插入到 before_id 之前的位置
+///
+/// 返回 Ok(None) 表示与前一项之间已无可用间隔
+fn position_before(
+    tx: &Transaction,
+    node_id: &str,
+    status: &str,
+    task_id: &str,
+    before_id: &str,
+) -> Result<Option<f64>> {
+    let before: f64 = tx
+        .query_row(
+            "SELECT position FROM tasks WHERE id = ? AND node_id = ? AND status = ?",
+            params![before_id, node_id, status],
+            |r| r.get(0),
+        )
+        .optional()?
+        .ok_or(Error::NotFound("before task in target column"))?;
+
+    let prev: Option<f64> = tx.query_row(
+        "SELECT MAX(position) FROM tasks WHERE node_id = ? AND status = ? AND position < ? AND id != ?",
+        params![node_id, status, before, task_id],
+        |r| r.get(0),
+    )?;
+
+    let prev = prev.unwrap_or(before - TASK_POSITION_GAP);
+    let mid = (prev + before) / 2.0;
+
+    // 浮点精度耗尽时中点会与某一端重合
+    if mid <= prev || mid >= before {
+        return Ok(None);
+    }
+
+    Ok(Some(mid))
+}
+
+/// 按当前顺序为整列重新分配等间隔位置
+fn renumber_column(tx: &Transaction, node_id: &str, status: &str) -> rusqlite::Result<()> {
+    let ids: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT id FROM tasks WHERE node_id = ? AND status = ? ORDER BY position ASC, created_at DESC",
+        )?;
+        let rows = stmt.query_map(params![node_id, status], |r| r.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (i, id) in ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE tasks SET position = ? WHERE id = ?",
+            params![(i as f64 + 1.0) * TASK_POSITION_GAP, id],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,656 @@
+use rusqlite::Connection;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{
+    journal, note_templates, notes, projects, search, snippet_vscode, snippets, tasks,
+    time_entries, tree, Error, Storage,
+};
+use crate::changelog;
+use crate::crypto::{note as note_crypto, session as crypto_session};
+use crate::db::migrate;
+
+/// 内存数据库 + 临时数据目录，测试结束时删除目录
+struct TestStorage {
+    storage: Storage,
+    dir: PathBuf,
+}
+
+impl TestStorage {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("noto-service-{}", uuid::Uuid::new_v4()));
+        crate::fs::create_dirs(&dir).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        migrate::run(&conn).unwrap();
+        Self {
+            storage: Storage::new(conn, &dir),
+            dir,
+        }
+    }
+}
+
+impl Drop for TestStorage {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn project(storage: &mut Storage) -> String {
+    tree::create(storage, "Project", "project", "notes", None, None).unwrap()
+}
+
+fn note_path(storage: &Storage, note_id: &str) -> PathBuf {
+    storage.root.join("notes").join(format!("{}.md", note_id))
+}
+
+#[test]
+fn task_lifecycle() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+    let node = project(s);
+
+    let a = tasks::create(s, &node, "A", None, None, None, None).unwrap();
+    let b = tasks::create(s, &node, "B", None, Some(2), None, Some("details")).unwrap();
+
+    let list = tasks::list(s, &node).unwrap();
+    assert_eq!(
+        list.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(),
+        ["A", "B"]
+    );
+    assert_eq!(list[0].status, "todo");
+    assert_eq!(list[1].priority, 2);
+
+    // 拖到 A 之前
+    tasks::move_to(s, &b, "todo", Some(&a)).unwrap();
+    let list = tasks::list(s, &node).unwrap();
+    assert_eq!(list[0].id, b);
+
+    tasks::update(s, &a, Some("A2"), Some("doing"), None, None, None).unwrap();
+    let task = tasks::get(s, &a).unwrap();
+    assert_eq!((task.title.as_str(), task.status.as_str()), ("A2", "doing"));
+
+    assert!(matches!(
+        tasks::update(s, "missing", Some("x"), None, None, None, None),
+        Err(Error::NotFound("task"))
+    ));
+
+    // 改变 status 后排在目标列末尾
+    let c = tasks::create(s, &node, "C", Some("doing"), None, None, None).unwrap();
+    tasks::update(s, &b, None, Some("doing"), None, None, None).unwrap();
+    let doing: Vec<_> = tasks::list(s, &node)
+        .unwrap()
+        .into_iter()
+        .filter(|t| t.status == "doing")
+        .map(|t| t.id)
+        .collect();
+    assert_eq!(doing, [a.clone(), c, b.clone()]);
+
+    assert!(matches!(
+        tasks::move_to(s, &a, "todo", Some(&a)),
+        Err(Error::Invalid(_))
+    ));
+
+    tasks::delete(s, &a).unwrap();
+    assert!(matches!(tasks::get(s, &a), Err(Error::NotFound("task"))));
+}
+
+#[test]
+fn move_task_renumbers_exhausted_column() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+    let node = project(s);
+
+    let first = tasks::create(s, &node, "first", None, None, None, None).unwrap();
+    let mut moved = Vec::new();
+    for i in 0..80 {
+        let id = tasks::create(s, &node, &format!("t{}", i), None, None, None, None).unwrap();
+        // 每次都插到同一位置之前，间隔不断减半直至耗尽
+        tasks::move_to(s, &id, "todo", Some(&first)).unwrap();
+        moved.push(id);
+    }
+
+    let list = tasks::list(s, &node).unwrap();
+    assert_eq!(list.last().unwrap().id, first);
+    assert_eq!(list[list.len() - 2].id, *moved.last().unwrap());
+    assert!(list.windows(2).all(|w| w[0].position < w[1].position));
+}
+
+#[test]
+fn time_entry_lifecycle() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+    let node = project(s);
+    let task = tasks::create(s, &node, "Task", None, None, None, None).unwrap();
+
+    let entry = |work_date, duration| time_entries::NewTimeEntry {
+        work_date,
+        duration,
+        description: "work",
+        start_time: None,
+        end_time: None,
+        source: None,
+    };
+    let older = time_entries::create(s, &task, &entry(1_000, 600)).unwrap();
+    let newer = time_entries::create(s, &task, &entry(2_000, 1_800)).unwrap();
+
+    let list = time_entries::list(s, &task).unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].id, newer);
+    assert_eq!(list[0].source, "manual");
+
+    time_entries::update(s, &older, 900, "review").unwrap();
+    time_entries::delete(s, &newer).unwrap();
+    let list = time_entries::list(s, &task).unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(
+        (list[0].duration, list[0].description.as_str()),
+        (900, "review")
+    );
+}
+
+#[test]
+fn tree_nesting_and_validation() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let root = tree::create(s, "Root", "folder", "notes", None, None).unwrap();
+    let second = tree::create(s, "Second", "folder", "notes", Some(&root), Some(2)).unwrap();
+    let first = tree::create(s, "First", "folder", "notes", Some(&root), Some(1)).unwrap();
+    tree::create(s, "Other scope", "folder", "snippets", None, None).unwrap();
+
+    assert!(matches!(
+        tree::create(s, "x", "folder", "notes", Some("missing"), None),
+        Err(Error::NotFound("parent node"))
+    ));
+    assert!(matches!(
+        tree::update(s, &root, "Root", Some(&root), None),
+        Err(Error::Invalid(_))
+    ));
+
+    let roots = tree::list_tree(s, Some("notes")).unwrap();
+    assert_eq!(roots.len(), 1);
+    let children = roots[0].children.as_ref().unwrap();
+    assert_eq!(
+        children.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+        [first.as_str(), second.as_str()]
+    );
+
+    // 移到根级
+    tree::update(s, &second, "Moved", None, Some(5)).unwrap();
+    let roots = tree::list_tree(s, Some("notes")).unwrap();
+    assert_eq!(roots.len(), 2);
+    assert_eq!(roots[1].label, "Moved");
+    assert_eq!(tree::list(s, None).unwrap().len(), 4);
+}
+
+#[test]
+fn list_children_pages_and_counts() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let root = tree::create(s, "Root", "folder", "notes", None, None).unwrap();
+    tree::create(s, "Other scope", "folder", "snippets", None, None).unwrap();
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        ids.push(
+            tree::create(
+                s,
+                &format!("c{}", i),
+                "folder",
+                "notes",
+                Some(&root),
+                Some(i),
+            )
+            .unwrap(),
+        );
+    }
+    tree::create(s, "grandchild", "folder", "notes", Some(&ids[0]), None).unwrap();
+
+    let roots = tree::list_children(s, None, Some("notes"), None, None).unwrap();
+    assert_eq!(roots.total, 1);
+    assert_eq!(roots.children[0].child_count, 5);
+
+    // 每页 2 条，按 order_index 依次翻页
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = tree::list_children(s, Some(&root), None, cursor.as_deref(), Some(2)).unwrap();
+        assert_eq!(page.total, 5);
+        seen.extend(page.children.iter().map(|c| c.id.clone()));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(seen, ids);
+
+    let refreshed = tree::node_with_children(s, &ids[0], None).unwrap();
+    assert!(refreshed.node.has_children);
+    assert_eq!(refreshed.children.children[0].label, "grandchild");
+    assert!(!refreshed.children.children[0].has_children);
+
+    assert!(matches!(
+        tree::list_children(s, Some(&root), None, Some("bad"), None),
+        Err(Error::Invalid(_))
+    ));
+    assert!(matches!(
+        tree::node_with_children(s, "missing", None),
+        Err(Error::NotFound("node"))
+    ));
+}
+
+#[test]
+fn deleting_a_folder_removes_notes_and_files() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let folder = tree::create(s, "Folder", "folder", "notes", None, None).unwrap();
+    let created = notes::create(s, "Inside", Some(&folder), None).unwrap();
+    let path = note_path(s, &created.note_id);
+    assert!(path.exists());
+    notes::update_metadata(
+        s,
+        &created.note_id,
+        &["tag".to_string()],
+        &["alias".to_string()],
+    )
+    .unwrap();
+
+    tree::delete(s, &folder).unwrap();
+    assert!(!path.exists());
+    let leftover: i64 = s
+        .conn
+        .query_row(
+            "SELECT (SELECT COUNT(1) FROM note_tags) + (SELECT COUNT(1) FROM note_aliases)",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(leftover, 0);
+    assert!(tree::list(s, None).unwrap().is_empty());
+    assert!(matches!(
+        notes::get(s, &created.note_id),
+        Err(Error::NotFound("note"))
+    ));
+}
+
+#[test]
+fn note_content_and_front_matter() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let created = notes::create(s, "Draft", None, None).unwrap();
+    let note = notes::get(s, &created.note_id).unwrap();
+    assert_eq!(note.title, "Draft");
+    assert!(note.content.contains("# Draft"));
+
+    // front matter 中的标题与标签同步到数据库和树节点
+    notes::update_content(
+        s,
+        &created.note_id,
+        "---\ntitle: Final\ntags: [rust, notes]\n---\n\nBody\n",
+    )
+    .unwrap();
+    let note = notes::get(s, &created.note_id).unwrap();
+    assert_eq!(note.title, "Final");
+    assert_eq!(note.tags, ["notes", "rust"]);
+    let node = tree::list(s, None).unwrap().remove(0);
+    assert_eq!(node.name, "Final");
+
+    notes::update_metadata(
+        s,
+        &created.note_id,
+        &["#todo".to_string(), "todo".to_string()],
+        &["alias".to_string()],
+    )
+    .unwrap();
+    let note = notes::get(s, &created.note_id).unwrap();
+    assert_eq!(note.tags, ["todo"]);
+    assert_eq!(note.aliases, ["alias"]);
+    assert!(note.content.contains("alias"));
+
+    notes::update_title(s, &created.note_id, "Renamed").unwrap();
+    let note = notes::get(s, &created.note_id).unwrap();
+    assert_eq!(note.title, "Renamed");
+    assert!(note.content.contains("title: Renamed"));
+
+    let html = notes::render_html(s, &created.note_id, None).unwrap();
+    assert!(html.contains("Body"));
+}
+
+#[test]
+fn external_front_matter_edits_sync_on_read() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let created = notes::create(s, "Draft", None, None).unwrap();
+    fs::write(
+        note_path(s, &created.note_id),
+        "---\ntitle: Edited outside\ntags: [b, a]\naliases: [other]\n---\n\nBody\n",
+    )
+    .unwrap();
+
+    let note = notes::get(s, &created.note_id).unwrap();
+    assert_eq!(note.title, "Edited outside");
+    assert_eq!(note.tags, ["a", "b"]);
+    assert_eq!(note.aliases, ["other"]);
+    let node = tree::list(s, None).unwrap().remove(0);
+    assert_eq!(node.name, "Edited outside");
+}
+
+#[test]
+fn note_copy_keeps_parent_folder() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let folder = tree::create(s, "Folder", "folder", "notes", None, None).unwrap();
+    let original = notes::create(s, "Original", Some(&folder), None).unwrap();
+    let parent = notes::note_parent_id(&s.conn, &original.note_id).unwrap();
+    assert_eq!(parent.as_deref(), Some(folder.as_str()));
+
+    let tx = s.conn.transaction().unwrap();
+    let (copy, path, content) = notes::insert_note_copy(
+        &tx,
+        &s.root,
+        "Copy",
+        parent.as_deref(),
+        "---\ntitle: Original\n---\ntext\n",
+        0,
+    )
+    .unwrap();
+    assert!(!path.exists());
+    tx.commit().unwrap();
+    fs::write(&path, content).unwrap();
+
+    let note = notes::get(s, &copy.note_id).unwrap();
+    assert_eq!(note.title, "Copy");
+    assert!(note.content.ends_with("text\n"));
+}
+
+#[test]
+fn note_encryption_updates_file_and_flag_together() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let created = notes::create(s, "Secret", None, None).unwrap();
+    let path = note_path(s, &created.note_id);
+    let plaintext = fs::read_to_string(&path).unwrap();
+    let encrypted = |s: &Storage| -> bool {
+        s.conn
+            .query_row(
+                "SELECT encrypted FROM notes WHERE id = ?",
+                [&created.note_id],
+                |r| r.get(0),
+            )
+            .unwrap()
+    };
+
+    assert!(matches!(
+        notes::encrypt(s, &created.note_id, ""),
+        Err(Error::Invalid(_))
+    ));
+    notes::encrypt(s, &created.note_id, "pass").unwrap();
+    assert!(encrypted(s));
+    assert_ne!(fs::read_to_string(&path).unwrap(), plaintext);
+    assert!(matches!(
+        notes::encrypt(s, &created.note_id, "pass"),
+        Err(Error::Invalid(_))
+    ));
+
+    // 口令错误时文件和标记都不变
+    assert!(notes::decrypt(s, &created.note_id, "wrong").is_err());
+    assert!(encrypted(s));
+
+    notes::decrypt(s, &created.note_id, "pass").unwrap();
+    assert!(!encrypted(s));
+    assert_eq!(fs::read_to_string(&path).unwrap(), plaintext);
+    assert!(!path.with_extension("md.tmp").exists());
+}
+
+#[test]
+fn undo_refuses_when_note_changed_since() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let created = notes::create(s, "Draft", None, None).unwrap();
+    notes::update_content(s, &created.note_id, "# Draft\n\nedited\n").unwrap();
+    let path = note_path(s, &created.note_id);
+    fs::write(&path, "changed outside").unwrap();
+
+    // 外部修改不会被撤销覆盖，过期的操作被丢弃
+    assert!(changelog::undo(&mut s.conn).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "changed outside");
+    let next = changelog::next_undo(&s.conn).unwrap().unwrap();
+    assert_ne!(next.label, "Edit note");
+}
+
+#[test]
+fn encrypting_a_note_drops_its_undo_history() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let created = notes::create(s, "Secret", None, None).unwrap();
+    notes::update_content(s, &created.note_id, "# Secret\n\nplain\n").unwrap();
+    let path = note_path(s, &created.note_id)
+        .to_string_lossy()
+        .into_owned();
+    let undo_files = |s: &Storage| -> i64 {
+        s.conn
+            .query_row(
+                "SELECT COUNT(*) FROM undo_files WHERE path = ?",
+                [&path],
+                |r| r.get(0),
+            )
+            .unwrap()
+    };
+    assert!(undo_files(s) > 0);
+
+    notes::encrypt(s, &created.note_id, "pass").unwrap();
+    assert_eq!(undo_files(s), 0);
+}
+
+#[test]
+fn search_matches_titles_and_content() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let project = project(s);
+    let note = notes::create(s, "Meeting", None, None).unwrap();
+    notes::update_content(s, &note.note_id, "# Meeting\n\nDiscuss the Budget\n").unwrap();
+    let task = tasks::create(s, &project, "Budget review", None, None, None, None).unwrap();
+    notes::create(s, "Unrelated", None, None).unwrap();
+
+    let hits = search::search(s, "budget", 10).unwrap();
+    let found: Vec<(&str, &str)> = hits.iter().map(|h| (h.kind, h.id.as_str())).collect();
+    assert_eq!(
+        found,
+        [("note", note.note_id.as_str()), ("task", task.as_str())]
+    );
+    assert_eq!(hits[0].excerpt.as_deref(), Some("Discuss the Budget"));
+    assert_eq!(search::search(s, "budget", 1).unwrap().len(), 1);
+}
+
+#[test]
+fn outline_follows_file_changes_and_lock_state() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let note = notes::create(s, "Plan", None, None).unwrap();
+    let headings = |s: &Storage| -> Vec<String> {
+        let outline = notes::outline(s, &note.note_id).unwrap();
+        outline.headings.into_iter().map(|h| h.text).collect()
+    };
+    assert_eq!(headings(s), ["Plan"]);
+
+    // 外部修改后缓存失效
+    fs::write(note_path(s, &note.note_id), "# Plan\n\n# Steps\n").unwrap();
+    assert_eq!(headings(s), ["Plan", "Steps"]);
+
+    notes::encrypt(s, &note.note_id, "pass").unwrap();
+    assert!(matches!(
+        notes::outline(s, &note.note_id),
+        Err(Error::Locked)
+    ));
+}
+
+#[test]
+fn search_reads_unlocked_encrypted_notes() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let note = notes::create(s, "Secret", None, None).unwrap();
+    notes::update_content(s, &note.note_id, "# Secret\n\nThe vault code\n").unwrap();
+    notes::encrypt(s, &note.note_id, "pass").unwrap();
+    assert!(search::search(s, "vault code", 10).unwrap().is_empty());
+
+    let raw = fs::read_to_string(note_path(s, &note.note_id)).unwrap();
+    let salt = note_crypto::read_salt(&raw).unwrap();
+    let key = note_crypto::derive_key("pass", &salt).unwrap();
+    crypto_session::unlock(&note.note_id, key, salt, Duration::from_secs(60));
+    let hits = search::search(s, "vault code", 10).unwrap();
+    crypto_session::lock(&note.note_id);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].excerpt.as_deref(), Some("The vault code"));
+}
+
+#[test]
+fn snippet_files() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let id = snippets::create(
+        s,
+        "Hello",
+        Some("rust".to_string()),
+        "fn main() {}".to_string(),
+        None,
+    )
+    .unwrap();
+    let detail = snippets::get(s, &id).unwrap();
+    assert_eq!(detail.language.as_deref(), Some("rust"));
+    assert_eq!(detail.files.len(), 1);
+    let primary = detail.files[0].id.clone();
+
+    let second =
+        snippets::add_file(s, &id, "build.py".to_string(), None, "print()".to_string()).unwrap();
+    assert!(matches!(
+        snippets::add_file(s, &id, "build.py".to_string(), None, String::new()),
+        Err(Error::Invalid(_))
+    ));
+    assert!(matches!(
+        snippets::add_file(s, &id, "a/b".to_string(), None, String::new()),
+        Err(Error::Invalid(_))
+    ));
+
+    // 第二个文件调到最前，成为主文件
+    snippets::reorder_files(s, &id, &[second.clone(), primary.clone()]).unwrap();
+    let detail = snippets::get(s, &id).unwrap();
+    assert_eq!(detail.files[0].id, second);
+    assert_eq!(detail.content, "print()");
+
+    snippets::rename_file(s, &second, "build.rs".to_string()).unwrap();
+    snippets::delete_file(s, &primary).unwrap();
+    assert!(matches!(
+        snippets::delete_file(s, &second),
+        Err(Error::Invalid(_))
+    ));
+
+    snippets::update(s, &id, "Renamed", None, "fn build() {}".to_string()).unwrap();
+    snippets::update_meta(s, &id, Some("hb"), Some(" "), None).unwrap();
+    let detail = snippets::get(s, &id).unwrap();
+    assert_eq!(detail.title, "Renamed");
+    assert_eq!(detail.files[0].file_name, "build.rs");
+    assert_eq!(detail.prefix.as_deref(), Some("hb"));
+    assert_eq!(detail.description, None);
+
+    // 前端回传自动识别的语言时仍视为自动识别，之后的编辑会重新识别
+    let auto = snippets::create(
+        s,
+        "Auto",
+        None,
+        "fn main() {\n    let mut v = vec![1];\n    println!(\"{:?}\", v);\n}\n".to_string(),
+        None,
+    )
+    .unwrap();
+    let detail = snippets::get(s, &auto).unwrap();
+    assert_eq!(detail.language.as_deref(), Some("rust"));
+    snippets::update(s, &auto, "Auto", detail.language, detail.content).unwrap();
+    assert!(snippets::get(s, &auto).unwrap().language_detected);
+    snippets::update(s, &auto, "Auto", Some("python".to_string()), String::new()).unwrap();
+    assert!(!snippets::get(s, &auto).unwrap().language_detected);
+
+    snippets::delete(s, &id).unwrap();
+    assert!(matches!(
+        snippets::get(s, &id),
+        Err(Error::NotFound("snippet"))
+    ));
+}
+
+#[test]
+fn daily_notes_use_journal_settings_and_templates() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+
+    let template = note_templates::create(s, "Daily", "# {{title}}\n\n{{cursor}}").unwrap();
+    journal::update_settings(s, "Diary", Some(&template)).unwrap();
+    assert!(journal::update_settings(s, " ", None).is_err());
+
+    let day = journal::open_daily(s, "2024-03-05", None).unwrap();
+    assert!(day.created);
+    let note = notes::get(s, &day.note_id).unwrap();
+    assert!(note.content.starts_with("# 2024-03-05"));
+    let mut folders: Vec<String> = tree::list(s, Some("notes"))
+        .unwrap()
+        .into_iter()
+        .filter(|n| n.node_type == "folder")
+        .map(|n| n.name)
+        .collect();
+    folders.sort();
+    assert_eq!(folders, ["03", "2024", "Diary"]);
+
+    let again = journal::open_daily(s, "2024-03-05", None).unwrap();
+    assert!(!again.created);
+    assert_eq!(again.note_id, day.note_id);
+    assert_eq!(journal::list_days(s, 2024, 3).unwrap(), [5]);
+    let previous = journal::adjacent(s, "2024-04-01", true).unwrap().unwrap();
+    assert_eq!(previous.date, "2024-03-05");
+    assert!(journal::adjacent(s, "2024-04-01", false).unwrap().is_none());
+}
+
+#[test]
+fn project_description_lifecycle() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+    let node = project(s);
+
+    let note_id = projects::create_description(s, &node, None).unwrap();
+    assert!(projects::create_description(s, &node, None).is_err());
+    let detail = projects::get(s, &node).unwrap();
+    assert_eq!(detail.description.unwrap().title, "Project");
+
+    projects::detach_description(s, &node).unwrap();
+    assert!(projects::get(s, &node).unwrap().description.is_none());
+    assert!(!note_path(s, &note_id).exists());
+}
+
+#[test]
+fn vscode_snippets_round_trip() {
+    let mut t = TestStorage::new();
+    let s = &mut t.storage;
+    let folder = tree::create(s, "Imported", "folder", "snippets", None, None).unwrap();
+
+    let input = s.root.join("in.code-snippets");
+    fs::write(
+        &input,
+        r#"{ "Log": { "prefix": "log", "body": ["console.log($1);"], "scope": "javascript" } }"#,
+    )
+    .unwrap();
+    assert_eq!(snippet_vscode::import(s, &folder, &input).unwrap(), 1);
+
+    let output = s.root.join("out.code-snippets");
+    assert_eq!(snippet_vscode::export(s, &folder, &output).unwrap(), 1);
+    let exported = fs::read_to_string(&output).unwrap();
+    assert!(exported.contains("\"prefix\": \"log\""));
+    assert!(exported.contains("console.log($1);"));
+}
@@ -0,0 +1,113 @@
+use chrono::Utc;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Result, Storage};
+use crate::changelog;
+
+#[derive(Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: String,
+    pub task_id: String,
+    /// 工作日期（本地零点，毫秒）
+    pub work_date: i64,
+    /// 时长（秒）
+    pub duration: i64,
+    pub description: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub source: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 新建工时记录的字段
+pub struct NewTimeEntry<'a> {
+    pub work_date: i64,
+    pub duration: i64,
+    pub description: &'a str,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    /// 为空时为 "manual"
+    pub source: Option<&'a str>,
+}
+
+/// 列出任务的工时记录（按工作日期倒序）
+pub fn list(storage: &Storage, task_id: &str) -> Result<Vec<TimeEntry>> {
+    let mut stmt = storage.conn.prepare(
+        "SELECT id, task_id, work_date, duration, description, start_time, end_time, source, created_at, updated_at FROM time_entries WHERE task_id = ? ORDER BY work_date DESC",
+    )?;
+
+    let rows = stmt.query_map(params![task_id], |r| {
+        Ok(TimeEntry {
+            id: r.get(0)?,
+            task_id: r.get(1)?,
+            work_date: r.get(2)?,
+            duration: r.get(3)?,
+            description: r.get(4)?,
+            start_time: r.get(5)?,
+            end_time: r.get(6)?,
+            source: r.get(7)?,
+            created_at: r.get(8)?,
+            updated_at: r.get(9)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 为任务新建工时记录，返回新 id
+pub fn create(storage: &mut Storage, task_id: &str, entry: &NewTimeEntry) -> Result<String> {
+    changelog::begin(&storage.conn)?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp_millis();
+
+    storage.conn.execute(
+        "INSERT INTO time_entries (id, task_id, work_date, duration, description, start_time, end_time, source, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            id,
+            task_id,
+            entry.work_date,
+            entry.duration,
+            entry.description,
+            entry.start_time,
+            entry.end_time,
+            entry.source.unwrap_or("manual"),
+            now,
+            now,
+        ],
+    )?;
+    changelog::record(&storage.conn, "Create time entry");
+
+    Ok(id)
+}
+
+/// 删除工时记录
+pub fn delete(storage: &mut Storage, entry_id: &str) -> Result<()> {
+    changelog::begin(&storage.conn)?;
+    storage
+        .conn
+        .execute("DELETE FROM time_entries WHERE id = ?", params![entry_id])?;
+    changelog::record(&storage.conn, "Delete time entry");
+
+    Ok(())
+}
+
+/// 修改工时记录的时长与描述
+pub fn update(
+    storage: &mut Storage,
+    entry_id: &str,
+    duration: i64,
+    description: &str,
+) -> Result<()> {
+    changelog::begin(&storage.conn)?;
+    let now = Utc::now().timestamp_millis();
+
+    storage.conn.execute(
+        "UPDATE time_entries SET duration = ?, description = ?, updated_at = ? WHERE id = ?",
+        params![duration, description, now, entry_id],
+    )?;
+    changelog::record(&storage.conn, "Update time entry");
+
+    Ok(())
+}
@@ -0,0 +1,432 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use super::{Error, Result, Storage};
+use crate::changelog;
+use crate::db::models as db_models;
+use crate::history::ChangeKind;
+
+#[derive(Serialize)]
+pub struct TreeNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub node_type: String,
+    pub scope: String,
+    pub order_index: i64,
+    pub description_note_id: Option<String>,
+    pub resource_id: Option<String>,
+    pub resource_type: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Serialize)]
+pub struct TreeResponseNode {
+    pub id: String,
+    pub label: String,
+    pub node_type: String,
+    pub resource_id: Option<String>,
+    pub resource_type: Option<String>,
+    pub children: Option<Vec<TreeResponseNode>>,
+}
+
+fn node_exists(conn: &Connection, node_id: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(1) FROM tree_nodes WHERE id = ?",
+        params![node_id],
+        |r| r.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// 创建树节点，返回新 id
+pub fn create(
+    storage: &mut Storage,
+    name: &str,
+    node_type: &str,
+    scope: &str,
+    parent_id: Option<&str>,
+    order_index: Option<i64>,
+) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let node_id = Uuid::new_v4().to_string();
+
+    // 验证 parent 是否存在（如果提供）
+    if let Some(pid) = parent_id {
+        if !node_exists(&storage.conn, pid)? {
+            return Err(Error::NotFound("parent node"));
+        }
+    }
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+    db_models::insert_tree_node(
+        &tx,
+        &node_id,
+        parent_id,
+        name,
+        node_type,
+        scope,
+        order_index.unwrap_or(0),
+        now,
+    )?;
+    tx.commit()?;
+    changelog::record(&storage.conn, "Create node");
+
+    Ok(node_id)
+}
+
+/// 重命名 / 移动树节点
+pub fn update(
+    storage: &mut Storage,
+    node_id: &str,
+    name: &str,
+    parent_id: Option<&str>,
+    order_index: Option<i64>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    // 验证节点存在
+    if !node_exists(&storage.conn, node_id)? {
+        return Err(Error::NotFound("node"));
+    }
+
+    // 如果提供 parent，验证 parent 存在且不等于自身
+    if let Some(pid) = parent_id {
+        if pid == node_id {
+            return Err(Error::Invalid(
+                "parent cannot be the node itself".to_string(),
+            ));
+        }
+        if !node_exists(&storage.conn, pid)? {
+            return Err(Error::NotFound("parent node"));
+        }
+    }
+
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+    db_models::update_tree_node(&tx, node_id, parent_id, name, order_index.unwrap_or(0), now)?;
+    tx.commit()?;
+    changelog::record(&storage.conn, "Update node");
+
+    Ok(())
+}
+
+/// 递归删除时收集的信息
+#[derive(Default)]
+struct Deleted {
+    /// 事务提交后要删除的 note 文件
+    files: Vec<String>,
+    /// 被删除节点引用的描述 note（待所有节点删除后再判断是否孤立）
+    description_note_ids: Vec<String>,
+    /// 被删除的 note：(note_id, 标题)，用于版本历史的提交说明
+    notes: Vec<(String, Option<String>)>,
+}
+
+/// 删除节点及其所有子节点，连同挂载的 note / snippet
+pub fn delete(storage: &mut Storage, node_id: &str) -> Result<()> {
+    changelog::begin(&storage.conn)?;
+    let tx = storage.conn.transaction()?;
+
+    let mut deleted = Deleted::default();
+    delete_node_recursive(&tx, node_id, &mut deleted)?;
+
+    // 描述 note 不再被任何节点引用时一并删除
+    for note_id in std::mem::take(&mut deleted.description_note_ids) {
+        if db_models::is_note_referenced(&tx, &note_id)? {
+            continue;
+        }
+        if let Ok((_id, content_path)) = db_models::get_note_detail(&tx, &note_id) {
+            deleted.files.push(content_path);
+        }
+        let title = db_models::get_note_title(&tx, &note_id)?;
+        db_models::delete_note(&tx, &note_id)?;
+        deleted.notes.push((note_id, title));
+    }
+
+    tx.commit()?;
+
+    // 删除对应的文件（在事务提交后），删除前保存内容以便撤销
+    for file_path in &deleted.files {
+        changelog::capture_file(&storage.conn, file_path)?;
+        let _ = std::fs::remove_file(file_path);
+    }
+    changelog::record(&storage.conn, "Delete node");
+    for (note_id, title) in &deleted.notes {
+        storage.note_changed(ChangeKind::Delete, note_id, title.as_deref());
+    }
+
+    Ok(())
+}
+
+fn delete_node_recursive(tx: &Transaction, node_id: &str, deleted: &mut Deleted) -> Result<()> {
+    // 先删除所有子节点
+    for child_id in db_models::get_child_node_ids(tx, node_id)? {
+        delete_node_recursive(tx, &child_id, deleted)?;
+    }
+
+    // 删除当前节点挂载的资源，同时记录文件路径
+    for (resource_id, resource_type) in db_models::get_node_resources(tx, node_id)? {
+        match resource_type.as_str() {
+            "note" => {
+                if let Ok((_id, content_path)) = db_models::get_note_detail(tx, &resource_id) {
+                    deleted.files.push(content_path);
+                }
+                let title = db_models::get_note_title(tx, &resource_id)?;
+                // 解除其他节点对该 note 的描述引用
+                db_models::clear_description_note_refs(tx, &resource_id)?;
+                db_models::delete_note(tx, &resource_id)?;
+                deleted.notes.push((resource_id, title));
+            }
+            "snippet" => {
+                db_models::delete_snippet(tx, &resource_id)?;
+            }
+            _ => {}
+        }
+    }
+
+    // 记录节点的描述 note
+    if let Some(note_id) = db_models::get_node_description_note_id(tx, node_id)? {
+        deleted.description_note_ids.push(note_id);
+    }
+
+    // 删除资源关联与节点本身
+    db_models::delete_node_resources(tx, node_id)?;
+    db_models::delete_tree_node(tx, node_id)?;
+
+    Ok(())
+}
+
+fn tree_node_from_row(r: &Row) -> rusqlite::Result<TreeNode> {
+    Ok(TreeNode {
+        id: r.get(0)?,
+        parent_id: r.get(1)?,
+        name: r.get(2)?,
+        node_type: r.get(3)?,
+        scope: r.get(4)?,
+        order_index: r.get(5)?,
+        description_note_id: r.get(6)?,
+        resource_id: r.get(7)?,
+        resource_type: r.get(8)?,
+        created_at: r.get(9)?,
+        updated_at: r.get(10)?,
+    })
+}
+
+/// 列出扁平节点（含挂载资源）；scope 为空时列出全部
+pub fn list(storage: &Storage, scope: Option<&str>) -> Result<Vec<TreeNode>> {
+    let mut stmt = storage.conn.prepare(
+        "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, tn.scope, tn.order_index, tn.description_note_id, nr.resource_id, nr.resource_type, tn.created_at, tn.updated_at
+         FROM tree_nodes tn
+         LEFT JOIN node_resources nr ON nr.node_id = tn.id
+         WHERE ?1 IS NULL OR tn.scope = ?1
+         ORDER BY tn.parent_id, tn.order_index",
+    )?;
+    let rows = stmt.query_map(params![scope], tree_node_from_row)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// 按 parent_id 组装为嵌套树；同级按 order_index 排序，遇到环时截断
+pub fn list_tree(storage: &Storage, scope: Option<&str>) -> Result<Vec<TreeResponseNode>> {
+    let nodes = list(storage, scope)?;
+    let by_id: HashMap<&str, &TreeNode> = nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    // parent -> children，父节点不存在的节点视为根节点
+    let mut children_map: HashMap<Option<&str>, Vec<&TreeNode>> = HashMap::new();
+    for node in &nodes {
+        let parent = node
+            .parent_id
+            .as_deref()
+            .filter(|pid| by_id.contains_key(pid));
+        children_map.entry(parent).or_default().push(node);
+    }
+    for children in children_map.values_mut() {
+        children.sort_by_key(|n| n.order_index);
+    }
+
+    fn build<'a>(
+        node: &'a TreeNode,
+        children_map: &HashMap<Option<&'a str>, Vec<&'a TreeNode>>,
+        visiting: &mut HashSet<&'a str>,
+    ) -> TreeResponseNode {
+        let mut children = Vec::new();
+        // 检测到环时不再向下展开
+        if visiting.insert(node.id.as_str()) {
+            if let Some(child_nodes) = children_map.get(&Some(node.id.as_str())) {
+                for child in child_nodes {
+                    children.push(build(child, children_map, visiting));
+                }
+            }
+            visiting.remove(node.id.as_str());
+        }
+
+        TreeResponseNode {
+            id: node.id.clone(),
+            label: node.name.clone(),
+            node_type: node.node_type.clone(),
+            resource_id: node.resource_id.clone(),
+            resource_type: node.resource_type.clone(),
+            children: if children.is_empty() {
+                None
+            } else {
+                Some(children)
+            },
+        }
+    }
+
+    let mut visiting = HashSet::new();
+    Ok(children_map
+        .get(&None)
+        .map(|roots| {
+            roots
+                .iter()
+                .map(|root| build(root, &children_map, &mut visiting))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// 一页子节点的默认 / 最大条数
+pub const DEFAULT_PAGE_SIZE: i64 = 200;
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+/// 按需展开时返回的节点：不含子节点，只带子节点数
+#[derive(Serialize)]
+pub struct ChildNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub label: String,
+    pub node_type: String,
+    pub scope: String,
+    pub order_index: i64,
+    pub resource_id: Option<String>,
+    pub resource_type: Option<String>,
+    pub has_children: bool,
+    pub child_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct ChildrenPage {
+    pub children: Vec<ChildNode>,
+    /// 下一页的游标；None 表示已是最后一页
+    pub next_cursor: Option<String>,
+    /// 子节点总数
+    pub total: i64,
+}
+
+#[derive(Serialize)]
+pub struct NodeWithChildren {
+    pub node: ChildNode,
+    pub children: ChildrenPage,
+}
+
+const CHILD_NODE_SELECT: &str = "SELECT tn.id, tn.parent_id, tn.name, tn.node_type, tn.scope, tn.order_index, nr.resource_id, nr.resource_type,
+            (SELECT COUNT(1) FROM tree_nodes c WHERE c.parent_id = tn.id)
+         FROM tree_nodes tn
+         LEFT JOIN node_resources nr ON nr.node_id = tn.id";
+
+/// parent_id 为空时匹配根节点（含父节点已不存在的节点，与 list_tree 一致）
+const CHILD_OF: &str = "(?1 IS NULL AND (tn.parent_id IS NULL OR NOT EXISTS (SELECT 1 FROM tree_nodes p WHERE p.id = tn.parent_id)) OR tn.parent_id = ?1)
+           AND (?2 IS NULL OR tn.scope = ?2)";
+
+fn child_node_from_row(r: &Row) -> rusqlite::Result<ChildNode> {
+    let child_count: i64 = r.get(8)?;
+    Ok(ChildNode {
+        id: r.get(0)?,
+        parent_id: r.get(1)?,
+        label: r.get(2)?,
+        node_type: r.get(3)?,
+        scope: r.get(4)?,
+        order_index: r.get(5)?,
+        resource_id: r.get(6)?,
+        resource_type: r.get(7)?,
+        has_children: child_count > 0,
+        child_count,
+    })
+}
+
+/// 游标为上一页最后一项的 `<order_index>:<id>`
+fn parse_cursor(cursor: &str) -> Result<(i64, &str)> {
+    cursor
+        .split_once(':')
+        .and_then(|(order, id)| Some((order.parse().ok()?, id)))
+        .ok_or_else(|| Error::Invalid("invalid cursor".to_string()))
+}
+
+/// 分页列出直接子节点（按 order_index、id 排序）
+///
+/// parent_id 为空时列出根节点；scope 为空时不限 scope
+pub fn list_children(
+    storage: &Storage,
+    parent_id: Option<&str>,
+    scope: Option<&str>,
+    cursor: Option<&str>,
+    limit: Option<i64>,
+) -> Result<ChildrenPage> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let (after_order, after_id) = match cursor {
+        Some(cursor) => {
+            let (order, id) = parse_cursor(cursor)?;
+            (Some(order), Some(id))
+        }
+        None => (None, None),
+    };
+
+    let total: i64 = storage.conn.query_row(
+        &format!("SELECT COUNT(1) FROM tree_nodes tn WHERE {}", CHILD_OF),
+        params![parent_id, scope],
+        |r| r.get(0),
+    )?;
+
+    // 多取一条判断是否还有下一页
+    let mut stmt = storage.conn.prepare(&format!(
+        "{} WHERE {}
+           AND (?3 IS NULL OR tn.order_index > ?3 OR (tn.order_index = ?3 AND tn.id > ?4))
+         ORDER BY tn.order_index, tn.id
+         LIMIT ?5",
+        CHILD_NODE_SELECT, CHILD_OF
+    ))?;
+    let rows = stmt.query_map(
+        params![parent_id, scope, after_order, after_id, limit + 1],
+        child_node_from_row,
+    )?;
+    let mut children = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let next_cursor = if children.len() as i64 > limit {
+        children.truncate(limit as usize);
+        children
+            .last()
+            .map(|last| format!("{}:{}", last.order_index, last.id))
+    } else {
+        None
+    };
+
+    Ok(ChildrenPage {
+        children,
+        next_cursor,
+        total,
+    })
+}
+
+/// 单个节点及其第一页直接子节点（修改后刷新侧边栏中的该节点）
+pub fn node_with_children(
+    storage: &Storage,
+    node_id: &str,
+    limit: Option<i64>,
+) -> Result<NodeWithChildren> {
+    let node = storage
+        .conn
+        .query_row(
+            &format!("{} WHERE tn.id = ?", CHILD_NODE_SELECT),
+            params![node_id],
+            child_node_from_row,
+        )
+        .optional()?
+        .ok_or(Error::NotFound("node"))?;
+    let children = list_children(storage, Some(node_id), None, None, limit)?;
+    Ok(NodeWithChildren { node, children })
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+use super::{Result, Storage};
+use crate::changelog::{self, UndoAction};
+
+#[derive(Serialize)]
+pub struct UndoState {
+    /// 可撤销的操作（用于菜单显示「撤销 xxx」）
+    pub undo: Option<UndoAction>,
+    /// 可重做的操作
+    pub redo: Option<UndoAction>,
+}
+
+/// 撤销最近一次操作；没有可撤销的操作时返回 None
+pub fn undo(storage: &mut Storage) -> Result<Option<UndoAction>> {
+    Ok(changelog::undo(&mut storage.conn)?)
+}
+
+/// 重做最近一次撤销的操作；没有可重做的操作时返回 None
+pub fn redo(storage: &mut Storage) -> Result<Option<UndoAction>> {
+    Ok(changelog::redo(&mut storage.conn)?)
+}
+
+/// 当前可撤销 / 可重做的操作
+pub fn state(storage: &Storage) -> Result<UndoState> {
+    Ok(UndoState {
+        undo: changelog::next_undo(&storage.conn)?,
+        redo: changelog::next_redo(&storage.conn)?,
+    })
+}
@@ -0,0 +1,537 @@
+//! 用户偏好设置：带类型、默认值与校验的键值存储
+//!
+//! 值以 JSON 文本保存在 settings 表中，键加 `prefs.` 前缀，与同步、API 等内部设置区分；
+//! 只保存与默认值不同的项。结构变化时提升 [`SCHEMA_VERSION`] 并在 [`MIGRATIONS`] 中追加迁移
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::collections::BTreeMap;
+
+use crate::db::models::{get_setting, set_setting};
+
+/// 设置变化后发给所有窗口的事件，参数为变化后的全部设置
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// 当前设置结构版本
+pub const SCHEMA_VERSION: i64 = 1;
+const VERSION_KEY: &str = "prefs.version";
+const KEY_PREFIX: &str = "prefs.";
+
+/// 设置项的类型与取值范围
+enum Kind {
+    Bool,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Text {
+        min_len: usize,
+        max_len: usize,
+    },
+    /// 可为空（null）的文本，空字符串视为 null
+    OptionalText {
+        max_len: usize,
+    },
+    Choice(&'static [&'static str]),
+    /// 代码高亮主题，取值见 [`crate::lang::highlight::theme_names`]
+    HighlightTheme,
+}
+
+struct Definition {
+    key: &'static str,
+    kind: Kind,
+    /// 默认值（JSON 字面量）
+    default: &'static str,
+}
+
+const DEFINITIONS: &[Definition] = &[
+    Definition {
+        key: "appearance.theme",
+        kind: Kind::Choice(&["system", "light", "dark"]),
+        default: r#""system""#,
+    },
+    Definition {
+        key: "appearance.language",
+        kind: Kind::Choice(&["system", "en", "zh"]),
+        default: r#""system""#,
+    },
+    Definition {
+        key: "editor.font_size",
+        kind: Kind::Integer { min: 8, max: 48 },
+        default: "14",
+    },
+    Definition {
+        key: "editor.tab_size",
+        kind: Kind::Integer { min: 1, max: 8 },
+        default: "4",
+    },
+    Definition {
+        key: "editor.line_numbers",
+        kind: Kind::Bool,
+        default: "false",
+    },
+    Definition {
+        key: "editor.spell_check",
+        kind: Kind::Bool,
+        default: "true",
+    },
+    Definition {
+        key: "editor.autosave_delay_ms",
+        kind: Kind::Integer {
+            min: 200,
+            max: 60_000,
+        },
+        default: "1000",
+    },
+    Definition {
+        key: "notes.highlight_theme",
+        kind: Kind::HighlightTheme,
+        default: r#""InspiredGitHub""#,
+    },
+    Definition {
+        key: "journal.folder",
+        kind: Kind::Text {
+            min_len: 1,
+            max_len: 100,
+        },
+        default: r#""Journal""#,
+    },
+    Definition {
+        key: "journal.template_id",
+        kind: Kind::OptionalText { max_len: 100 },
+        default: "null",
+    },
+    Definition {
+        key: "tasks.show_done",
+        kind: Kind::Bool,
+        default: "true",
+    },
+];
+
+/// 旧版本设置的迁移：MIGRATIONS[i] 把版本 i + 1 的设置升级到 i + 2
+const MIGRATIONS: &[fn(&mut JsonMap<String, JsonValue>)] = &[];
+
+/// 早期直接以纯文本保存在 settings 表中的设置项（键名与设置项相同、无前缀），读取时并入
+const LEGACY_KEYS: &[&str] = &["journal.folder", "journal.template_id"];
+
+/// 全部设置（含默认值）
+#[derive(Serialize, Clone)]
+pub struct Settings {
+    pub version: i64,
+    pub values: BTreeMap<String, JsonValue>,
+}
+
+/// 导出文件格式
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    version: i64,
+    settings: JsonMap<String, JsonValue>,
+}
+
+fn definition(key: &str) -> Result<&'static Definition, String> {
+    DEFINITIONS
+        .iter()
+        .find(|d| d.key == key)
+        .ok_or_else(|| format!("unknown setting: {}", key))
+}
+
+fn default_value(def: &Definition) -> JsonValue {
+    serde_json::from_str(def.default).expect("invalid setting default")
+}
+
+/// 校验设置值，返回规范化后的值（文本去除首尾空白）
+fn validate(key: &str, value: JsonValue) -> Result<JsonValue, String> {
+    let def = definition(key)?;
+    let invalid = |expected: String| format!("invalid value for {}: expected {}", key, expected);
+    match &def.kind {
+        Kind::Bool => value
+            .as_bool()
+            .map(JsonValue::from)
+            .ok_or_else(|| invalid("a boolean".to_string())),
+        Kind::Integer { min, max } => value
+            .as_i64()
+            .filter(|v| (*min..=*max).contains(v))
+            .map(JsonValue::from)
+            .ok_or_else(|| invalid(format!("an integer between {} and {}", min, max))),
+        Kind::Text { min_len, max_len } => value
+            .as_str()
+            .map(str::trim)
+            .filter(|v| (*min_len..=*max_len).contains(&v.chars().count()))
+            .map(JsonValue::from)
+            .ok_or_else(|| invalid(format!("text of {} to {} characters", min_len, max_len))),
+        Kind::OptionalText { max_len } => match value {
+            JsonValue::Null => Ok(JsonValue::Null),
+            JsonValue::String(v) if v.trim().is_empty() => Ok(JsonValue::Null),
+            JsonValue::String(v) if v.trim().chars().count() <= *max_len => {
+                Ok(JsonValue::from(v.trim()))
+            }
+            _ => Err(invalid(format!(
+                "null or text of at most {} characters",
+                max_len
+            ))),
+        },
+        Kind::Choice(options) => value
+            .as_str()
+            .filter(|v| options.contains(v))
+            .map(JsonValue::from)
+            .ok_or_else(|| invalid(format!("one of {}", options.join(", ")))),
+        Kind::HighlightTheme => value
+            .as_str()
+            .filter(|v| crate::lang::highlight::theme_names().iter().any(|t| t == v))
+            .map(JsonValue::from)
+            .ok_or_else(|| invalid("an installed highlight theme".to_string())),
+    }
+}
+
+/// 按版本依次迁移；版本高于当前程序时拒绝
+fn migrate(version: i64, values: &mut JsonMap<String, JsonValue>) -> Result<(), String> {
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "settings version {} is newer than supported version {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+    for migration in MIGRATIONS.iter().skip((version.max(1) - 1) as usize) {
+        migration(values);
+    }
+    Ok(())
+}
+
+/// 读取已保存的设置项（不含默认值），只读；旧版本的设置在内存中升级
+fn load_stored(conn: &Connection) -> Result<JsonMap<String, JsonValue>, String> {
+    let mut stored = read_stored(conn)?;
+    let version = stored_version(conn)?;
+    if version != SCHEMA_VERSION {
+        migrate(version, &mut stored)?;
+    }
+    Ok(stored)
+}
+
+fn read_stored(conn: &Connection) -> Result<JsonMap<String, JsonValue>, String> {
+    let mut stored = JsonMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings WHERE key LIKE 'prefs.%' AND key != ?")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![VERSION_KEY], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (key, value) = row.map_err(|e| e.to_string())?;
+            let Ok(value) = serde_json::from_str(&value) else {
+                continue;
+            };
+            stored.insert(key[KEY_PREFIX.len()..].to_string(), value);
+        }
+    }
+    Ok(stored)
+}
+
+fn stored_version(conn: &Connection) -> Result<i64, String> {
+    Ok(get_setting(conn, VERSION_KEY)
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(SCHEMA_VERSION))
+}
+
+/// 数据库迁移时升级已保存的设置：按版本迁移，并并入 [`LEGACY_KEYS`] 中的旧设置
+///
+/// 由 [`crate::db::migrate::run`] 调用；版本高于当前程序的设置保持不变
+pub(crate) fn upgrade(conn: &Connection) -> Result<(), String> {
+    let version = stored_version(conn)?;
+    let legacy = load_legacy(conn)?;
+    if version > SCHEMA_VERSION || (version == SCHEMA_VERSION && legacy.is_empty()) {
+        return Ok(());
+    }
+
+    let mut stored = read_stored(conn)?;
+    migrate(version, &mut stored)?;
+    for (key, value) in legacy {
+        if let Ok(value) = validate(&key, value) {
+            stored.entry(key).or_insert(value);
+        }
+    }
+    stored.retain(|key, _| definition(key).is_ok());
+    write(conn, &stored, true)?;
+    for key in LEGACY_KEYS {
+        conn.execute("DELETE FROM settings WHERE key = ?", params![key])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 读取 [`LEGACY_KEYS`] 中仍以旧格式保存的设置项
+fn load_legacy(conn: &Connection) -> Result<JsonMap<String, JsonValue>, String> {
+    let mut legacy = JsonMap::new();
+    for key in LEGACY_KEYS {
+        if let Some(value) = get_setting(conn, key).map_err(|e| e.to_string())? {
+            legacy.insert(key.to_string(), JsonValue::from(value));
+        }
+    }
+    Ok(legacy)
+}
+
+/// 写入设置项；replace 为 true 时先清空已有的设置
+fn write(
+    conn: &Connection,
+    values: &JsonMap<String, JsonValue>,
+    replace: bool,
+) -> Result<(), String> {
+    let now = Utc::now().timestamp();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    if replace {
+        tx.execute("DELETE FROM settings WHERE key LIKE 'prefs.%'", [])
+            .map_err(|e| e.to_string())?;
+    }
+    for (key, value) in values {
+        let def = definition(key)?;
+        let stored_key = format!("{}{}", KEY_PREFIX, key);
+        // 与默认值相同的项不保存，默认值变化时随之变化
+        let text = (*value != default_value(def)).then(|| value.to_string());
+        set_setting(&tx, &stored_key, text.as_deref(), now).map_err(|e| e.to_string())?;
+    }
+    set_setting(&tx, VERSION_KEY, Some(&SCHEMA_VERSION.to_string()), now)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 全部设置：已保存的值覆盖默认值（未知或不合法的旧值回退到默认值）
+pub fn get(conn: &Connection) -> Result<Settings, String> {
+    let stored = load_stored(conn)?;
+    let values = DEFINITIONS
+        .iter()
+        .map(|def| {
+            let value = stored
+                .get(def.key)
+                .and_then(|v| validate(def.key, v.clone()).ok())
+                .unwrap_or_else(|| default_value(def));
+            (def.key.to_string(), value)
+        })
+        .collect();
+    Ok(Settings {
+        version: SCHEMA_VERSION,
+        values,
+    })
+}
+
+/// 代码高亮主题（`notes.highlight_theme`），渲染 note 与 snippet 时未指定主题则使用此值
+pub fn highlight_theme(conn: &Connection) -> Result<String, String> {
+    Ok(value(conn, "notes.highlight_theme")?
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// 单个设置项的当前值（未设置时为默认值）
+pub fn value(conn: &Connection, key: &str) -> Result<JsonValue, String> {
+    get(conn)?
+        .values
+        .remove(key)
+        .ok_or_else(|| format!("unknown setting: {}", key))
+}
+
+/// 更新部分设置；任一项不合法时全部不写入
+pub fn update(conn: &Connection, values: JsonMap<String, JsonValue>) -> Result<Settings, String> {
+    load_stored(conn)?;
+    let validated = values
+        .into_iter()
+        .map(|(key, value)| validate(&key, value).map(|v| (key, v)))
+        .collect::<Result<JsonMap<_, _>, _>>()?;
+    write(conn, &validated, false)?;
+    get(conn)
+}
+
+/// 恢复默认值；keys 为 None 或空时恢复全部
+pub fn reset(conn: &Connection, keys: Option<&[String]>) -> Result<Settings, String> {
+    match keys.filter(|keys| !keys.is_empty()) {
+        None => write(conn, &JsonMap::new(), true)?,
+        Some(keys) => {
+            let defaults = keys
+                .iter()
+                .map(|key| definition(key).map(|def| (key.clone(), default_value(def))))
+                .collect::<Result<JsonMap<_, _>, _>>()?;
+            write(conn, &defaults, false)?;
+        }
+    }
+    get(conn)
+}
+
+/// 导出为 JSON（包含全部设置项，便于查看与编辑）
+pub fn export(conn: &Connection) -> Result<String, String> {
+    let settings = get(conn)?;
+    let file = SettingsFile {
+        version: settings.version,
+        settings: settings.values.into_iter().collect(),
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+}
+
+/// 从 JSON 导入：先按文件版本迁移，再整体替换当前设置（文件中缺少的项恢复默认值）
+pub fn import(conn: &Connection, json: &str) -> Result<Settings, String> {
+    let mut file: SettingsFile =
+        serde_json::from_str(json).map_err(|e| format!("invalid settings file: {}", e))?;
+    migrate(file.version, &mut file.settings)?;
+
+    let validated = file
+        .settings
+        .into_iter()
+        .map(|(key, value)| validate(&key, value).map(|v| (key, v)))
+        .collect::<Result<JsonMap<_, _>, _>>()?;
+    write(conn, &validated, true)?;
+    get(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrate;
+    use serde_json::json;
+
+    fn connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate::run(&conn).unwrap();
+        conn
+    }
+
+    fn values(value: JsonValue) -> JsonMap<String, JsonValue> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn validate_checks_type_and_range() {
+        assert_eq!(
+            validate("editor.line_numbers", json!(true)),
+            Ok(json!(true))
+        );
+        assert!(validate("editor.line_numbers", json!("yes")).is_err());
+        assert_eq!(validate("editor.font_size", json!(16)), Ok(json!(16)));
+        assert!(validate("editor.font_size", json!(100)).is_err());
+        assert!(validate("appearance.theme", json!("blue")).is_err());
+        assert_eq!(
+            validate("journal.folder", json!("  Daily ")),
+            Ok(json!("Daily"))
+        );
+        assert!(validate("journal.folder", json!("   ")).is_err());
+        assert_eq!(
+            validate("journal.template_id", json!("")),
+            Ok(JsonValue::Null)
+        );
+        assert_eq!(
+            validate("journal.template_id", json!(null)),
+            Ok(JsonValue::Null)
+        );
+        assert!(validate("journal.template_id", json!(1)).is_err());
+        assert!(validate("unknown.key", json!(1)).is_err());
+    }
+
+    #[test]
+    fn highlight_theme_must_be_installed() {
+        let theme = crate::lang::highlight::theme_names().remove(0);
+        assert_eq!(
+            validate("notes.highlight_theme", json!(theme.clone())),
+            Ok(json!(theme))
+        );
+        assert!(validate("notes.highlight_theme", json!("No Such Theme")).is_err());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        for def in DEFINITIONS {
+            assert_eq!(
+                validate(def.key, default_value(def)),
+                Ok(default_value(def))
+            );
+        }
+    }
+
+    #[test]
+    fn update_is_all_or_nothing() {
+        let conn = connection();
+        let err = update(
+            &conn,
+            values(json!({ "editor.font_size": 20, "editor.tab_size": 0 })),
+        );
+        assert!(err.is_err());
+        assert_eq!(value(&conn, "editor.font_size"), Ok(json!(14)));
+
+        update(&conn, values(json!({ "editor.font_size": 20 }))).unwrap();
+        assert_eq!(value(&conn, "editor.font_size"), Ok(json!(20)));
+    }
+
+    #[test]
+    fn reset_without_keys_restores_everything() {
+        let conn = connection();
+        update(
+            &conn,
+            values(json!({ "editor.font_size": 20, "tasks.show_done": false })),
+        )
+        .unwrap();
+
+        reset(&conn, Some(&[])).unwrap();
+        assert_eq!(value(&conn, "editor.font_size"), Ok(json!(14)));
+        assert_eq!(value(&conn, "tasks.show_done"), Ok(json!(true)));
+    }
+
+    #[test]
+    fn migrate_rejects_newer_versions() {
+        let mut stored = JsonMap::new();
+        assert!(migrate(SCHEMA_VERSION, &mut stored).is_ok());
+        assert!(migrate(SCHEMA_VERSION + 1, &mut stored).is_err());
+    }
+
+    #[test]
+    fn legacy_journal_settings_are_adopted() {
+        let conn = connection();
+        set_setting(&conn, "journal.folder", Some("Diary"), 0).unwrap();
+        set_setting(&conn, "journal.template_id", Some("template-1"), 0).unwrap();
+        // 读取不写入数据库，旧设置在数据库迁移时并入
+        assert_eq!(value(&conn, "journal.folder"), Ok(json!("Journal")));
+        migrate::run(&conn).unwrap();
+
+        assert_eq!(value(&conn, "journal.folder"), Ok(json!("Diary")));
+        assert_eq!(value(&conn, "journal.template_id"), Ok(json!("template-1")));
+        assert_eq!(get_setting(&conn, "journal.folder").unwrap(), None);
+        assert_eq!(
+            get_setting(&conn, "prefs.journal.folder").unwrap(),
+            Some(r#""Diary""#.to_string())
+        );
+    }
+
+    #[test]
+    fn export_import_round_trips() {
+        let source = connection();
+        update(
+            &source,
+            values(json!({
+                "appearance.theme": "dark",
+                "editor.font_size": 18,
+                "journal.folder": "Diary",
+                "journal.template_id": "template-1",
+            })),
+        )
+        .unwrap();
+        let exported = export(&source).unwrap();
+
+        let target = connection();
+        update(&target, values(json!({ "editor.tab_size": 2 }))).unwrap();
+        let imported = import(&target, &exported).unwrap();
+
+        assert_eq!(imported.values, get(&source).unwrap().values);
+        // 文件中的值覆盖全部设置，目标原有的修改恢复为文件中的值
+        assert_eq!(value(&target, "editor.tab_size"), Ok(json!(4)));
+    }
+
+    #[test]
+    fn import_rejects_invalid_files() {
+        let conn = connection();
+        let newer = json!({ "version": SCHEMA_VERSION + 1, "settings": {} }).to_string();
+        assert!(import(&conn, &newer).is_err());
+        let invalid = json!({ "version": 1, "settings": { "editor.font_size": "big" } });
+        assert!(import(&conn, &invalid.to_string()).is_err());
+        assert!(import(&conn, "not json").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use chrono::Local;
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::transport::{Precondition, Transport, WriteOutcome};
+use super::SyncReport;
+
+/// 按文件同步的目录（相对数据目录）；note 文件随变更记录同步，不在此列
+pub const FILE_SYNC_DIRS: &[&str] = &["attachments"];
+
+/// 上次同步时的文件状态
+struct FileState {
+    local_hash: String,
+    remote_etag: Option<String>,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 同步附件文件：先推送本机修改，再拉取远端修改
+///
+/// 以上次同步时记录的内容哈希与远端 ETag 判断哪一端有修改；两端都修改时远端版本
+/// 保留原文件名，本机版本另存为冲突副本。文件删除不会同步
+pub fn sync_files(
+    conn: &Connection,
+    transport: &dyn Transport,
+    data_dir: &Path,
+    device_name: &str,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let mut index = load_index(conn)?;
+    let mut local = HashMap::new();
+    let mut remote = HashMap::new();
+    for dir in FILE_SYNC_DIRS {
+        walk_local(data_dir, dir, &mut local)?;
+        walk_remote(transport, dir, &mut remote)?;
+    }
+
+    // 1. 推送：上次同步后本机有修改的文件，按记录的 ETag 条件写入
+    let mut forced = HashSet::new();
+    for (path, hash) in &local {
+        let state = index.get(path);
+        if state.is_some_and(|s| &s.local_hash == hash) {
+            continue;
+        }
+        // 本机新文件与远端同名文件内容相同（例如两端各自导入）
+        if state.is_none() && remote.contains_key(path) {
+            let (data, etag) = transport.fetch(path)?;
+            if &sha256_hex(&data) == hash {
+                save_state(conn, &mut index, path, hash, etag)?;
+                continue;
+            }
+        }
+
+        let data = fs::read(data_dir.join(path)).map_err(|e| e.to_string())?;
+        let precondition = match state.and_then(|s| s.remote_etag.as_deref()) {
+            Some(etag) => Precondition::Match(etag),
+            None if state.is_some() => Precondition::Any,
+            None => Precondition::Absent,
+        };
+        match transport.write_if(path, &data, precondition)? {
+            WriteOutcome::Written(etag) => {
+                save_state(conn, &mut index, path, hash, etag)?;
+                report.files_pushed += 1;
+            }
+            WriteOutcome::Conflict => {
+                // 远端保留其他设备的版本，本机版本另存为冲突副本
+                let copy = conflict_copy_path(path, device_name);
+                fs::write(data_dir.join(&copy), &data).map_err(|e| e.to_string())?;
+                let etag = match transport.write_if(&copy, &data, Precondition::Any)? {
+                    WriteOutcome::Written(etag) => etag,
+                    WriteOutcome::Conflict => None,
+                };
+                save_state(conn, &mut index, &copy, hash, etag)?;
+
+                forced.insert(path.clone());
+                report.conflicts += 1;
+                report.file_conflicts.push(copy);
+            }
+        }
+    }
+
+    // 2. 拉取：远端版本与上次同步时不同，且本机未修改（或已另存冲突副本）的文件
+    for (path, etag) in &remote {
+        let state = index.get(path);
+        if !forced.contains(path) {
+            if etag.is_some() && state.and_then(|s| s.remote_etag.as_ref()) == etag.as_ref() {
+                continue;
+            }
+            let local_changed = match (local.get(path), state) {
+                (Some(hash), Some(state)) => hash != &state.local_hash,
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            if local_changed {
+                continue;
+            }
+        }
+
+        let (data, fetched_etag) = transport.fetch(path)?;
+        let hash = sha256_hex(&data);
+        let target = data_dir.join(path);
+        if local.get(path) != Some(&hash) {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let tmp = target.with_extension("tmp");
+            fs::write(&tmp, &data).map_err(|e| e.to_string())?;
+            fs::rename(&tmp, &target).map_err(|e| e.to_string())?;
+            report.files_pulled += 1;
+        }
+        save_state(
+            conn,
+            &mut index,
+            path,
+            &hash,
+            fetched_etag.or_else(|| etag.clone()),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn load_index(conn: &Connection) -> Result<HashMap<String, FileState>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, local_hash, remote_etag FROM sync_files")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                FileState {
+                    local_hash: r.get(1)?,
+                    remote_etag: r.get(2)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<HashMap<_, _>>>()
+        .map_err(|e| e.to_string())
+}
+
+fn save_state(
+    conn: &Connection,
+    index: &mut HashMap<String, FileState>,
+    path: &str,
+    local_hash: &str,
+    remote_etag: Option<String>,
+) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT INTO sync_files (path, local_hash, remote_etag, synced_at) VALUES (?, ?, ?, ?)
+        ON CONFLICT(path) DO UPDATE SET
+            local_hash = excluded.local_hash,
+            remote_etag = excluded.remote_etag,
+            synced_at = excluded.synced_at
+        "#,
+        params![
+            path,
+            local_hash,
+            remote_etag,
+            chrono::Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    index.insert(
+        path.to_string(),
+        FileState {
+            local_hash: local_hash.to_string(),
+            remote_etag,
+        },
+    );
+    Ok(())
+}
+
+/// 本机文件：相对路径 → 内容哈希
+fn walk_local(
+    data_dir: &Path,
+    dir: &str,
+    files: &mut HashMap<String, String>,
+) -> Result<(), String> {
+    let entries = match fs::read_dir(data_dir.join(dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if name.starts_with('.') || name.ends_with(".tmp") {
+            continue;
+        }
+        let path = format!("{}/{}", dir, name);
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        if file_type.is_dir() {
+            walk_local(data_dir, &path, files)?;
+        } else if file_type.is_file() {
+            let data = fs::read(entry.path()).map_err(|e| e.to_string())?;
+            files.insert(path, sha256_hex(&data));
+        }
+    }
+    Ok(())
+}
+
+/// 远端文件：相对路径 → ETag
+fn walk_remote(
+    transport: &dyn Transport,
+    dir: &str,
+    files: &mut HashMap<String, Option<String>>,
+) -> Result<(), String> {
+    for entry in transport.list_entries(dir)? {
+        if entry.name.starts_with('.') {
+            continue;
+        }
+        let path = format!("{}/{}", dir, entry.name);
+        if entry.is_dir {
+            walk_remote(transport, &path, files)?;
+        } else {
+            files.insert(path, entry.etag);
+        }
+    }
+    Ok(())
+}
+
+/// `attachments/images/a.png` → `attachments/images/a (conflict copy <设备> <时间>).png`
+fn conflict_copy_path(path: &str, device_name: &str) -> String {
+    let (dir, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = match file.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (file, String::new()),
+    };
+    let device: String = device_name
+        .chars()
+        .map(|c| if r#"/\:*?"<>|"#.contains(c) { '_' } else { c })
+        .collect();
+    let file = format!(
+        "{} (conflict copy {} {}){}",
+        stem,
+        device,
+        Local::now().format("%Y-%m-%d %H%M%S"),
+        ext
+    );
+    if dir.is_empty() {
+        file
+    } else {
+        format!("{}/{}", dir, file)
+    }
+}
@@ -0,0 +1,615 @@
+pub mod files;
+pub mod records;
+pub mod transport;
+pub mod webdav;
+
+#[cfg(test)]
+mod tests;
+
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::db::models::{
+    get_note_aliases, get_note_tags, get_setting, set_note_aliases, set_note_tags, set_setting,
+};
+use crate::service::notes::{insert_note_copy, note_parent_id};
+use records::{SyncTable, SYNC_TABLES};
+use transport::{FolderTransport, Transport};
+use webdav::WebDavTransport;
+
+/// 存在即表示已启用同步（触发器据此决定是否记录变更）
+pub const SYNC_ENABLED_KEY: &str = "sync.enabled";
+pub const SYNC_TRANSPORT_KEY: &str = "sync.transport";
+pub const SYNC_DIR_KEY: &str = "sync.dir";
+pub const SYNC_WEBDAV_URL_KEY: &str = "sync.webdav.url";
+pub const SYNC_WEBDAV_USERNAME_KEY: &str = "sync.webdav.username";
+pub const SYNC_WEBDAV_PASSWORD_KEY: &str = "sync.webdav.password";
+const DEVICE_ID_KEY: &str = "sync.device_id";
+const DEVICE_NAME_KEY: &str = "sync.device_name";
+/// 本机 Lamport 时钟
+const CLOCK_KEY: &str = "sync.clock";
+
+/// 同一时刻只允许一次同步
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+/// 记录版本：产生该版本的设备 + 其 Lamport 时钟
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub device_id: String,
+    pub clock: i64,
+}
+
+impl Version {
+    /// 并发修改时的胜者判定：时钟大者胜，相同时比较设备 id（两端结果一致）
+    fn wins_over(&self, other: &Version) -> bool {
+        (self.clock, &self.device_id) > (other.clock, &other.device_id)
+    }
+}
+
+/// note 记录附带的文件内容与标签（加密 note 为密文原样）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotePayload {
+    pub content: String,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+}
+
+/// 单条记录的变更文件：`changes/<device_id>/<clock>.json`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Change {
+    pub device_id: String,
+    pub clock: i64,
+    pub table: String,
+    pub key: String,
+    /// 修改后的完整记录；None 表示删除
+    pub row: Option<JsonMap<String, JsonValue>>,
+    /// 本次修改基于的版本（首次创建时为 None），用于判断并发修改
+    pub base: Option<Version>,
+    pub note: Option<NotePayload>,
+}
+
+impl Change {
+    fn version(&self) -> Version {
+        Version {
+            device_id: self.device_id.clone(),
+            clock: self.clock,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeviceInfo {
+    device_id: String,
+    name: String,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct SyncReport {
+    pub exported: usize,
+    pub imported: usize,
+    /// 检测到的并发修改数
+    pub conflicts: usize,
+    /// 为保留本机被覆盖内容而创建的冲突副本 note
+    pub conflict_copies: Vec<String>,
+    /// 上传 / 下载的附件文件数
+    pub files_pushed: usize,
+    pub files_pulled: usize,
+    /// 两端同时修改时另存的附件冲突副本（相对数据目录的路径）
+    pub file_conflicts: Vec<String>,
+}
+
+pub fn is_enabled(conn: &Connection) -> Result<bool, String> {
+    Ok(get_setting(conn, SYNC_ENABLED_KEY)
+        .map_err(|e| e.to_string())?
+        .is_some())
+}
+
+/// 本机设备 id（首次调用时生成）
+pub fn device_id(conn: &Connection) -> Result<String, String> {
+    if let Some(id) = get_setting(conn, DEVICE_ID_KEY).map_err(|e| e.to_string())? {
+        return Ok(id);
+    }
+    let id = Uuid::new_v4().to_string();
+    set_setting(
+        conn,
+        DEVICE_ID_KEY,
+        Some(&id),
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub fn device_name(conn: &Connection) -> Result<String, String> {
+    Ok(get_setting(conn, DEVICE_NAME_KEY)
+        .map_err(|e| e.to_string())?
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "device".to_string()))
+}
+
+/// 按设置创建同步后端
+pub fn transport_from_settings(conn: &Connection) -> Result<Box<dyn Transport>, String> {
+    let kind = get_setting(conn, SYNC_TRANSPORT_KEY).map_err(|e| e.to_string())?;
+    match kind.as_deref() {
+        Some("folder") => {
+            let dir = get_setting(conn, SYNC_DIR_KEY)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "sync directory is not configured".to_string())?;
+            Ok(Box::new(FolderTransport::new(PathBuf::from(dir))))
+        }
+        Some("webdav") => {
+            let url = get_setting(conn, SYNC_WEBDAV_URL_KEY)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "WebDAV url is not configured".to_string())?;
+            let username =
+                get_setting(conn, SYNC_WEBDAV_USERNAME_KEY).map_err(|e| e.to_string())?;
+            let password =
+                get_setting(conn, SYNC_WEBDAV_PASSWORD_KEY).map_err(|e| e.to_string())?;
+            Ok(Box::new(WebDavTransport::new(
+                &url,
+                username.as_deref(),
+                password.as_deref(),
+            )?))
+        }
+        _ => Err("sync is not configured".to_string()),
+    }
+}
+
+/// 启用同步：登记设备，并把现有全部记录加入待导出队列（完整快照）
+///
+/// 已启用时只更新设备名称
+pub fn enable(
+    conn: &Connection,
+    transport: &dyn Transport,
+    name: Option<&str>,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let device_id = device_id(conn)?;
+    if let Some(name) = name.map(str::trim).filter(|n| !n.is_empty()) {
+        set_setting(conn, DEVICE_NAME_KEY, Some(name), now).map_err(|e| e.to_string())?;
+    }
+
+    if !is_enabled(conn)? {
+        // 停用期间的修改没有记录，重新启用时整体导出一次
+        for table in SYNC_TABLES {
+            for key in records::all_keys(conn, table)? {
+                conn.execute(
+                    "INSERT INTO sync_log (table_name, row_key) VALUES (?, ?)",
+                    params![table.name, key],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+        set_setting(conn, SYNC_ENABLED_KEY, Some("1"), now).map_err(|e| e.to_string())?;
+    }
+
+    let info = DeviceInfo {
+        device_id: device_id.clone(),
+        name: device_name(conn)?,
+    };
+    let data = serde_json::to_vec_pretty(&info).map_err(|e| e.to_string())?;
+    transport.write(&format!("devices/{}.json", device_id), &data)
+}
+
+/// 停用同步（清空待导出队列，已同步的数据保留）
+pub fn disable(conn: &Connection) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    set_setting(conn, SYNC_ENABLED_KEY, None, now).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM sync_log", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 执行一次同步：先导出本机变更，再按时钟顺序合并其他设备的变更，最后同步附件文件
+pub fn sync_now(
+    conn: &mut Connection,
+    transport: &dyn Transport,
+    data_dir: &Path,
+) -> Result<SyncReport, String> {
+    let _guard = SYNC_LOCK.lock().map_err(|e| e.to_string())?;
+    if !is_enabled(conn)? {
+        return Err("sync is not enabled".to_string());
+    }
+
+    let device_id = device_id(conn)?;
+    let mut report = SyncReport {
+        exported: export(conn, transport, &device_id)?,
+        ..Default::default()
+    };
+    import(conn, transport, &device_id, data_dir, &mut report)?;
+    // 合并时新建的冲突副本立即导出
+    report.exported += export(conn, transport, &device_id)?;
+    files::sync_files(conn, transport, data_dir, &device_name(conn)?, &mut report)?;
+    // 清理失败不影响本次同步，下次再试
+    let _ = transport.collect_garbage();
+
+    Ok(report)
+}
+
+fn get_clock(conn: &Connection) -> Result<i64, String> {
+    Ok(get_setting(conn, CLOCK_KEY)
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0))
+}
+
+fn set_clock(conn: &Connection, clock: i64) -> Result<(), String> {
+    set_setting(
+        conn,
+        CLOCK_KEY,
+        Some(&clock.to_string()),
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn get_version(conn: &Connection, table: &str, key: &str) -> Result<Option<Version>, String> {
+    conn.query_row(
+        "SELECT device_id, clock FROM sync_versions WHERE table_name = ? AND row_key = ?",
+        params![table, key],
+        |r| {
+            Ok(Version {
+                device_id: r.get(0)?,
+                clock: r.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn set_version(conn: &Connection, table: &str, key: &str, version: &Version) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT INTO sync_versions (table_name, row_key, device_id, clock)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(table_name, row_key) DO UPDATE SET
+            device_id = excluded.device_id,
+            clock = excluded.clock
+        "#,
+        params![table, key, version.device_id, version.clock],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn note_payload(
+    conn: &Connection,
+    note_id: &str,
+    row: &JsonMap<String, JsonValue>,
+) -> Result<NotePayload, String> {
+    let content = row
+        .get("content_path")
+        .and_then(JsonValue::as_str)
+        .and_then(|p| fs::read_to_string(p).ok())
+        .unwrap_or_default();
+    Ok(NotePayload {
+        content,
+        tags: get_note_tags(conn, note_id).map_err(|e| e.to_string())?,
+        aliases: get_note_aliases(conn, note_id).map_err(|e| e.to_string())?,
+    })
+}
+
+/// 把 sync_log 中的本机修改逐条写成变更文件
+///
+/// 每条先推进时钟、再写文件、最后出队，中途失败时剩余记录留到下次
+fn export(conn: &Connection, transport: &dyn Transport, device_id: &str) -> Result<usize, String> {
+    let pending: Vec<(i64, String, String)> = {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT MAX(seq), table_name, row_key
+                FROM sync_log
+                GROUP BY table_name, row_key
+                ORDER BY MIN(seq)
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut clock = get_clock(conn)?;
+    let mut exported = 0;
+
+    for (seq, table_name, key) in pending {
+        let dequeue = || {
+            conn.execute(
+                "DELETE FROM sync_log WHERE table_name = ? AND row_key = ? AND seq <= ?",
+                params![table_name, key, seq],
+            )
+            .map_err(|e| e.to_string())
+        };
+
+        let Some(table) = records::find_table(&table_name) else {
+            dequeue()?;
+            continue;
+        };
+        let row = records::read_row(conn, table, &key)?;
+        let base = get_version(conn, &table_name, &key)?;
+        // 从未导出过就被删除的记录无需同步
+        if row.is_none() && base.is_none() {
+            dequeue()?;
+            continue;
+        }
+
+        let note = match (&row, table_name.as_str()) {
+            (Some(row), "notes") => Some(note_payload(conn, &key, row)?),
+            _ => None,
+        };
+
+        clock += 1;
+        set_clock(conn, clock)?;
+
+        let change = Change {
+            device_id: device_id.to_string(),
+            clock,
+            table: table_name.clone(),
+            key: key.clone(),
+            row,
+            base,
+            note,
+        };
+        let data = serde_json::to_vec(&change).map_err(|e| e.to_string())?;
+        transport.write(&format!("changes/{}/{:012}.json", device_id, clock), &data)?;
+
+        set_version(conn, &table_name, &key, &change.version())?;
+        dequeue()?;
+        exported += 1;
+    }
+
+    Ok(exported)
+}
+
+/// 读取其他设备尚未合并的变更（按设备游标增量读取）
+///
+/// 某个文件读取或解析失败（例如同步工具尚未传完）时，该设备其后的变更留到下次
+fn fetch_remote_changes(
+    conn: &Connection,
+    transport: &dyn Transport,
+    device_id: &str,
+) -> Result<Vec<Change>, String> {
+    let mut changes = Vec::new();
+
+    for peer in transport.list("changes")? {
+        if peer == device_id {
+            continue;
+        }
+        let cursor: i64 = conn
+            .query_row(
+                "SELECT last_clock FROM sync_peers WHERE device_id = ?",
+                params![&peer],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .unwrap_or(0);
+
+        let mut files: Vec<(i64, String)> = transport
+            .list(&format!("changes/{}", peer))?
+            .into_iter()
+            .filter_map(|name| {
+                let clock = name.strip_suffix(".json")?.parse::<i64>().ok()?;
+                (clock > cursor).then_some((clock, name))
+            })
+            .collect();
+        files.sort();
+
+        for (_, name) in files {
+            let parsed = transport
+                .read(&format!("changes/{}/{}", peer, name))
+                .and_then(|data| {
+                    serde_json::from_slice::<Change>(&data).map_err(|e| e.to_string())
+                });
+            match parsed {
+                Ok(change) if change.device_id == peer => changes.push(change),
+                _ => break,
+            }
+        }
+    }
+
+    changes.sort_by(|a, b| (a.clock, &a.device_id).cmp(&(b.clock, &b.device_id)));
+    Ok(changes)
+}
+
+/// 被覆盖前的本机 note，用于生成冲突副本
+struct LocalNote {
+    title: String,
+    parent_id: Option<String>,
+    content: String,
+}
+
+fn import(
+    conn: &mut Connection,
+    transport: &dyn Transport,
+    device_id: &str,
+    data_dir: &Path,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let changes = fetch_remote_changes(conn, transport, device_id)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    // 合并过程中触发器产生的日志需要清掉，避免把远端修改再导出回去
+    let log_seq: i64 = tx
+        .query_row("SELECT COALESCE(MAX(seq), 0) FROM sync_log", [], |r| {
+            r.get(0)
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut clock = get_clock(&tx)?;
+    let mut overwritten: Vec<LocalNote> = Vec::new();
+    // 提交后再落盘的 note 文件：Some 为写入内容，None 为删除
+    let mut note_files: BTreeMap<PathBuf, Option<String>> = BTreeMap::new();
+
+    for change in &changes {
+        clock = clock.max(change.clock);
+        tx.execute(
+            r#"
+            INSERT INTO sync_peers (device_id, last_clock, last_synced_at) VALUES (?, ?, ?)
+            ON CONFLICT(device_id) DO UPDATE SET
+                last_clock = MAX(last_clock, excluded.last_clock),
+                last_synced_at = excluded.last_synced_at
+            "#,
+            params![change.device_id, change.clock, now],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let Some(table) = records::find_table(&change.table) else {
+            continue;
+        };
+        let local_version = get_version(&tx, &change.table, &change.key)?;
+        if local_version.as_ref() == Some(&change.version()) {
+            continue;
+        }
+
+        // 本机版本不是对方修改所基于的版本 → 并发修改
+        let concurrent = local_version.is_some() && local_version != change.base;
+        let apply = match local_version {
+            Some(ref local) if concurrent => {
+                report.conflicts += 1;
+                let local_exists = records::read_row(&tx, table, &change.key)?.is_some();
+                match (change.row.is_some(), local_exists) {
+                    // 修改优先于删除
+                    (true, false) => true,
+                    (false, true) => false,
+                    (false, false) => true,
+                    (true, true) => change.version().wins_over(local),
+                }
+            }
+            _ => true,
+        };
+        if !apply {
+            continue;
+        }
+
+        // 本机 note 内容将被覆盖：先保存，合并后另存为冲突副本
+        if concurrent && change.table == "notes" && change.row.is_some() {
+            if let Some(local) = load_local_note(&tx, &change.key, &note_files)? {
+                overwritten.push(local);
+            }
+        }
+
+        apply_change(&tx, table, change, data_dir, &mut note_files)?;
+        set_version(&tx, &change.table, &change.key, &change.version())?;
+        report.imported += 1;
+    }
+
+    tx.execute("DELETE FROM sync_log WHERE seq > ?", params![log_seq])
+        .map_err(|e| e.to_string())?;
+    set_clock(&tx, clock)?;
+
+    // 冲突副本是本机新建的记录，需要正常导出给其他设备
+    for local in overwritten {
+        let title = format!(
+            "{} (conflict copy {})",
+            local.title,
+            Local::now().format("%Y-%m-%d %H:%M")
+        );
+        let (created, path, content) = insert_note_copy(
+            &tx,
+            data_dir,
+            &title,
+            local.parent_id.as_deref(),
+            &local.content,
+            now,
+        )?;
+        note_files.insert(path, Some(content));
+        report.conflict_copies.push(created.note_id);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for (path, content) in note_files {
+        match content {
+            Some(content) => fs::write(&path, content).map_err(|e| e.to_string())?,
+            None => {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 本机 note 的当前内容；本次合并中已被覆盖过的 note 取待写入的内容
+fn load_local_note(
+    tx: &Transaction,
+    note_id: &str,
+    note_files: &BTreeMap<PathBuf, Option<String>>,
+) -> Result<Option<LocalNote>, String> {
+    let Some((title, content_path)) = tx
+        .query_row(
+            "SELECT title, content_path FROM notes WHERE id = ?",
+            params![note_id],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(LocalNote {
+        title,
+        parent_id: note_parent_id(tx, note_id)?,
+        content: match note_files.get(Path::new(&content_path)) {
+            Some(pending) => pending.clone().unwrap_or_default(),
+            None => fs::read_to_string(content_path).unwrap_or_default(),
+        },
+    }))
+}
+
+/// 把一条远端变更写入本地；note 的文件路径改写为本机路径
+///
+/// note 文件的写入与删除记入 note_files，事务提交后再落盘，避免回滚后文件与数据库不一致
+fn apply_change(
+    tx: &Transaction,
+    table: &SyncTable,
+    change: &Change,
+    data_dir: &Path,
+    note_files: &mut BTreeMap<PathBuf, Option<String>>,
+) -> Result<(), String> {
+    let note_path = data_dir.join("notes").join(format!("{}.md", change.key));
+
+    match (&change.row, table.name) {
+        (Some(row), "notes") => {
+            let mut row = row.clone();
+            row.insert(
+                "content_path".to_string(),
+                JsonValue::from(note_path.to_string_lossy().into_owned()),
+            );
+            records::upsert_row(tx, table, &row)?;
+
+            if let Some(ref note) = change.note {
+                note_files.insert(note_path, Some(note.content.clone()));
+                set_note_tags(tx, &change.key, &note.tags).map_err(|e| e.to_string())?;
+                set_note_aliases(tx, &change.key, &note.aliases).map_err(|e| e.to_string())?;
+            }
+        }
+        (Some(row), _) => records::upsert_row(tx, table, row)?,
+        (None, "notes") => {
+            set_note_tags(tx, &change.key, &[]).map_err(|e| e.to_string())?;
+            set_note_aliases(tx, &change.key, &[]).map_err(|e| e.to_string())?;
+            records::delete_row(tx, table, &change.key)?;
+            note_files.insert(note_path, None);
+        }
+        (None, _) => records::delete_row(tx, table, &change.key)?,
+    }
+
+    Ok(())
+}
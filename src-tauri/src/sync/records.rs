@@ -0,0 +1,213 @@
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// 参与同步的表及其主键列
+///
+/// note_tags / note_aliases 作为 note 记录的一部分同步，不单独列出
+pub struct SyncTable {
+    pub name: &'static str,
+    pub key: &'static [&'static str],
+}
+
+pub const SYNC_TABLES: &[SyncTable] = &[
+    SyncTable {
+        name: "tree_nodes",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "notes",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "note_templates",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "tasks",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "time_entries",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "snippets",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "snippet_files",
+        key: &["id"],
+    },
+    SyncTable {
+        name: "node_resources",
+        key: &["node_id", "resource_id", "resource_type"],
+    },
+    SyncTable {
+        name: "daily_notes",
+        key: &["date"],
+    },
+];
+
+/// 复合主键拼接分隔符
+const KEY_SEPARATOR: char = '|';
+
+pub fn find_table(name: &str) -> Option<&'static SyncTable> {
+    SYNC_TABLES.iter().find(|t| t.name == name)
+}
+
+/// 触发器中计算记录键的 SQL 表达式（row 为 NEW / OLD）
+pub fn key_expr(table: &SyncTable, row: &str) -> String {
+    table
+        .key
+        .iter()
+        .map(|col| format!("{}.{}", row, col))
+        .collect::<Vec<_>>()
+        .join(&format!(" || '{}' || ", KEY_SEPARATOR))
+}
+
+fn key_values<'a>(table: &SyncTable, row_key: &'a str) -> Result<Vec<&'a str>, String> {
+    let values: Vec<&str> = if table.key.len() == 1 {
+        vec![row_key]
+    } else {
+        row_key.split(KEY_SEPARATOR).collect()
+    };
+    if values.len() != table.key.len() {
+        return Err(format!("invalid key for {}: {}", table.name, row_key));
+    }
+    Ok(values)
+}
+
+fn key_filter(table: &SyncTable) -> String {
+    table
+        .key
+        .iter()
+        .map(|col| format!("{} = ?", col))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// 读取一条记录为 JSON 对象（不存在时返回 None）
+pub fn read_row(
+    conn: &Connection,
+    table: &SyncTable,
+    row_key: &str,
+) -> Result<Option<JsonMap<String, JsonValue>>, String> {
+    let keys = key_values(table, row_key)?;
+    let sql = format!("SELECT * FROM {} WHERE {}", table.name, key_filter(table));
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    let mut rows = stmt
+        .query(params_from_iter(keys))
+        .map_err(|e| e.to_string())?;
+    let Some(row) = rows.next().map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+
+    let mut object = JsonMap::new();
+    for (i, column) in columns.into_iter().enumerate() {
+        let value = match row.get_ref(i).map_err(|e| e.to_string())? {
+            ValueRef::Null => JsonValue::Null,
+            ValueRef::Integer(v) => JsonValue::from(v),
+            ValueRef::Real(v) => JsonValue::from(v),
+            ValueRef::Text(v) | ValueRef::Blob(v) => {
+                JsonValue::from(String::from_utf8_lossy(v).into_owned())
+            }
+        };
+        object.insert(column, value);
+    }
+
+    Ok(Some(object))
+}
+
+/// 写入（插入或覆盖）一条记录；只写本地表中存在的列，兼容两端 schema 版本不一致
+pub fn upsert_row(
+    conn: &Connection,
+    table: &SyncTable,
+    row: &JsonMap<String, JsonValue>,
+) -> Result<(), String> {
+    let local_columns = table_columns(conn, table.name)?;
+    let columns: Vec<&String> = row
+        .keys()
+        .filter(|c| local_columns.iter().any(|l| l == *c))
+        .collect();
+    if table.key.iter().any(|k| !columns.iter().any(|c| c == k)) {
+        return Err(format!("missing key columns for {}", table.name));
+    }
+
+    let values: Vec<SqlValue> = columns
+        .iter()
+        .map(|c| match &row[c.as_str()] {
+            JsonValue::Null => SqlValue::Null,
+            JsonValue::Bool(b) => SqlValue::Integer(*b as i64),
+            JsonValue::Number(n) => match n.as_i64() {
+                Some(i) => SqlValue::Integer(i),
+                None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+            },
+            JsonValue::String(s) => SqlValue::Text(s.clone()),
+            other => SqlValue::Text(other.to_string()),
+        })
+        .collect();
+
+    let column_list = columns
+        .iter()
+        .map(|c| c.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|c| !table.key.contains(&c.as_str()))
+        .map(|c| format!("{0} = excluded.{0}", c))
+        .collect();
+    let conflict_action = if updates.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        format!("UPDATE SET {}", updates.join(", "))
+    };
+
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT({}) DO {}",
+        table.name,
+        column_list,
+        placeholders,
+        table.key.join(", "),
+        conflict_action
+    );
+    conn.execute(&sql, params_from_iter(values))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 删除一条记录
+pub fn delete_row(conn: &Connection, table: &SyncTable, row_key: &str) -> Result<(), String> {
+    let keys = key_values(table, row_key)?;
+    let sql = format!("DELETE FROM {} WHERE {}", table.name, key_filter(table));
+    conn.execute(&sql, params_from_iter(keys))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 表中全部记录的键（启用同步时生成完整快照）
+pub fn all_keys(conn: &Connection, table: &SyncTable) -> Result<Vec<String>, String> {
+    let sql = format!("SELECT {} FROM {}", key_expr(table, table.name), table.name);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 表的全部列名
+pub fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| r.get::<_, String>(1))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())
+}
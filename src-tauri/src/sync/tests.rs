@@ -0,0 +1,172 @@
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::transport::FolderTransport;
+use super::{enable, sync_now, SyncReport, Version};
+use crate::db::migrate;
+use crate::service::{notes, tree, Storage};
+
+/// 一台设备：内存数据库 + 独立的数据目录，共用同一个同步目录
+struct Device {
+    storage: Storage,
+    transport: FolderTransport,
+}
+
+impl Device {
+    fn new(root: &Path, name: &str) -> Self {
+        let dir = root.join(name);
+        crate::fs::create_dirs(&dir).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        migrate::run(&conn).unwrap();
+        let device = Self {
+            storage: Storage::new(conn, &dir),
+            transport: FolderTransport::new(root.join("sync")),
+        };
+        enable(&device.storage.conn, &device.transport, Some(name)).unwrap();
+        device
+    }
+
+    fn sync(&mut self) -> SyncReport {
+        let root = self.storage.root.clone();
+        sync_now(&mut self.storage.conn, &self.transport, &root).unwrap()
+    }
+
+    fn content(&self, note_id: &str) -> String {
+        let path: String = self
+            .storage
+            .conn
+            .query_row(
+                "SELECT content_path FROM notes WHERE id = ?",
+                [note_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// 标题中带 "conflict copy" 的 note 内容
+    fn conflict_copies(&self) -> Vec<String> {
+        let mut stmt = self
+            .storage
+            .conn
+            .prepare("SELECT id FROM notes WHERE title LIKE '%(conflict copy %'")
+            .unwrap();
+        let ids: Vec<String> = stmt
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        ids.iter().map(|id| self.content(id)).collect()
+    }
+}
+
+/// 两台设备共用的临时目录，测试结束时删除
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("noto-sync-{}", uuid::Uuid::new_v4())))
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn version(device_id: &str, clock: i64) -> Version {
+    Version {
+        device_id: device_id.to_string(),
+        clock,
+    }
+}
+
+#[test]
+fn version_wins_by_clock_then_device() {
+    assert!(version("a", 2).wins_over(&version("b", 1)));
+    assert!(!version("b", 1).wins_over(&version("a", 2)));
+    // 时钟相同时比较设备 id，两端判定一致
+    assert!(version("b", 1).wins_over(&version("a", 1)));
+    assert!(!version("a", 1).wins_over(&version("b", 1)));
+    assert!(!version("a", 1).wins_over(&version("a", 1)));
+}
+
+#[test]
+fn sequential_edits_sync_without_conflicts() {
+    let dir = TestDir::new();
+    let mut a = Device::new(&dir.0, "a");
+    let mut b = Device::new(&dir.0, "b");
+
+    let note = notes::create(&mut a.storage, "Shared", None, None).unwrap();
+    notes::update_content(&mut a.storage, &note.note_id, "# Shared\n\nfrom a\n").unwrap();
+    a.sync();
+    let report = b.sync();
+    assert!(report.imported > 0);
+    assert_eq!(report.conflicts, 0);
+    assert!(b.content(&note.note_id).ends_with("from a\n"));
+
+    notes::update_content(&mut b.storage, &note.note_id, "# Shared\n\nfrom b\n").unwrap();
+    b.sync();
+    let report = a.sync();
+    assert_eq!(report.conflicts, 0);
+    assert!(a.content(&note.note_id).ends_with("from b\n"));
+    assert!(a.conflict_copies().is_empty());
+}
+
+#[test]
+fn concurrent_edits_keep_a_conflict_copy() {
+    let dir = TestDir::new();
+    let mut a = Device::new(&dir.0, "a");
+    let mut b = Device::new(&dir.0, "b");
+
+    let note = notes::create(&mut a.storage, "Shared", None, None).unwrap();
+    a.sync();
+    b.sync();
+
+    // 两端基于同一版本各自修改
+    notes::update_content(&mut a.storage, &note.note_id, "# Shared\n\nedit a\n").unwrap();
+    notes::update_content(&mut b.storage, &note.note_id, "# Shared\n\nedit b\n").unwrap();
+    a.sync();
+    let report_b = b.sync();
+    let report_a = a.sync();
+    b.sync();
+    // note 与其树节点都是并发修改
+    assert!(report_a.conflicts + report_b.conflicts >= 1);
+
+    // 两端最终内容一致，落败的一方内容保存在冲突副本中（副本也会同步到另一端）
+    let winner = a.content(&note.note_id);
+    assert_eq!(winner, b.content(&note.note_id));
+    let loser = if winner.ends_with("edit a\n") {
+        "edit b\n"
+    } else {
+        "edit a\n"
+    };
+    for device in [&a, &b] {
+        let copies = device.conflict_copies();
+        assert_eq!(copies.len(), 1);
+        assert!(copies[0].ends_with(loser));
+    }
+}
+
+#[test]
+fn concurrent_edit_wins_over_delete() {
+    let dir = TestDir::new();
+    let mut a = Device::new(&dir.0, "a");
+    let mut b = Device::new(&dir.0, "b");
+
+    let note = notes::create(&mut a.storage, "Shared", None, None).unwrap();
+    a.sync();
+    b.sync();
+
+    tree::delete(&mut a.storage, &note.node_id).unwrap();
+    notes::update_content(&mut b.storage, &note.note_id, "# Shared\n\nkept\n").unwrap();
+    a.sync();
+    let report = b.sync();
+    assert!(report.conflicts >= 1);
+    assert!(b.content(&note.note_id).ends_with("kept\n"));
+
+    a.sync();
+    assert!(a.content(&note.note_id).ends_with("kept\n"));
+}
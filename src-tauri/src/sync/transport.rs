@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+/// 目录项
+#[derive(Clone, Debug)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// 文件版本标识（内容变化时随之变化），用于检测并发修改
+    pub etag: Option<String>,
+    pub size: Option<u64>,
+    /// 最后修改时间（Unix 时间戳，后端未提供时为 None）
+    pub modified: Option<i64>,
+}
+
+/// 条件写入的前置条件
+#[derive(Clone, Copy, Debug)]
+pub enum Precondition<'a> {
+    /// 无条件覆盖
+    Any,
+    /// 仅当文件不存在时写入
+    Absent,
+    /// 仅当文件仍是该版本时写入
+    Match(&'a str),
+}
+
+#[derive(Clone, Debug)]
+pub enum WriteOutcome {
+    /// 已写入，附带新版本标识（后端未返回时为 None）
+    Written(Option<String>),
+    /// 前置条件不满足：文件已被其他设备修改
+    Conflict,
+}
+
+/// 同步存储后端：以 `/` 分隔的相对路径读写文件
+///
+/// 每台设备只写入自己的 `changes/<device_id>/` 目录，不同设备之间不会写同一文件；
+/// 附件等共享文件通过版本标识（ETag）做条件写入
+pub trait Transport {
+    /// 列出目录下的文件与子目录（目录不存在时返回空列表）
+    fn list_entries(&self, dir: &str) -> Result<Vec<RemoteEntry>, String>;
+    /// 读取文件内容及其版本标识
+    fn fetch(&self, path: &str) -> Result<(Vec<u8>, Option<String>), String>;
+    /// 按前置条件写入文件（需保证其他设备不会读到写了一半的内容）
+    fn write_if(
+        &self,
+        path: &str,
+        data: &[u8],
+        precondition: Precondition,
+    ) -> Result<WriteOutcome, String>;
+
+    /// 列出目录下的名称
+    fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .list_entries(dir)?
+            .into_iter()
+            .map(|e| e.name)
+            .collect())
+    }
+
+    fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.fetch(path).map(|(data, _)| data)
+    }
+
+    fn write(&self, path: &str, data: &[u8]) -> Result<(), String> {
+        self.write_if(path, data, Precondition::Any).map(|_| ())
+    }
+
+    /// 清理后端内部不再被引用的数据（例如分块上传的分块），默认无需清理
+    fn collect_garbage(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// 本地目录（Syncthing / 网盘 / 共享盘挂载目录）
+pub struct FolderTransport {
+    root: PathBuf,
+}
+
+impl FolderTransport {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        path.split('/')
+            .filter(|p| !p.is_empty() && *p != "..")
+            .fold(self.root.clone(), |acc, p| acc.join(p))
+    }
+}
+
+/// 本地文件的版本标识：修改时间 + 大小
+fn file_etag(metadata: &fs::Metadata) -> Option<String> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("{:x}-{:x}", modified.as_nanos(), metadata.len()))
+}
+
+impl Transport for FolderTransport {
+    fn list_entries(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        let entries = match fs::read_dir(self.resolve(dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let mut result = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            // 写入中的临时文件
+            if name.ends_with(".tmp") {
+                continue;
+            }
+            let metadata = entry.metadata().map_err(|e| e.to_string())?;
+            result.push(RemoteEntry {
+                name,
+                is_dir: metadata.is_dir(),
+                etag: file_etag(&metadata),
+                size: metadata.is_file().then_some(metadata.len()),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64),
+            });
+        }
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(result)
+    }
+
+    fn fetch(&self, path: &str) -> Result<(Vec<u8>, Option<String>), String> {
+        let target = self.resolve(path);
+        let data = fs::read(&target).map_err(|e| e.to_string())?;
+        let etag = fs::metadata(&target).ok().and_then(|m| file_etag(&m));
+        Ok((data, etag))
+    }
+
+    fn write_if(
+        &self,
+        path: &str,
+        data: &[u8],
+        precondition: Precondition,
+    ) -> Result<WriteOutcome, String> {
+        let target = self.resolve(path);
+        let current = fs::metadata(&target).ok();
+        let satisfied = match precondition {
+            Precondition::Any => true,
+            Precondition::Absent => current.is_none(),
+            Precondition::Match(etag) => {
+                current.as_ref().and_then(file_etag).as_deref() == Some(etag)
+            }
+        };
+        if !satisfied {
+            return Ok(WriteOutcome::Conflict);
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        // 先写临时文件再重命名，避免同步工具传出半个文件
+        let tmp = target.with_extension("tmp");
+        fs::write(&tmp, data).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &target).map_err(|e| e.to_string())?;
+
+        let etag = fs::metadata(&target).ok().and_then(|m| file_etag(&m));
+        Ok(WriteOutcome::Written(etag))
+    }
+}
@@ -0,0 +1,765 @@
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::files::sha256_hex;
+use super::transport::{Precondition, RemoteEntry, Transport, WriteOutcome};
+use crate::util::percent_decode;
+
+/// 超过该大小的文件分块上传
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// 分块存放目录：`.chunks/<文件 sha256>/<序号>`，按内容寻址，中断后重新上传时跳过已有分块
+const CHUNKS_DIR: &str = ".chunks";
+/// 分块文件在原路径写入的清单开头
+const MANIFEST_PREFIX: &[u8] = br#"{"format":"noto-chunked""#;
+/// 不超过该大小的文件才可能是分块清单
+const MAX_MANIFEST_SIZE: u64 = 512;
+/// 该时长内有修改的分块目录不清理：其他设备可能正在上传，清单尚未写入
+const CHUNK_GRACE_SECS: i64 = 24 * 60 * 60;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getetag/><d:getcontentlength/><d:getlastmodified/></d:prop>
+</d:propfind>"#;
+
+static RESPONSE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[\w-]+:)?response\b[^>]*>(.*?)</(?:[\w-]+:)?response>")
+        .expect("invalid regex")
+});
+static HREF: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[\w-]+:)?href\b[^>]*>(.*?)</(?:[\w-]+:)?href>").expect("invalid regex")
+});
+static ETAG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[\w-]+:)?getetag\b[^>]*>(.*?)</(?:[\w-]+:)?getetag>")
+        .expect("invalid regex")
+});
+static CONTENT_LENGTH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[\w-]+:)?getcontentlength\b[^>]*>\s*(\d+)\s*</").expect("invalid regex")
+});
+static LAST_MODIFIED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[\w-]+:)?getlastmodified\b[^>]*>(.*?)</(?:[\w-]+:)?getlastmodified>")
+        .expect("invalid regex")
+});
+static COLLECTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").expect("invalid regex"));
+
+/// 大文件清单：内容按序存放在 `.chunks/<sha256>/` 下
+#[derive(Serialize, Deserialize)]
+struct ChunkManifest {
+    format: String,
+    sha256: String,
+    size: u64,
+    chunk_size: u64,
+    chunks: usize,
+}
+
+/// WebDAV 服务器（Nextcloud / ownCloud / 坚果云等），路径相对于配置的目录 URL
+pub struct WebDavTransport {
+    /// 以 `/` 结尾
+    base_url: String,
+    authorization: Option<String>,
+    agent: ureq::Agent,
+    /// 已确认存在的目录，避免重复 MKCOL
+    collections: Mutex<HashSet<String>>,
+}
+
+impl WebDavTransport {
+    pub fn new(url: &str, username: Option<&str>, password: Option<&str>) -> Result<Self, String> {
+        let url = url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err("WebDAV url must start with http:// or https://".to_string());
+        }
+
+        let authorization = username.filter(|u| !u.is_empty()).map(|user| {
+            let credentials = format!("{}:{}", user, password.unwrap_or_default());
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        });
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout(Duration::from_secs(120))
+            .build();
+
+        Ok(Self {
+            base_url: format!("{}/", url.trim_end_matches('/')),
+            authorization,
+            agent,
+            collections: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        let encoded: Vec<String> = segments(path).map(encode_segment).collect();
+        format!("{}{}", self.base_url, encoded.join("/"))
+    }
+
+    /// 目录 URL（以 `/` 结尾，避免服务器重定向）
+    fn collection_url(&self, dir: &str) -> String {
+        let url = self.url(dir);
+        if url.ends_with('/') {
+            url
+        } else {
+            format!("{}/", url)
+        }
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// 逐级创建目录（已存在时服务器返回 405）
+    fn ensure_collection(&self, dir: &str) -> Result<(), String> {
+        let mut current = String::new();
+        for segment in segments(dir) {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(segment);
+
+            let mut created = self.collections.lock().map_err(|e| e.to_string())?;
+            if created.contains(&current) {
+                continue;
+            }
+            let url = self.collection_url(&current);
+            let response = send(self.request("MKCOL", &url), None)?;
+            match response.status() {
+                200..=299 | 405 => {
+                    created.insert(current.clone());
+                }
+                _ => return Err(status_error("MKCOL", &current, response)),
+            }
+        }
+        Ok(())
+    }
+
+    fn ensure_parent(&self, path: &str) -> Result<(), String> {
+        match path.trim_matches('/').rsplit_once('/') {
+            Some((parent, _)) => self.ensure_collection(parent),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, path: &str) -> Result<(Vec<u8>, Option<String>), String> {
+        let response = send(self.request("GET", &self.url(path)), None)?;
+        if response.status() != 200 {
+            return Err(status_error("GET", path, response));
+        }
+        let etag = response.header("ETag").map(str::to_string);
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut data)
+            .map_err(|e| e.to_string())?;
+        Ok((data, etag))
+    }
+
+    /// 分块上传大文件并返回清单；已完整上传的分块直接跳过（断点续传）
+    fn upload_chunks(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let sha256 = sha256_hex(data);
+        let dir = format!("{}/{}", CHUNKS_DIR, sha256);
+        self.ensure_collection(&dir)?;
+
+        let uploaded: HashMap<String, Option<u64>> = self
+            .list_entries(&dir)?
+            .into_iter()
+            .map(|e| (e.name, e.size))
+            .collect();
+
+        let mut chunks = 0;
+        for (i, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+            chunks += 1;
+            let name = format!("{:06}", i);
+            if uploaded.get(&name) == Some(&Some(chunk.len() as u64)) {
+                continue;
+            }
+            let path = format!("{}/{}", dir, name);
+            let response = send(self.request("PUT", &self.url(&path)), Some(chunk))?;
+            if !(200..=299).contains(&response.status()) {
+                return Err(status_error("PUT", &path, response));
+            }
+        }
+
+        let manifest = ChunkManifest {
+            format: "noto-chunked".to_string(),
+            sha256,
+            size: data.len() as u64,
+            chunk_size: CHUNK_SIZE as u64,
+            chunks,
+        };
+        serde_json::to_vec(&manifest).map_err(|e| e.to_string())
+    }
+
+    fn download_chunks(&self, manifest: &ChunkManifest) -> Result<Vec<u8>, String> {
+        let mut data = Vec::with_capacity(manifest.size as usize);
+        for i in 0..manifest.chunks {
+            let (chunk, _) = self.get(&format!("{}/{}/{:06}", CHUNKS_DIR, manifest.sha256, i))?;
+            data.extend_from_slice(&chunk);
+        }
+        if sha256_hex(&data) != manifest.sha256 {
+            return Err("chunked file is incomplete or corrupted".to_string());
+        }
+        Ok(data)
+    }
+
+    /// 递归查找目录下的分块清单，记录其引用的分块目录（`.chunks/` 本身除外）
+    fn collect_manifests(&self, dir: &str, referenced: &mut HashSet<String>) -> Result<(), String> {
+        for entry in self.list_entries(dir)? {
+            let path = if dir.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", dir, entry.name)
+            };
+            if entry.is_dir {
+                if path != CHUNKS_DIR {
+                    self.collect_manifests(&path, referenced)?;
+                }
+                continue;
+            }
+            if entry.size.is_some_and(|size| size > MAX_MANIFEST_SIZE) {
+                continue;
+            }
+            let (data, _) = self.get(&path)?;
+            if !data.starts_with(MANIFEST_PREFIX) {
+                continue;
+            }
+            if let Ok(manifest) = serde_json::from_slice::<ChunkManifest>(&data) {
+                referenced.insert(manifest.sha256);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Transport for WebDavTransport {
+    fn list_entries(&self, dir: &str) -> Result<Vec<RemoteEntry>, String> {
+        let url = self.collection_url(dir);
+        let request = self
+            .request("PROPFIND", &url)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8");
+        let response = send(request, Some(PROPFIND_BODY.as_bytes()))?;
+        match response.status() {
+            207 => {}
+            404 => return Ok(Vec::new()),
+            _ => return Err(status_error("PROPFIND", dir, response)),
+        }
+
+        let mut body = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut body)
+            .map_err(|e| e.to_string())?;
+
+        let own_path = percent_decode(url_path(&url));
+        let own_path = own_path.trim_end_matches('/');
+        let mut entries = Vec::new();
+        for block in RESPONSE.captures_iter(&body) {
+            let block = &block[1];
+            let Some(href) = HREF.captures(block) else {
+                continue;
+            };
+            let href = percent_decode(url_path(&xml_unescape(href[1].trim())));
+            let href = href.trim_end_matches('/');
+            // 响应中包含目录自身
+            if href == own_path {
+                continue;
+            }
+            let Some(name) = href.rsplit('/').next().filter(|n| !n.is_empty()) else {
+                continue;
+            };
+
+            entries.push(RemoteEntry {
+                name: name.to_string(),
+                is_dir: COLLECTION.is_match(block),
+                etag: ETAG
+                    .captures(block)
+                    .map(|c| xml_unescape(c[1].trim()))
+                    .filter(|e| !e.is_empty()),
+                size: CONTENT_LENGTH
+                    .captures(block)
+                    .and_then(|c| c[1].parse().ok()),
+                modified: LAST_MODIFIED
+                    .captures(block)
+                    .and_then(|c| chrono::DateTime::parse_from_rfc2822(c[1].trim()).ok())
+                    .map(|t| t.timestamp()),
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    fn fetch(&self, path: &str) -> Result<(Vec<u8>, Option<String>), String> {
+        let (data, etag) = self.get(path)?;
+        if !data.starts_with(MANIFEST_PREFIX) {
+            return Ok((data, etag));
+        }
+        let manifest: ChunkManifest = serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+        Ok((self.download_chunks(&manifest)?, etag))
+    }
+
+    fn write_if(
+        &self,
+        path: &str,
+        data: &[u8],
+        precondition: Precondition,
+    ) -> Result<WriteOutcome, String> {
+        self.ensure_parent(path)?;
+
+        // 恰好以清单开头的小文件也按分块写入，避免读取时被误认为清单
+        let body: Cow<[u8]> = if data.len() > CHUNK_SIZE || data.starts_with(MANIFEST_PREFIX) {
+            Cow::Owned(self.upload_chunks(data)?)
+        } else {
+            Cow::Borrowed(data)
+        };
+
+        let request = self.request("PUT", &self.url(path));
+        let request = match precondition {
+            Precondition::Any => request,
+            Precondition::Absent => request.set("If-None-Match", "*"),
+            Precondition::Match(etag) => request.set("If-Match", etag),
+        };
+        let response = send(request, Some(&body))?;
+        match response.status() {
+            200..=299 => Ok(WriteOutcome::Written(
+                response.header("ETag").map(str::to_string),
+            )),
+            412 => Ok(WriteOutcome::Conflict),
+            _ => Err(status_error("PUT", path, response)),
+        }
+    }
+
+    /// 删除不再被任何清单引用的 `.chunks/<sha256>/` 目录
+    ///
+    /// 文件被覆盖或条件写入冲突后，原先的分块不再被引用
+    fn collect_garbage(&self) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp();
+        let candidates: Vec<RemoteEntry> = self
+            .list_entries(CHUNKS_DIR)?
+            .into_iter()
+            .filter(|e| e.is_dir)
+            .filter(|e| e.modified.is_none_or(|m| now - m > CHUNK_GRACE_SECS))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(());
+        }
+
+        let mut referenced = HashSet::new();
+        self.collect_manifests("", &mut referenced)?;
+        for entry in candidates {
+            if referenced.contains(&entry.name) {
+                continue;
+            }
+            let dir = format!("{}/{}", CHUNKS_DIR, entry.name);
+            let response = send(self.request("DELETE", &self.collection_url(&dir)), None)?;
+            match response.status() {
+                200..=299 | 404 => {
+                    self.collections
+                        .lock()
+                        .map_err(|e| e.to_string())?
+                        .remove(&dir);
+                }
+                _ => return Err(status_error("DELETE", &dir, response)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 发送请求；HTTP 错误状态同样作为响应返回，由调用方判断
+fn send(request: ureq::Request, body: Option<&[u8]>) -> Result<ureq::Response, String> {
+    let result = match body {
+        Some(body) => request.send_bytes(body),
+        None => request.call(),
+    };
+    match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => Ok(response),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn status_error(method: &str, path: &str, response: ureq::Response) -> String {
+    format!(
+        "WebDAV {} {} failed: {} {}",
+        method,
+        path,
+        response.status(),
+        response.status_text()
+    )
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|p| !p.is_empty() && *p != "..")
+}
+
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// href 可能是完整 URL 或绝对路径，统一取路径部分
+fn url_path(url: &str) -> &str {
+    match url.find("://") {
+        Some(i) => {
+            let rest = &url[i + 3..];
+            rest.find('/').map(|j| &rest[j..]).unwrap_or("/")
+        }
+        None => url,
+    }
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use tiny_http::{Header, Request, Response, Server};
+
+    /// 内存中的 WebDAV 服务器状态
+    #[derive(Default)]
+    struct State {
+        /// 相对路径 → 内容与 ETag；目录以 `/` 结尾
+        entries: BTreeMap<String, (Vec<u8>, String)>,
+        next_etag: u64,
+        /// 每个路径收到的 PUT 次数
+        puts: HashMap<String, usize>,
+    }
+
+    impl State {
+        fn insert(&mut self, path: &str, data: Vec<u8>) -> String {
+            self.next_etag += 1;
+            let etag = format!("\"{}\"", self.next_etag);
+            self.entries.insert(path.to_string(), (data, etag.clone()));
+            etag
+        }
+
+        fn exists_dir(&self, dir: &str) -> bool {
+            dir.is_empty() || self.entries.contains_key(&format!("{}/", dir))
+        }
+
+        fn parent_exists(&self, path: &str) -> bool {
+            match path.trim_end_matches('/').rsplit_once('/') {
+                Some((parent, _)) => self.exists_dir(parent),
+                None => true,
+            }
+        }
+    }
+
+    /// 只实现同步用到的 PROPFIND / MKCOL / GET / PUT / DELETE
+    struct FakeServer {
+        server: Arc<Server>,
+        state: Arc<Mutex<State>>,
+        url: String,
+    }
+
+    impl FakeServer {
+        fn start() -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+            let port = server.server_addr().to_ip().unwrap().port();
+            let origin = format!("http://127.0.0.1:{}", port);
+            let state = Arc::new(Mutex::new(State::default()));
+
+            let (worker, worker_state, worker_origin) =
+                (server.clone(), state.clone(), origin.clone());
+            std::thread::spawn(move || {
+                for request in worker.incoming_requests() {
+                    handle(&worker_state, &worker_origin, request);
+                }
+            });
+
+            Self {
+                server,
+                state,
+                url: format!("{}/dav", origin),
+            }
+        }
+
+        fn transport(&self) -> WebDavTransport {
+            WebDavTransport::new(&self.url, Some("user"), Some("secret")).unwrap()
+        }
+
+        fn stored(&self, path: &str) -> Option<Vec<u8>> {
+            let state = self.state.lock().unwrap();
+            state.entries.get(path).map(|(data, _)| data.clone())
+        }
+
+        fn puts(&self, path: &str) -> usize {
+            let state = self.state.lock().unwrap();
+            state.puts.get(path).copied().unwrap_or(0)
+        }
+    }
+
+    impl Drop for FakeServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+        }
+    }
+
+    fn header(request: &Request, name: &'static str) -> Option<String> {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().to_string())
+    }
+
+    fn handle(state: &Mutex<State>, origin: &str, mut request: Request) {
+        let mut body = Vec::new();
+        let _ = request.as_reader().read_to_end(&mut body);
+        let url = request.url().to_string();
+        let path = percent_decode(url.strip_prefix("/dav").unwrap_or(&url));
+        let path = path.trim_matches('/').to_string();
+        let mut state = state.lock().unwrap();
+
+        let (status, data, etag) = match request.method().as_str() {
+            "PROPFIND" => {
+                if !state.exists_dir(&path) {
+                    (404, Vec::new(), None)
+                } else {
+                    (207, propfind(&state, origin, &path).into_bytes(), None)
+                }
+            }
+            "MKCOL" => {
+                if state.exists_dir(&path) {
+                    (405, Vec::new(), None)
+                } else if !state.parent_exists(&path) {
+                    (409, Vec::new(), None)
+                } else {
+                    state.insert(&format!("{}/", path), Vec::new());
+                    (201, Vec::new(), None)
+                }
+            }
+            "GET" => match state.entries.get(&path) {
+                Some((data, etag)) => (200, data.clone(), Some(etag.clone())),
+                None => (404, Vec::new(), None),
+            },
+            "PUT" => {
+                *state.puts.entry(path.clone()).or_default() += 1;
+                let current = state.entries.get(&path).map(|(_, etag)| etag.clone());
+                let conflict = match (
+                    header(&request, "If-None-Match"),
+                    header(&request, "If-Match"),
+                ) {
+                    (Some(_), _) => current.is_some(),
+                    (_, Some(expected)) => current.as_deref() != Some(expected.as_str()),
+                    _ => false,
+                };
+                if conflict {
+                    (412, Vec::new(), None)
+                } else if !state.parent_exists(&path) {
+                    (409, Vec::new(), None)
+                } else {
+                    let etag = state.insert(&path, body);
+                    (201, Vec::new(), Some(etag))
+                }
+            }
+            "DELETE" => {
+                let dir = format!("{}/", path);
+                let before = state.entries.len();
+                state
+                    .entries
+                    .retain(|key, _| *key != path && !key.starts_with(&dir));
+                let status = if state.entries.len() < before {
+                    204
+                } else {
+                    404
+                };
+                (status, Vec::new(), None)
+            }
+            _ => (405, Vec::new(), None),
+        };
+
+        let mut response = Response::from_data(data).with_status_code(status);
+        if let Some(etag) = etag {
+            response.add_header(Header::from_bytes(&b"ETag"[..], etag.as_bytes()).unwrap());
+        }
+        let _ = request.respond(response);
+    }
+
+    /// Depth: 1 的多状态响应：目录自身 + 直接子项，href 为编码后的完整 URL
+    fn propfind(state: &State, origin: &str, dir: &str) -> String {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir)
+        };
+        let href = |path: &str| {
+            let encoded: Vec<String> = path.split('/').map(encode_segment).collect();
+            format!("{}/dav/{}", origin, encoded.join("/"))
+        };
+
+        let mut xml = String::from(r#"<?xml version="1.0"?><D:multistatus xmlns:D="DAV:">"#);
+        xml.push_str(&format!(
+            "<D:response><D:href>{}</D:href><D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat></D:response>",
+            href(&prefix)
+        ));
+        for (key, (data, etag)) in state.entries.range(prefix.clone()..) {
+            let Some(rest) = key.strip_prefix(&prefix) else {
+                break;
+            };
+            let name = rest.trim_end_matches('/');
+            if name.is_empty() || name.contains('/') {
+                continue;
+            }
+            let props = if key.ends_with('/') {
+                "<D:resourcetype><D:collection/></D:resourcetype>".to_string()
+            } else {
+                format!(
+                    "<D:resourcetype/><D:getetag>{}</D:getetag><D:getcontentlength>{}</D:getcontentlength>",
+                    etag.replace('"', "&quot;"),
+                    data.len()
+                )
+            };
+            xml.push_str(&format!(
+                "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop></D:propstat></D:response>",
+                href(key),
+                props
+            ));
+        }
+        xml.push_str("</D:multistatus>");
+        xml
+    }
+
+    fn big_file(seed: u8) -> Vec<u8> {
+        (0..CHUNK_SIZE * 2 + 100)
+            .map(|i| (i % 251) as u8 ^ seed)
+            .collect()
+    }
+
+    #[test]
+    fn propfind_lists_entries() {
+        let server = FakeServer::start();
+        let transport = server.transport();
+
+        transport.write("notes dir/a b.txt", b"hello").unwrap();
+        transport.write("notes dir/sub/c.txt", b"c").unwrap();
+
+        let entries = transport.list_entries("notes dir").unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a b.txt", "sub"]);
+        assert!(!entries[0].is_dir);
+        assert_eq!(entries[0].size, Some(5));
+        let (_, etag) = transport.fetch("notes dir/a b.txt").unwrap();
+        assert_eq!(entries[0].etag, etag);
+        assert!(entries[1].is_dir);
+
+        assert_eq!(transport.list("").unwrap(), ["notes dir"]);
+        assert!(transport.list_entries("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn preconditions_report_conflicts() {
+        let server = FakeServer::start();
+        let transport = server.transport();
+
+        let WriteOutcome::Written(Some(first)) = transport
+            .write_if("file.txt", b"v1", Precondition::Absent)
+            .unwrap()
+        else {
+            panic!("first write should succeed");
+        };
+        assert!(matches!(
+            transport.write_if("file.txt", b"v1", Precondition::Absent),
+            Ok(WriteOutcome::Conflict)
+        ));
+        assert!(matches!(
+            transport.write_if("file.txt", b"v2", Precondition::Match(&first)),
+            Ok(WriteOutcome::Written(Some(_)))
+        ));
+        // 基于旧版本的写入被拒绝，内容保持不变
+        assert!(matches!(
+            transport.write_if("file.txt", b"v3", Precondition::Match(&first)),
+            Ok(WriteOutcome::Conflict)
+        ));
+        assert_eq!(transport.read("file.txt").unwrap(), b"v2");
+    }
+
+    #[test]
+    fn chunked_upload_resumes() {
+        let server = FakeServer::start();
+        let transport = server.transport();
+        let data = big_file(0);
+        let dir = format!("{}/{}", CHUNKS_DIR, sha256_hex(&data));
+
+        // 上次上传中断：第一个分块已完整，第二个只传了一半
+        {
+            let mut state = server.state.lock().unwrap();
+            state.insert(&format!("{}/", CHUNKS_DIR), Vec::new());
+            state.insert(&format!("{}/", dir), Vec::new());
+            state.insert(&format!("{}/000000", dir), data[..CHUNK_SIZE].to_vec());
+            state.insert(
+                &format!("{}/000001", dir),
+                data[CHUNK_SIZE..CHUNK_SIZE + 10].to_vec(),
+            );
+        }
+
+        transport.write("attachments/big.bin", &data).unwrap();
+        assert_eq!(server.puts(&format!("{}/000000", dir)), 0);
+        assert_eq!(server.puts(&format!("{}/000001", dir)), 1);
+        assert_eq!(server.puts(&format!("{}/000002", dir)), 1);
+
+        let stored = server.stored("attachments/big.bin").unwrap();
+        assert!(stored.starts_with(MANIFEST_PREFIX));
+        assert_eq!(transport.read("attachments/big.bin").unwrap(), data);
+    }
+
+    #[test]
+    fn content_starting_with_manifest_prefix_round_trips() {
+        let server = FakeServer::start();
+        let transport = server.transport();
+        let data = br#"{"format":"noto-chunked","note":"not a manifest"}"#;
+
+        transport.write("tricky.json", data).unwrap();
+        // 原路径保存的是清单，内容放在分块中
+        assert_ne!(server.stored("tricky.json").unwrap(), data);
+        assert_eq!(transport.read("tricky.json").unwrap(), data);
+    }
+
+    #[test]
+    fn unreferenced_chunks_are_collected() {
+        let server = FakeServer::start();
+        let transport = server.transport();
+        let (old, new) = (big_file(1), big_file(2));
+
+        transport.write("attachments/big.bin", &old).unwrap();
+        transport.write("attachments/big.bin", &new).unwrap();
+        // 清单不足 MAX_MANIFEST_SIZE 才会被读取
+        assert!(server.stored("attachments/big.bin").unwrap().len() as u64 <= MAX_MANIFEST_SIZE);
+        assert_eq!(transport.list(CHUNKS_DIR).unwrap().len(), 2);
+
+        transport.collect_garbage().unwrap();
+        assert_eq!(transport.list(CHUNKS_DIR).unwrap(), [sha256_hex(&new)]);
+        assert_eq!(transport.read("attachments/big.bin").unwrap(), new);
+
+        // 被清理的分块目录可以重新上传
+        transport.write("attachments/copy.bin", &old).unwrap();
+        assert_eq!(transport.read("attachments/copy.bin").unwrap(), old);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod note;
+pub mod snippet;
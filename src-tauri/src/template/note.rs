@@ -0,0 +1,63 @@
+use chrono::Local;
+
+/// 实例化 note 模板时可用的变量
+pub struct NoteTemplateContext<'a> {
+    pub title: &'a str,
+    /// 所属项目名（没有时替换为空字符串）
+    pub project: Option<&'a str>,
+}
+
+/// 模板实例化结果
+pub struct RenderedNote {
+    pub content: String,
+    /// 第一个 `{{cursor}}` 的位置（UTF-16 偏移，与前端编辑器一致）
+    pub cursor: Option<usize>,
+}
+
+/// 实例化 note 模板
+///
+/// - `{{title}}` / `{{project}}` / `{{date}}` / `{{time}}` / `{{datetime}}`：替换为对应值
+/// - `{{cursor}}`：移除，只记录第一个出现的位置
+/// - 其他 `{{...}}` 原样保留
+pub fn render(template: &str, ctx: &NoteTemplateContext) -> RenderedNote {
+    let now = Local::now();
+    let mut content = String::with_capacity(template.len());
+    let mut cursor = None;
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        content.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let Some(end) = after.find("}}") else {
+            content.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let name = after[..end].trim();
+        let value = match name {
+            "title" => Some(ctx.title.to_string()),
+            "project" => Some(ctx.project.unwrap_or_default().to_string()),
+            "date" => Some(now.format("%Y-%m-%d").to_string()),
+            "time" => Some(now.format("%H:%M").to_string()),
+            "datetime" => Some(now.format("%Y-%m-%d %H:%M").to_string()),
+            "cursor" => {
+                if cursor.is_none() {
+                    cursor = Some(content.encode_utf16().count());
+                }
+                Some(String::new())
+            }
+            _ => None,
+        };
+
+        match value {
+            Some(v) => content.push_str(&v),
+            None => content.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    content.push_str(rest);
+
+    RenderedNote { content, cursor }
+}
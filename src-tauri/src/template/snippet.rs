@@ -0,0 +1,176 @@
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 内置变量（未由调用方提供值时自动填充）
+///
+/// clipboard 需要读取系统剪贴板，由调用方（command 层）解析
+pub const BUILTIN_VARIABLES: &[&str] =
+    &["date", "time", "datetime", "timestamp", "uuid", "clipboard"];
+
+/// snippet 中的一个占位变量：`${name}` / `${name:default}`
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub default: Option<String>,
+    pub builtin: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Variable {
+        name: String,
+        default: Option<String>,
+    },
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// 解析模板内容
+///
+/// - `${name}` / `${name:default}`：变量（name 由字母、数字、下划线组成）
+/// - `\$`：转义为字面量 `$`；默认值内可用 `\}` 表示 `}`
+/// - 不合法的 `${...}` 原样保留为文本
+fn parse(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let chars: Vec<char> = content.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\\' && chars.get(i + 1) == Some(&'$') {
+            text.push('$');
+            i += 2;
+            continue;
+        }
+
+        if c == '$' && chars.get(i + 1) == Some(&'{') {
+            if let Some((name, default, next)) = parse_variable(&chars, i + 2) {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Variable { name, default });
+                i = next;
+                continue;
+            }
+        }
+
+        text.push(c);
+        i += 1;
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    segments
+}
+
+/// 从 `${` 之后开始解析，返回 (name, default, 结束位置)
+fn parse_variable(chars: &[char], start: usize) -> Option<(String, Option<String>, usize)> {
+    let mut i = start;
+    let mut name = String::new();
+    while i < chars.len() && is_name_char(chars[i]) {
+        name.push(chars[i]);
+        i += 1;
+    }
+    if name.is_empty() {
+        return None;
+    }
+
+    match chars.get(i)? {
+        '}' => Some((name, None, i + 1)),
+        ':' => {
+            i += 1;
+            let mut default = String::new();
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' if chars.get(i + 1) == Some(&'}') => {
+                        default.push('}');
+                        i += 2;
+                    }
+                    '}' => return Some((name, Some(default), i + 1)),
+                    c => {
+                        default.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// 列出模板中的变量（按首次出现顺序去重，默认值取首个非空）
+pub fn variables(content: &str) -> Vec<TemplateVariable> {
+    let mut vars: Vec<TemplateVariable> = Vec::new();
+
+    for segment in parse(content) {
+        let Segment::Variable { name, default } = segment else {
+            continue;
+        };
+        match vars.iter_mut().find(|v| v.name == name) {
+            Some(existing) => {
+                if existing.default.is_none() {
+                    existing.default = default;
+                }
+            }
+            None => vars.push(TemplateVariable {
+                builtin: BUILTIN_VARIABLES.contains(&name.as_str()),
+                name,
+                default,
+            }),
+        }
+    }
+
+    vars
+}
+
+/// 内置变量的值（clipboard 除外）
+pub fn builtin_value(name: &str) -> Option<String> {
+    let now = Local::now();
+    match name {
+        "date" => Some(now.format("%Y-%m-%d").to_string()),
+        "time" => Some(now.format("%H:%M:%S").to_string()),
+        "datetime" => Some(now.format("%Y-%m-%d %H:%M:%S").to_string()),
+        "timestamp" => Some(now.timestamp().to_string()),
+        "uuid" => Some(Uuid::new_v4().to_string()),
+        _ => None,
+    }
+}
+
+/// 渲染模板
+///
+/// 变量取值顺序：resolve(name) → 默认值；两者都没有时返回错误。
+/// 同名变量在一次渲染中只解析一次（多处 `${uuid}` 得到同一个值）
+pub fn render<F>(content: &str, mut resolve: F) -> Result<String, String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(content.len());
+    let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+
+    for segment in parse(content) {
+        match segment {
+            Segment::Text(text) => out.push_str(&text),
+            Segment::Variable { name, default } => {
+                let value = resolved
+                    .entry(name.clone())
+                    .or_insert_with(|| resolve(&name))
+                    .clone()
+                    .or(default)
+                    .ok_or_else(|| format!("missing value for variable: {}", name))?;
+                out.push_str(&value);
+            }
+        }
+    }
+
+    Ok(out)
+}
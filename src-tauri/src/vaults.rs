@@ -0,0 +1,248 @@
+//! 库注册表：在多个库（例如工作库与个人库）之间切换
+//!
+//! 注册表保存在系统配置目录下的 `vaults.json`，当前打开的库即 [`crate::app::data_dir`]；
+//! 切换库时替换数据库与数据目录，并通过 `vault-changed` 事件通知前端重新加载
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::vault;
+use crate::location::{self, Source};
+
+/// 切换库后发给前端的事件，参数为新的当前库
+pub const VAULT_CHANGED_EVENT: &str = "vault-changed";
+/// 注册表文件
+const REGISTRY_FILE: &str = "vaults.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    id: String,
+    name: String,
+    path: PathBuf,
+    created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Registry {
+    vaults: Vec<Entry>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct VaultInfo {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    /// 是否为当前打开的库
+    pub active: bool,
+    /// 目录是否存在（例如 U 盘未插入时为 false）
+    pub available: bool,
+    /// 是否为加密库且尚未输入密钥（非当前库只要加密即视为锁定）
+    pub locked: bool,
+}
+
+impl Entry {
+    fn info(&self, current: &Path) -> VaultInfo {
+        let active = self.path == current;
+        let db_path = self.path.join("db.sqlite");
+        VaultInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            path: self.path.to_string_lossy().to_string(),
+            active,
+            available: self.path.is_dir(),
+            locked: if active {
+                vault::is_locked(&db_path)
+            } else {
+                vault::is_encrypted(&db_path)
+            },
+        }
+    }
+}
+
+impl Registry {
+    /// 读取注册表；当前库不在其中时自动登记
+    ///
+    /// 文件损坏时返回错误，避免以空注册表覆盖已登记的库
+    fn load() -> Result<Self, String> {
+        let content = match location::config_file(REGISTRY_FILE).map(fs::read_to_string) {
+            Some(Ok(content)) => Some(content),
+            Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("cannot read {}: {}", REGISTRY_FILE, e));
+            }
+            _ => None,
+        };
+        let mut registry: Registry = match content {
+            Some(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("{} is invalid: {}", REGISTRY_FILE, e))?,
+            None => Registry::default(),
+        };
+
+        let current = crate::app::data_dir();
+        if !registry.vaults.iter().any(|v| v.path == current) {
+            let name = match crate::app::data_dir_source() {
+                Source::Default => "Default".to_string(),
+                _ => current
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Vault".to_string()),
+            };
+            registry.vaults.push(Entry {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                path: current,
+                created_at: chrono::Utc::now().timestamp(),
+            });
+            registry.save()?;
+        }
+        Ok(registry)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        location::write_config_file(REGISTRY_FILE, &content)
+    }
+
+    fn find_mut(&mut self, id: &str) -> Result<&mut Entry, String> {
+        self.vaults
+            .iter_mut()
+            .find(|v| v.id == id)
+            .ok_or_else(|| "vault not found".to_string())
+    }
+}
+
+fn require_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("vault name is empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 列出已登记的库
+pub fn list() -> Result<Vec<VaultInfo>, String> {
+    let registry = Registry::load()?;
+    let current = crate::app::data_dir();
+    Ok(registry.vaults.iter().map(|v| v.info(&current)).collect())
+}
+
+/// 登记一个库：目录不存在或为空时新建，已是库目录（含 db.sqlite）时直接登记，
+/// 其他非空目录拒绝
+pub fn create(name: &str, path: &str) -> Result<VaultInfo, String> {
+    let name = require_name(name)?;
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return Err("vault path must be an absolute path".to_string());
+    }
+
+    let mut registry = Registry::load()?;
+    if registry.vaults.iter().any(|v| v.path == path) {
+        return Err("this directory is already a registered vault".to_string());
+    }
+    if path.exists() && !is_empty_or_vault(&path)? {
+        return Err("the directory is not empty and is not a noto vault".to_string());
+    }
+    crate::fs::create_dirs(&path).map_err(|e| e.to_string())?;
+
+    let entry = Entry {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        path,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    let info = entry.info(&crate::app::data_dir());
+    registry.vaults.push(entry);
+    registry.save()?;
+    Ok(info)
+}
+
+fn is_empty_or_vault(path: &Path) -> Result<bool, String> {
+    if !path.is_dir() {
+        return Ok(false);
+    }
+    let empty = fs::read_dir(path)
+        .map_err(|e| e.to_string())?
+        .next()
+        .is_none();
+    Ok(empty || path.join("db.sqlite").is_file())
+}
+
+/// 重命名库（只修改显示名称，目录不变）
+pub fn rename(id: &str, name: &str) -> Result<(), String> {
+    let name = require_name(name)?;
+    let mut registry = Registry::load()?;
+    registry.find_mut(id)?.name = name;
+    registry.save()
+}
+
+/// 从注册表中移除库（不删除文件）；当前打开的库不能关闭
+pub fn close(id: &str) -> Result<(), String> {
+    let mut registry = Registry::load()?;
+    let entry = registry.find_mut(id)?;
+    if entry.path == crate::app::data_dir() {
+        return Err("cannot close the open vault: open another vault first".to_string());
+    }
+    registry.vaults.retain(|v| v.id != id);
+    registry.save()
+}
+
+/// 库被移动到新位置后更新注册表
+pub fn relocate(from: &Path, to: &Path) -> Result<(), String> {
+    let mut registry = Registry::load()?;
+    for entry in registry.vaults.iter_mut().filter(|v| v.path == from) {
+        entry.path = to.to_path_buf();
+    }
+    registry.save()
+}
+
+/// 运行中切换到另一个库
+///
+/// 1. 提交旧库等待中的版本历史，等待旧库上正在进行的操作结束并独占其库锁，
+///    停止本机 API，清除库密钥与已解锁的 note
+/// 2. 替换数据库与数据目录，并记为下次启动打开的库，之后释放旧库的锁
+/// 3. 新库未加密时完成迁移并按其设置启动 API；加密库由前端解锁后完成
+pub fn open(app: &AppHandle, id: &str) -> Result<VaultInfo, String> {
+    let registry = Registry::load()?;
+    let entry = registry
+        .vaults
+        .iter()
+        .find(|v| v.id == id)
+        .ok_or_else(|| "vault not found".to_string())?;
+    if entry.path == crate::app::data_dir() {
+        return Ok(entry.info(&entry.path));
+    }
+    // 目录不存在时（例如 U 盘未插入）不新建空库
+    if !entry.path.is_dir() {
+        return Err(format!(
+            "vault directory is not available: {}",
+            entry.path.display()
+        ));
+    }
+
+    // 1. 收尾旧库
+    if !vault::is_locked(&crate::app::db_path()) && crate::history::is_enabled() {
+        let _ = crate::history::flush();
+    }
+    let old_vault = crate::lock::wait_exclusive().map_err(|e| e.to_string())?;
+    crate::api::stop();
+    crate::crypto::session::lock_all();
+    vault::set_key(None);
+
+    // 2. 切换目录
+    crate::fs::create_dirs(&entry.path).map_err(|e| e.to_string())?;
+    crate::app::switch_paths(entry.path.clone(), Source::Setting).map_err(|e| e.to_string())?;
+    location::save_setting(Some(&entry.path))?;
+    drop(old_vault);
+
+    // 3. 初始化新库
+    if !vault::is_locked(&crate::app::db_path()) {
+        let conn = crate::db::connection::get_connection().map_err(|e| e.to_string())?;
+        crate::db::migrate::run(&conn).map_err(|e| e.to_string())?;
+        crate::api::start_if_enabled(app);
+    }
+
+    let info = entry.info(&entry.path);
+    let _ = app.emit(VAULT_CHANGED_EVENT, info.clone());
+    Ok(info)
+}